@@ -0,0 +1,18 @@
+// ray tracing core shared by the viewer and the tools embedding the tracer:
+// a scene is built with the scene builder or from a scene description, the render takes the scene and the camera
+// and produces a texture that can be read as a buffer or saved to a file
+
+#[macro_use]
+mod macros;
+
+pub mod math;
+pub mod render;
+// state of the interactive viewer, the SDL window itself lives in the binary
+#[cfg(feature = "viewer")]
+pub mod system;
+
+pub use crate::math::Vector3;
+pub use crate::render::{
+  Camera, Color, Material, MaterialHandle, MaterialKind, MaterialPreset, Render, Scene, SceneBuilder, SceneDesc, Texture,
+  TextureCache, TextureHandle,
+};
@@ -1,25 +1,20 @@
+use std::collections::HashMap;
 use std::time::Instant;
 
 use anyhow::{Error, Result};
 use rusttype::Font;
+use sdl2::controller::{Axis, GameController};
 use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
 use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
 use sdl2::render::{TextureAccess, WindowCanvas};
 use sdl2::surface::Surface;
 
-use system::KeyCode;
-use system::App;
-
-use crate::math::constants::VERY_SMALL_NUMBER;
-
-#[macro_use]
-mod macros;
-
-mod math;
-mod render;
-mod system;
+use reflax_man_rs::math::constants::VERY_SMALL_NUMBER;
+use reflax_man_rs::system::{GamepadAxis, KeyCode};
+use reflax_man_rs::system::{App, Settings};
 
 fn main() {
   if let Err(err) = run() {
@@ -32,6 +27,8 @@ fn run() -> Result<()> {
   let mut height: u32 = 480;
   let sdl_context = sdl2::init().unwrap();
   let video_subsystem = sdl_context.video().unwrap();
+  let controller_subsystem = sdl_context.game_controller().map_err(Error::msg)?;
+  let mut controllers: Vec<GameController> = Vec::new();
 
   let window = video_subsystem.window("ReflaxManRs", width, height)
     .position_centered()
@@ -54,6 +51,8 @@ fn run() -> Result<()> {
   app.resize_image(width, height);
   let font_data = include_bytes!("../assets/fonts/arial.ttf");
   let font = Font::try_from_bytes(font_data as &[u8]).expect("Error constructing Font");
+  let mut key_map = build_key_map(app.get_settings())?;
+  let mut settings_version = app.get_settings_version();
   let mut time = Instant::now();
   let mut image_surface = Surface::new(width, height, PixelFormatEnum::RGBA8888).map_err(Error::msg)?;
   let mut final_texture = texture_creator.create_texture(PixelFormatEnum::RGBA8888, TextureAccess::Static, width, height)?;
@@ -62,7 +61,11 @@ fn run() -> Result<()> {
     let mut need_repaint = app.pulse()?;
 
     if need_repaint {
-      image_surface = rebuild_image_surface(&app);
+      image_surface = if app.is_preview() {
+        rebuild_preview_surface(&app, width, height)
+      } else {
+        rebuild_image_surface(&app)
+      };
     }
 
     if need_repaint || time.elapsed().as_millis() > 100 {
@@ -77,16 +80,61 @@ fn run() -> Result<()> {
       canvas.present();
     }
 
+    if settings_version != app.get_settings_version() {
+      settings_version = app.get_settings_version();
+
+      match build_key_map(app.get_settings()) {
+        Ok(new_key_map) => key_map = new_key_map,
+        Err(err) => println!("Key bindings are not updated: {}", err),
+      }
+    }
+
+    if sdl_context.mouse().relative_mouse_mode() != app.is_mouse_look() {
+      sdl_context.mouse().set_relative_mouse_mode(app.is_mouse_look());
+    }
+
+    if video_subsystem.text_input().is_active() != app.is_text_input() {
+      if app.is_text_input() {
+        video_subsystem.text_input().start();
+      } else {
+        video_subsystem.text_input().stop();
+      }
+    }
+
     for event in event_pump.poll_iter() {
       match event {
         Event::Quit { .. } => {
           break 'running;
         }
         Event::KeyDown { keycode: Some(key), .. } => {
-          handle_key_message(&mut app, key, true);
+          handle_key_message(&mut app, &key_map, key, true);
         }
         Event::KeyUp { keycode: Some(key), .. } => {
-          handle_key_message(&mut app, key, false);
+          handle_key_message(&mut app, &key_map, key, false);
+        }
+        Event::TextInput { text, .. } => {
+          app.handle_text_input(&text);
+        }
+        Event::MouseMotion { xrel, yrel, .. } => {
+          app.handle_mouse_motion(xrel, yrel);
+        }
+        Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+          app.handle_mouse_click(x, y);
+        }
+        Event::MouseWheel { y, .. } => {
+          app.handle_mouse_wheel(y);
+        }
+        Event::ControllerDeviceAdded { which, .. } => {
+          if let Ok(controller) = controller_subsystem.open(which) {
+            println!("Game controller connected: {}", controller.name());
+            controllers.push(controller);
+          }
+        }
+        Event::ControllerDeviceRemoved { which, .. } => {
+          controllers.retain(|controller| controller.instance_id() != which);
+        }
+        Event::ControllerAxisMotion { axis, value, .. } => {
+          handle_axis_message(&mut app, axis, value);
         }
         Event::Window { win_event: WindowEvent::Resized(w, h), .. } => {
           println!("Resize: {} x {}", w, h);
@@ -102,32 +150,35 @@ fn run() -> Result<()> {
   Ok(())
 }
 
-fn handle_key_message(app: &mut App, key_code: Keycode, is_down: bool) {
-  match key_code {
-    Keycode::Left => app.handle_key_event(KeyCode::KeyLeft, is_down),
-    Keycode::Right => app.handle_key_event(KeyCode::KeyRight, is_down),
-    Keycode::Up => app.handle_key_event(KeyCode::KeyUp, is_down),
-    Keycode::Down => app.handle_key_event(KeyCode::KeyDown, is_down),
-    Keycode::W => app.handle_key_event(KeyCode::KeyW, is_down),
-    Keycode::S => app.handle_key_event(KeyCode::KeyS, is_down),
-    Keycode::A => app.handle_key_event(KeyCode::KeyA, is_down),
-    Keycode::D => app.handle_key_event(KeyCode::KeyD, is_down),
-    Keycode::Space => app.handle_key_event(KeyCode::KeySpace, is_down),
-    Keycode::LCtrl => app.handle_key_event(KeyCode::KeyControl, is_down),
-    Keycode::F2 => app.handle_key_event(KeyCode::KeyF2, is_down),
-    Keycode::Num1 => app.handle_key_event(KeyCode::Key1, is_down),
-    Keycode::Num2 => app.handle_key_event(KeyCode::Key2, is_down),
-    Keycode::Num3 => app.handle_key_event(KeyCode::Key3, is_down),
-    Keycode::Num4 => app.handle_key_event(KeyCode::Key4, is_down),
-    Keycode::Num5 => app.handle_key_event(KeyCode::Key5, is_down),
-    Keycode::Num6 => app.handle_key_event(KeyCode::Key6, is_down),
-    Keycode::Num7 => app.handle_key_event(KeyCode::Key7, is_down),
-    Keycode::Num8 => app.handle_key_event(KeyCode::Key8, is_down),
-    Keycode::Num9 => app.handle_key_event(KeyCode::Key9, is_down),
-    Keycode::Escape => app.handle_key_event(KeyCode::KeyEscape, is_down),
-    Keycode::Y => app.handle_key_event(KeyCode::KeyY, is_down),
-    Keycode::N => app.handle_key_event(KeyCode::KeyN, is_down),
-    _ => {}
+fn build_key_map(settings: &Settings) -> Result<HashMap<Keycode, KeyCode>> {
+  let mut key_map = HashMap::new();
+
+  for binding in &settings.key_bindings {
+    for name in &binding.names {
+      let key_code = Keycode::from_name(name).ok_or_else(|| Error::msg(format!("Unknown key name \"{}\"", name)))?;
+      key_map.insert(key_code, binding.key);
+    }
+  }
+
+  Ok(key_map)
+}
+
+fn handle_key_message(app: &mut App, key_map: &HashMap<Keycode, KeyCode>, key_code: Keycode, is_down: bool) {
+  if let Some(&key) = key_map.get(&key_code) {
+    app.handle_key_event(key, is_down);
+  }
+}
+
+fn handle_axis_message(app: &mut App, axis: Axis, value: i16) {
+  let value = value as f32 / i16::MAX as f32;
+
+  match axis {
+    Axis::LeftX => app.handle_gamepad_axis(GamepadAxis::LeftX, value),
+    Axis::LeftY => app.handle_gamepad_axis(GamepadAxis::LeftY, value),
+    Axis::RightX => app.handle_gamepad_axis(GamepadAxis::RightX, value),
+    Axis::RightY => app.handle_gamepad_axis(GamepadAxis::RightY, value),
+    Axis::TriggerLeft => app.handle_gamepad_axis(GamepadAxis::TriggerLeft, value),
+    Axis::TriggerRight => app.handle_gamepad_axis(GamepadAxis::TriggerRight, value),
   }
 }
 
@@ -191,6 +242,24 @@ fn rebuild_image_surface<'a, 'b>(app: &'a App) -> Surface<'b> {
   surface
 }
 
+fn rebuild_preview_surface<'a>(app: &App, width: u32, height: u32) -> Surface<'a> {
+  let mut surface = Surface::new(width, height, PixelFormatEnum::RGBA8888).unwrap();
+  let pixel_data = surface.without_lock_mut().unwrap();
+
+  for y in 0..height {
+    for x in 0..width {
+      let rgb = app.get_preview_pixel(x, y, width, height);
+      let idx = (x + y * width) as usize * 4;
+      pixel_data[idx] = 255;
+      pixel_data[idx + 1] = rgb[2];
+      pixel_data[idx + 2] = rgb[1];
+      pixel_data[idx + 3] = rgb[0];
+    }
+  }
+
+  surface
+}
+
 fn render_all_text<'a, 'b>(app: &'a App, background_surface: &Surface, font: &Font, width: u32, height: u32) -> Surface<'b> {
   let mut surface = Surface::new(width, height, PixelFormatEnum::RGBA8888).unwrap();
   let center = surface.rect().center();
@@ -1,7 +1,7 @@
 pub use self::approx_eq::ApproxEq;
 pub use self::clamp::clamp;
 pub use self::matrix33::Matrix33;
-pub use self::rnd::Rnd;
+pub use self::rnd::{hash, Rnd};
 pub use self::vector3::Vector3;
 
 pub mod constants;
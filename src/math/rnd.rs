@@ -1,32 +1,64 @@
-use std::cell::RefCell;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::num::Wrapping;
-use std::time::Instant;
+use std::cell::Cell;
 
 use super::constants::FAST_RAND_MAX;
 
+const PCG_MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+// PCG32 generator, the sequence depends only on the seed and the stream,
+// so every pixel sample can have its own reproducible sequence regardless of the render order
 pub struct Rnd {
-  g_seed: RefCell<i32>,
+  state: Cell<u64>,
+  inc: u64,
 }
 
 impl Rnd {
-  pub fn new() -> Rnd {
-    let mut hasher = DefaultHasher::new();
-    Instant::now().hash(&mut hasher);
-    std::thread::current().id().hash(&mut hasher);
-    std::process::id().hash(&mut hasher);
-    let hash = hasher.finish();
-    let seed = (hash ^ (hash >> 32)) as i32 & FAST_RAND_MAX;
-
-    Rnd { g_seed: RefCell::new(seed) }
+  pub fn new(seed: u64) -> Rnd {
+    Rnd::with_stream(seed, 0)
   }
 
-  pub fn fastrand(&self) -> i32 {
-    let seed = *self.g_seed.borrow();
-    let seed = (Wrapping(214013) * Wrapping(seed) + Wrapping(2531011)).0;
-    *self.g_seed.borrow_mut() = seed;
+  pub fn with_stream(seed: u64, stream: u64) -> Rnd {
+    let rnd = Rnd {
+      state: Cell::new(0),
+      inc: (stream << 1) | 1,
+    };
+
+    rnd.next_u32();
+    rnd.state.set(rnd.state.get().wrapping_add(seed));
+    rnd.next_u32();
+
+    rnd
+  }
+
+  pub fn for_sample(seed: u64, frame: u32, pixel: u32, sample: u32) -> Rnd {
+    let stream = hash(hash(frame as u64) ^ ((pixel as u64) << 32 | sample as u64));
+
+    Rnd::with_stream(hash(seed ^ stream), stream)
+  }
+
+  pub fn next_u32(&self) -> u32 {
+    let state = self.state.get();
+    self.state.set(state.wrapping_mul(PCG_MULTIPLIER).wrapping_add(self.inc));
+    let xor_shifted = (((state >> 18) ^ state) >> 27) as u32;
+    let rot = (state >> 59) as u32;
 
-    (seed >> 16) & FAST_RAND_MAX
+    xor_shifted.rotate_right(rot)
   }
-}
\ No newline at end of file
+
+  // uniform in range [0.0, 1.0)
+  pub fn next_f32(&self) -> f32 {
+    (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+  }
+
+  pub fn fastrand(&self) -> i32 {
+    (self.next_u32() >> 17) as i32 & FAST_RAND_MAX
+  }
+}
+
+// SplitMix64 finalizer, spreads close indices to unrelated values
+pub fn hash(value: u64) -> u64 {
+  let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+  z ^ (z >> 31)
+}
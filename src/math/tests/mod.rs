@@ -1,5 +1,6 @@
 mod vector3;
 mod matrix33;
 mod clamp;
+mod rnd;
 
 use super::*;
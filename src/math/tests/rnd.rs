@@ -0,0 +1,41 @@
+use super::Rnd;
+
+#[test]
+fn same_seed_same_sequence() {
+  let rnd1 = Rnd::new(7);
+  let rnd2 = Rnd::new(7);
+
+  for _ in 0..100 {
+    assert_eq!(rnd1.next_u32(), rnd2.next_u32());
+  }
+}
+
+#[test]
+fn different_seeds() {
+  let rnd1 = Rnd::new(7);
+  let rnd2 = Rnd::new(8);
+  let same_count = (0..100).filter(|_| rnd1.next_u32() == rnd2.next_u32()).count();
+  assert!(same_count < 2);
+}
+
+#[test]
+fn sample_streams() {
+  let first = Rnd::for_sample(1, 0, 10, 0).next_u32();
+  assert_eq!(Rnd::for_sample(1, 0, 10, 0).next_u32(), first);
+  assert_ne!(Rnd::for_sample(1, 0, 10, 1).next_u32(), first);
+  assert_ne!(Rnd::for_sample(1, 0, 11, 0).next_u32(), first);
+  assert_ne!(Rnd::for_sample(1, 1, 10, 0).next_u32(), first);
+  assert_ne!(Rnd::for_sample(2, 0, 10, 0).next_u32(), first);
+}
+
+#[test]
+fn ranges() {
+  let rnd = Rnd::new(3);
+
+  for _ in 0..10000 {
+    let f = rnd.next_f32();
+    assert!((0.0..1.0).contains(&f));
+    let i = rnd.fastrand();
+    assert!((0..=0x7FFF).contains(&i));
+  }
+}
@@ -1,4 +1,4 @@
-use super::Vector3;
+use super::{Rnd, Vector3};
 
 #[test]
 fn length() {
@@ -91,15 +91,42 @@ fn neg() {
 
 #[test]
 fn random() {
+  let rnd = Rnd::new(1);
+
   for radius in 1..1000 {
-    let v = Vector3::random_inside_sphere(radius as f32);
+    let v = Vector3::random_inside_sphere(radius as f32, &rnd);
     assert!(v.length() <= radius as f32, "length > radius\n vector: {}\n length: {}\n radius: {}", v, v.length(), radius);
     assert_ne!(v.length(), 0.0, "length == 0\n vector: {}\n radius: {}", v, radius);
   }
 
   for radius in 1..1000 {
-    let v1 = Vector3::random_inside_sphere(radius as f32);
-    let v2 = Vector3::random_inside_sphere(radius as f32);
+    let v1 = Vector3::random_inside_sphere(radius as f32, &rnd);
+    let v2 = Vector3::random_inside_sphere(radius as f32, &rnd);
     assert_ne!(v1, v2);
   }
 }
+
+#[test]
+fn random_reproducible() {
+  let rnd1 = Rnd::new(42);
+  let rnd2 = Rnd::new(42);
+
+  for radius in 1..100 {
+    assert_eq!(Vector3::random_inside_sphere(radius as f32, &rnd1), Vector3::random_inside_sphere(radius as f32, &rnd2));
+  }
+}
+
+#[test]
+fn inside_sphere() {
+  let rnd = Rnd::new(1);
+
+  for radius in 1..1000 {
+    let u = [rnd.next_f32(), rnd.next_f32(), rnd.next_f32()];
+    let v = Vector3::inside_sphere(radius as f32, u);
+    assert!(v.length() <= radius as f32 * 1.0001, "length > radius\n vector: {}\n length: {}\n radius: {}", v, v.length(), radius);
+  }
+
+  assert_eq!(Vector3::inside_sphere(2.0, [0.0, 0.0, 1.0]), Vector3::new(0.0, 0.0, 2.0));
+  assert_eq!(Vector3::inside_sphere(2.0, [0.5, 0.0, 0.0]).length(), 0.0);
+  assert_ne!(Vector3::inside_sphere(1.0, [0.1, 0.2, 0.3]), Vector3::inside_sphere(1.0, [0.1, 0.2, 0.4]));
+}
@@ -2,7 +2,6 @@ use std::cmp::PartialEq;
 use std::fmt;
 use std::iter::{FromIterator, IntoIterator};
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign};
-use std::rc::Rc;
 
 use super::ApproxEq;
 use super::constants::{FAST_RAND_MAX, VERY_SMALL_NUMBER};
@@ -15,18 +14,12 @@ pub struct Vector3 {
   pub z: f32,
 }
 
-thread_local! {
-  pub static RND: Rc<Rnd> = Rc::new(Rnd::new());
-}
-
 impl Vector3 {
   pub fn new(x: f32, y: f32, z: f32) -> Vector3 {
     Vector3 { x, y, z }
   }
 
-  pub fn random_inside_sphere(radius: f32) -> Vector3 {
-    let rnd = RND.with(|r| Rc::clone(r));
-
+  pub fn random_inside_sphere(radius: f32, rnd: &Rnd) -> Vector3 {
     loop {
       let vec = Vector3::new(
         rnd.fastrand() as f32 / (FAST_RAND_MAX as f32 / 2.0) - 1.0,
@@ -40,6 +33,16 @@ impl Vector3 {
     }
   }
 
+  // maps a uniform sample from the unit cube to a point uniformly distributed inside the sphere
+  pub fn inside_sphere(radius: f32, u: [f32; 3]) -> Vector3 {
+    let z = 1.0 - 2.0 * u[0];
+    let r = f32::sqrt(f32::max(0.0, 1.0 - z * z));
+    let phi = 2.0 * std::f32::consts::PI * u[1];
+    let length = radius * u[2].cbrt();
+
+    Vector3::new(r * phi.cos() * length, r * phi.sin() * length, z * length)
+  }
+
   pub fn length(&self) -> f32 {
     (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
   }
@@ -0,0 +1,28 @@
+use super::Color;
+use super::pixel::Pixel;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSampling {
+  // relative standard error of the pixel luminance where sampling of the pixel stops
+  pub threshold: f32,
+  pub min_samples: u32,
+  pub max_samples: u32,
+}
+
+impl AdaptiveSampling {
+  pub fn is_converged(&self, pixel: &Pixel) -> bool {
+    pixel.samples >= self.max_samples ||
+      (pixel.samples >= self.min_samples && pixel.get_error() <= self.threshold)
+  }
+}
+
+// blue for few samples through green and yellow to red for many
+pub fn heatmap_color(value: f32) -> Color {
+  let t = value.clamp(0.0, 1.0) * 3.0;
+
+  match t {
+    t if t < 1.0 => Color::new(0.0, t, 1.0 - t),
+    t if t < 2.0 => Color::new(t - 1.0, 1.0, 0.0),
+    t => Color::new(1.0, 3.0 - t, 0.0),
+  }
+}
@@ -0,0 +1,138 @@
+use super::{Color, FirstHit, SKY_DEPTH};
+use super::math::{hash, Vector3};
+
+// arbitrary output variables, per pixel properties of the first surface seen through the pixel,
+// there is no motion pass: the render keeps no camera of the previous frame to project the surfaces with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AovKind {
+  Depth,
+  Normal,
+  Position,
+  Albedo,
+  ObjectId,
+  MaterialId,
+  Direct,
+  Indirect,
+  Shadow,
+}
+
+pub const AOV_KINDS: [AovKind; 9] = [
+  AovKind::Depth,
+  AovKind::Normal,
+  AovKind::Position,
+  AovKind::Albedo,
+  AovKind::ObjectId,
+  AovKind::MaterialId,
+  AovKind::Direct,
+  AovKind::Indirect,
+  AovKind::Shadow,
+];
+
+impl AovKind {
+  pub fn from_name(name: &str) -> Option<AovKind> {
+    AOV_KINDS.iter().copied().find(|kind| kind.name() == name)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      AovKind::Depth => "depth",
+      AovKind::Normal => "normal",
+      AovKind::Position => "position",
+      AovKind::Albedo => "albedo",
+      AovKind::ObjectId => "object_id",
+      AovKind::MaterialId => "material_id",
+      AovKind::Direct => "direct",
+      AovKind::Indirect => "indirect",
+      AovKind::Shadow => "shadow",
+    }
+  }
+}
+
+// AOV values of the pixel that are not kept by the image pixel itself,
+// IDs can't be averaged, so the pixel takes the IDs of its first sample
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AovPixel {
+  pub position_sum: Vector3,
+  pub direct_sum: Color,
+  pub indirect_sum: Color,
+  pub shadow_sum: f32,
+  pub object_id: u32,
+  pub material_id: u32,
+  pub samples: u32,
+}
+
+impl AovPixel {
+  pub fn add_sample(&mut self, color: &Color, first_hit: &FirstHit) {
+    if self.samples == 0 {
+      self.object_id = first_hit.object_id;
+      self.material_id = first_hit.material_id;
+    }
+
+    let mut indirect = color - &first_hit.direct;
+    indirect.r = f32::max(indirect.r, 0.0);
+    indirect.g = f32::max(indirect.g, 0.0);
+    indirect.b = f32::max(indirect.b, 0.0);
+
+    self.position_sum += &first_hit.position;
+    self.direct_sum += &first_hit.direct;
+    self.indirect_sum += indirect;
+    self.shadow_sum += first_hit.shadow;
+    self.samples += 1;
+  }
+
+  pub fn get_position(&self) -> Vector3 {
+    &self.position_sum / u32::max(self.samples, 1) as f32
+  }
+
+  pub fn get_direct(&self) -> Color {
+    &self.direct_sum / u32::max(self.samples, 1) as f32
+  }
+
+  pub fn get_indirect(&self) -> Color {
+    &self.indirect_sum / u32::max(self.samples, 1) as f32
+  }
+
+  pub fn get_shadow(&self) -> f32 {
+    self.shadow_sum / u32::max(self.samples, 1) as f32
+  }
+}
+
+// depth along the forward axis of the camera is shown from black at the camera to white at the farthest surface
+// and the sky
+pub fn depth_color(depth: f32, max_depth: f32) -> Color {
+  let value = if depth >= SKY_DEPTH || max_depth <= 0.0 {
+    1.0
+  } else {
+    f32::min(depth / max_depth, 1.0)
+  };
+
+  Color::new(value, value, value)
+}
+
+// normal components are mapped from [-1, 1] to [0, 1]
+pub fn normal_color(normal: &Vector3) -> Color {
+  Color::new(normal.x * 0.5 + 0.5, normal.y * 0.5 + 0.5, normal.z * 0.5 + 0.5)
+}
+
+// position is mapped into the box bounding all the visible surfaces
+pub fn position_color(position: &Vector3, min: &Vector3, max: &Vector3) -> Color {
+  let scale = |value: f32, min: f32, max: f32| if max > min { (value - min) / (max - min) } else { 0.0 };
+
+  Color::new(
+    scale(position.x, min.x, max.x),
+    scale(position.y, min.y, max.y),
+    scale(position.z, min.z, max.z),
+  )
+}
+
+// every ID gets its own stable bright color, zero ID (the sky) is black
+pub fn id_color(id: u32) -> Color {
+  if id == 0 {
+    return Color::default();
+  }
+
+  let bits = hash(id as u64);
+  let channel = |shift: u32| 0.25 + 0.75 * ((bits >> shift) & 0xFF) as f32 / 255.0;
+
+  Color::new(channel(0), channel(8), channel(16))
+}
@@ -7,11 +7,14 @@ const MUL_PI_2: f32 = 2.0 * PI;
 
 const TURN_ACCELERATION: f32 = 2.0;
 const TURN_DECELERATION: f32 = 2.0;
-const MAX_TURN_SPEED: f32 = 0.2;
+pub const DEFAULT_MAX_TURN_SPEED: f32 = 0.2;
 
 const SHIFT_ACCELERATION: f32 = 50.0;
 const SHIFT_DECELERATION: f32 = 50.0;
-const MAX_SHIFT_SPEED: f32 = 10.0;
+pub const DEFAULT_MAX_SHIFT_SPEED: f32 = 10.0;
+
+const MIN_FOV: f32 = 0.1;
+const MAX_FOV: f32 = 2.5;
 
 pub const TURN_LEFT_MASK: u32 = 1 << 0;
 pub const TURN_RIGHT_MASK: u32 = 1 << 1;
@@ -24,6 +27,26 @@ pub const SHIFT_DOWN_MASK: u32 = 1 << 9;
 pub const SHIFT_FORWARD_MASK: u32 = 1 << 10;
 pub const SHIFT_BACK_MASK: u32 = 1 << 11;
 
+// analog control rates in range [-1.0, 1.0], the zero value means no analog input for the axis
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AnalogControl {
+  pub turn_rl: f32,
+  pub turn_ud: f32,
+  pub shift_rl: f32,
+  pub shift_ud: f32,
+  pub shift_fb: f32,
+}
+
+impl AnalogControl {
+  pub fn is_active(&self) -> bool {
+    self.turn_rl != 0.0 ||
+      self.turn_ud != 0.0 ||
+      self.shift_rl != 0.0 ||
+      self.shift_ud != 0.0 ||
+      self.shift_fb != 0.0
+  }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Camera {
   pub turn_rl_speed: f32,
@@ -31,6 +54,8 @@ pub struct Camera {
   pub shift_rl_speed: f32,
   pub shift_ud_speed: f32,
   pub shift_fb_speed: f32,
+  pub max_turn_speed: f32,
+  pub max_shift_speed: f32,
 
   pub yaw: f32,
   pub pitch: f32,
@@ -68,26 +93,32 @@ impl Camera {
       shift_rl_speed: 0.0,
       shift_ud_speed: 0.0,
       shift_fb_speed: 0.0,
+      max_turn_speed: DEFAULT_MAX_TURN_SPEED,
+      max_shift_speed: DEFAULT_MAX_SHIFT_SPEED,
     }
   }
 
   // TODO: move control to render and make private
-  pub fn proceed_control(&mut self, control_flags: u32, time_passed_sec: f32) {
+  pub fn proceed_control(&mut self, control_flags: u32, analog: &AnalogControl, time_passed_sec: f32) {
     let prev_turn_rl_speed = self.turn_rl_speed;
     let prev_turn_ud_speed = self.turn_ud_speed;
     let prev_shift_rl_speed = self.shift_rl_speed;
     let prev_shift_ud_speed = self.shift_ud_speed;
     let prev_shift_fb_speed = self.shift_fb_speed;
 
+    // analog input sets the speed directly and overrides the digital control of the same axis
     match control_flags & (TURN_LEFT_MASK | TURN_RIGHT_MASK)
     {
+      _ if analog.turn_rl != 0.0 => {
+        self.turn_rl_speed = clamp(analog.turn_rl, -1.0, 1.0) * self.max_turn_speed;
+      }
       TURN_RIGHT_MASK => {
         let yaw_speed = self.turn_rl_speed + TURN_ACCELERATION * time_passed_sec;
-        self.turn_rl_speed = clamp(yaw_speed, -MAX_TURN_SPEED, MAX_TURN_SPEED);
+        self.turn_rl_speed = clamp(yaw_speed, -self.max_turn_speed, self.max_turn_speed);
       }
       TURN_LEFT_MASK => {
         let yaw_speed = self.turn_rl_speed - TURN_ACCELERATION * time_passed_sec;
-        self.turn_rl_speed = clamp(yaw_speed, -MAX_TURN_SPEED, MAX_TURN_SPEED);
+        self.turn_rl_speed = clamp(yaw_speed, -self.max_turn_speed, self.max_turn_speed);
       }
       _ => {
         if self.turn_rl_speed < 0.0 {
@@ -100,13 +131,16 @@ impl Camera {
 
     match control_flags & (TURN_UP_MASK | TURN_DOWN_MASK)
     {
+      _ if analog.turn_ud != 0.0 => {
+        self.turn_ud_speed = clamp(analog.turn_ud, -1.0, 1.0) * self.max_turn_speed;
+      }
       TURN_UP_MASK => {
         let pitch_speed = self.turn_ud_speed + TURN_ACCELERATION * time_passed_sec;
-        self.turn_ud_speed = clamp(pitch_speed, -MAX_TURN_SPEED, MAX_TURN_SPEED);
+        self.turn_ud_speed = clamp(pitch_speed, -self.max_turn_speed, self.max_turn_speed);
       }
       TURN_DOWN_MASK => {
         let pitch_speed = self.turn_ud_speed - TURN_ACCELERATION * time_passed_sec;
-        self.turn_ud_speed = clamp(pitch_speed, -MAX_TURN_SPEED, MAX_TURN_SPEED);
+        self.turn_ud_speed = clamp(pitch_speed, -self.max_turn_speed, self.max_turn_speed);
       }
       _ => {
         if self.turn_ud_speed < 0.0 {
@@ -119,21 +153,24 @@ impl Camera {
 
     match control_flags & (SHIFT_LEFT_MASK | SHIFT_RIGHT_MASK)
     {
+      _ if analog.shift_rl != 0.0 => {
+        self.shift_rl_speed = clamp(analog.shift_rl, -1.0, 1.0) * self.max_shift_speed;
+      }
       SHIFT_RIGHT_MASK => {
         if self.shift_rl_speed < 0.0 {
           let shift_rl_speed = self.shift_rl_speed + (SHIFT_DECELERATION + SHIFT_ACCELERATION) * time_passed_sec;
-          self.shift_rl_speed = clamp(shift_rl_speed, -MAX_SHIFT_SPEED, MAX_SHIFT_SPEED);
+          self.shift_rl_speed = clamp(shift_rl_speed, -self.max_shift_speed, self.max_shift_speed);
         } else {
           let shift_rl_speed = self.shift_rl_speed + SHIFT_ACCELERATION * time_passed_sec;
-          self.shift_rl_speed = clamp(shift_rl_speed, -MAX_SHIFT_SPEED, MAX_SHIFT_SPEED);
+          self.shift_rl_speed = clamp(shift_rl_speed, -self.max_shift_speed, self.max_shift_speed);
         }
       }
 
       SHIFT_LEFT_MASK => {
         if self.shift_rl_speed > 0.0 {
-          self.shift_rl_speed = clamp(self.shift_rl_speed - (SHIFT_DECELERATION + SHIFT_ACCELERATION) * time_passed_sec, -MAX_SHIFT_SPEED, MAX_SHIFT_SPEED);
+          self.shift_rl_speed = clamp(self.shift_rl_speed - (SHIFT_DECELERATION + SHIFT_ACCELERATION) * time_passed_sec, -self.max_shift_speed, self.max_shift_speed);
         } else {
-          self.shift_rl_speed = clamp(self.shift_rl_speed - SHIFT_ACCELERATION * time_passed_sec, -MAX_SHIFT_SPEED, MAX_SHIFT_SPEED);
+          self.shift_rl_speed = clamp(self.shift_rl_speed - SHIFT_ACCELERATION * time_passed_sec, -self.max_shift_speed, self.max_shift_speed);
         }
       }
       _ => {
@@ -147,11 +184,14 @@ impl Camera {
 
     match control_flags & (SHIFT_UP_MASK | SHIFT_DOWN_MASK)
     {
+      _ if analog.shift_ud != 0.0 => {
+        self.shift_ud_speed = clamp(analog.shift_ud, -1.0, 1.0) * self.max_shift_speed;
+      }
       SHIFT_UP_MASK => {
-        self.shift_ud_speed = clamp(self.shift_ud_speed + SHIFT_ACCELERATION * time_passed_sec, -MAX_SHIFT_SPEED, MAX_SHIFT_SPEED);
+        self.shift_ud_speed = clamp(self.shift_ud_speed + SHIFT_ACCELERATION * time_passed_sec, -self.max_shift_speed, self.max_shift_speed);
       }
       SHIFT_DOWN_MASK => {
-        self.shift_ud_speed = clamp(self.shift_ud_speed - SHIFT_ACCELERATION * time_passed_sec, -MAX_SHIFT_SPEED, MAX_SHIFT_SPEED);
+        self.shift_ud_speed = clamp(self.shift_ud_speed - SHIFT_ACCELERATION * time_passed_sec, -self.max_shift_speed, self.max_shift_speed);
       }
       _ => {
         if self.shift_ud_speed < 0.0 {
@@ -164,11 +204,14 @@ impl Camera {
 
     match control_flags & (SHIFT_BACK_MASK | SHIFT_FORWARD_MASK)
     {
+      _ if analog.shift_fb != 0.0 => {
+        self.shift_fb_speed = clamp(analog.shift_fb, -1.0, 1.0) * self.max_shift_speed;
+      }
       SHIFT_FORWARD_MASK => {
-        self.shift_fb_speed = clamp(self.shift_fb_speed + SHIFT_ACCELERATION * time_passed_sec, -MAX_SHIFT_SPEED, MAX_SHIFT_SPEED);
+        self.shift_fb_speed = clamp(self.shift_fb_speed + SHIFT_ACCELERATION * time_passed_sec, -self.max_shift_speed, self.max_shift_speed);
       }
       SHIFT_BACK_MASK => {
-        self.shift_fb_speed = clamp(self.shift_fb_speed - SHIFT_ACCELERATION * time_passed_sec, -MAX_SHIFT_SPEED, MAX_SHIFT_SPEED);
+        self.shift_fb_speed = clamp(self.shift_fb_speed - SHIFT_ACCELERATION * time_passed_sec, -self.max_shift_speed, self.max_shift_speed);
       }
       _ => {
         if self.shift_fb_speed < 0.0 {
@@ -179,16 +222,9 @@ impl Camera {
       }
     }
 
-    self.yaw += time_passed_sec * MUL_PI_2 * (self.turn_rl_speed + prev_turn_rl_speed) / 2.0;
-    self.pitch = clamp(self.pitch + time_passed_sec * MUL_PI_2 * (self.turn_ud_speed + prev_turn_ud_speed) / 2.0, -0.95 * FRAC_PI_2, 0.95 * FRAC_PI_2);
-
-    if self.yaw >= MUL_PI_2 {
-      self.yaw -= MUL_PI_2;
-    } else if self.yaw <= -MUL_PI_2 {
-      self.yaw += MUL_PI_2;
-    }
-
-    self.view = Matrix33::from_yaw_pitch(self.yaw, self.pitch);
+    let yaw_delta = time_passed_sec * MUL_PI_2 * (self.turn_rl_speed + prev_turn_rl_speed) / 2.0;
+    let pitch_delta = time_passed_sec * MUL_PI_2 * (self.turn_ud_speed + prev_turn_ud_speed) / 2.0;
+    self.turn(yaw_delta, pitch_delta);
 
     if self.shift_rl_speed.abs() > f32::EPSILON ||
       self.shift_ud_speed.abs() > f32::EPSILON ||
@@ -209,14 +245,32 @@ impl Camera {
 
       let shift_sq_length = shift.sq_length();
 
-      if shift_sq_length > MAX_SHIFT_SPEED * MAX_SHIFT_SPEED {
-        shift *= MAX_SHIFT_SPEED / shift_sq_length.sqrt();
+      if shift_sq_length > self.max_shift_speed * self.max_shift_speed {
+        shift *= self.max_shift_speed / shift_sq_length.sqrt();
       }
 
       self.eye += shift * time_passed_sec;
     }
   }
 
+  // immediate rotation used by mouse look, bypasses the turn speed acceleration
+  pub fn turn(&mut self, yaw_delta: f32, pitch_delta: f32) {
+    self.yaw += yaw_delta;
+    self.pitch = clamp(self.pitch + pitch_delta, -0.95 * FRAC_PI_2, 0.95 * FRAC_PI_2);
+
+    if self.yaw >= MUL_PI_2 {
+      self.yaw -= MUL_PI_2;
+    } else if self.yaw <= -MUL_PI_2 {
+      self.yaw += MUL_PI_2;
+    }
+
+    self.view = Matrix33::from_yaw_pitch(self.yaw, self.pitch);
+  }
+
+  pub fn zoom(&mut self, factor: f32) {
+    self.fov = clamp(self.fov * factor, MIN_FOV, MAX_FOV);
+  }
+
   pub fn is_in_motion(&self) -> bool {
     return self.turn_rl_speed.abs() > f32::EPSILON ||
       self.turn_ud_speed.abs() > f32::EPSILON ||
@@ -40,6 +40,11 @@ impl Color {
     self.b = clamp(self.b, 0.0, 1.0);
   }
 
+  // relative luminance with Rec. 709 weights
+  pub fn luminance(&self) -> f32 {
+    0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+  }
+
   pub fn rgb(&self) -> [u8; 3] {
     [
       (self.r * 255.999) as u8,
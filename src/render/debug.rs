@@ -0,0 +1,75 @@
+use super::{Color, MaterialKind};
+
+// distance where the debug depth reaches the middle gray
+const DEPTH_SCALE: f32 = 10.0;
+
+// debug shading produced by the scene instead of the full lighting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugMode {
+  Normal,
+  Uv,
+  Barycentric,
+  Depth,
+  MaterialKind,
+  Bounces,
+  // ray-object intersection tests spent on the path
+  Cost,
+  // visibility of the light sources from the first surface
+  Occlusion,
+}
+
+pub const DEBUG_MODES: [DebugMode; 8] = [
+  DebugMode::Normal,
+  DebugMode::Uv,
+  DebugMode::Barycentric,
+  DebugMode::Depth,
+  DebugMode::MaterialKind,
+  DebugMode::Bounces,
+  DebugMode::Cost,
+  DebugMode::Occlusion,
+];
+
+impl DebugMode {
+  pub fn from_name(name: &str) -> Option<DebugMode> {
+    DEBUG_MODES.iter().copied().find(|mode| mode.name() == name)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      DebugMode::Normal => "normal",
+      DebugMode::Uv => "uv",
+      DebugMode::Barycentric => "barycentric",
+      DebugMode::Depth => "depth",
+      DebugMode::MaterialKind => "material",
+      DebugMode::Bounces => "bounces",
+      DebugMode::Cost => "cost",
+      DebugMode::Occlusion => "occlusion",
+    }
+  }
+
+  // cycles through all the modes and the full lighting (None)
+  pub fn next(mode: Option<DebugMode>) -> Option<DebugMode> {
+    match mode {
+      None => Some(DEBUG_MODES[0]),
+      Some(mode) => {
+        let index = DEBUG_MODES.iter().position(|&known| known == mode).unwrap();
+        DEBUG_MODES.get(index + 1).copied()
+      }
+    }
+  }
+}
+
+// the camera is black, the far surfaces fade to white
+pub fn debug_depth_color(depth: f32) -> Color {
+  let value = 1.0 - f32::exp(-depth * std::f32::consts::LN_2 / DEPTH_SCALE);
+  Color::new(value, value, value)
+}
+
+pub fn material_kind_color(kind: &MaterialKind) -> Color {
+  match kind {
+    MaterialKind::None => Color::new(0.5, 0.5, 0.5),
+    MaterialKind::Metal => Color::new(1.0, 0.8, 0.3),
+    MaterialKind::Dielectric => Color::new(0.3, 0.6, 1.0),
+    MaterialKind::Subsurface => Color::new(1.0, 0.5, 0.6),
+  }
+}
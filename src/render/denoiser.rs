@@ -0,0 +1,108 @@
+use super::Color;
+use super::math::Vector3;
+
+// B3 spline kernel of the a-trous wavelet transform
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+// auxiliary buffers of the first hit, averaged over the pixel samples
+pub struct Guide<'a> {
+  pub albedo: &'a [Color],
+  pub normal: &'a [Vector3],
+  pub depth: &'a [f32],
+}
+
+// edge-avoiding a-trous wavelet filter (Dammertz et al.), every iteration doubles the kernel step,
+// the edges are kept where color, albedo, normal or depth of the neighbour pixels differ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Denoiser {
+  pub iterations: u32,
+  pub color_sigma: f32,
+  pub albedo_sigma: f32,
+  pub normal_sigma: f32,
+  pub depth_sigma: f32,
+}
+
+impl Default for Denoiser {
+  fn default() -> Denoiser {
+    Denoiser {
+      iterations: 5,
+      color_sigma: 0.5,
+      albedo_sigma: 0.1,
+      normal_sigma: 0.3,
+      depth_sigma: 0.05,
+    }
+  }
+}
+
+impl Denoiser {
+  pub fn denoise(&self, width: u32, height: u32, color: &[Color], guide: &Guide) -> Vec<Color> {
+    let size = (width * height) as usize;
+    assert!(color.len() >= size && guide.albedo.len() >= size && guide.normal.len() >= size && guide.depth.len() >= size);
+
+    let mut input = color[..size].to_vec();
+    let mut output = vec![Color::default(); size];
+    let mut color_sigma = self.color_sigma;
+
+    for iteration in 0..self.iterations {
+      let step = 1i64 << iteration;
+
+      for y in 0..height as i64 {
+        for x in 0..width as i64 {
+          let idx = (x + y * width as i64) as usize;
+          let mut sum = Color::default();
+          let mut weight_sum = 0.0;
+
+          for (ky, &hy) in KERNEL.iter().enumerate() {
+            let qy = y + (ky as i64 - 2) * step;
+
+            if qy < 0 || qy >= height as i64 {
+              continue;
+            }
+
+            for (kx, &hx) in KERNEL.iter().enumerate() {
+              let qx = x + (kx as i64 - 2) * step;
+
+              if qx < 0 || qx >= width as i64 {
+                continue;
+              }
+
+              let q = (qx + qy * width as i64) as usize;
+              let weight = hx * hy * self.edge_weight(idx, q, &input, guide, color_sigma);
+              sum += &input[q] * weight;
+              weight_sum += weight;
+            }
+          }
+
+          // the pixel itself always has a positive weight
+          output[idx] = sum / weight_sum;
+        }
+      }
+
+      std::mem::swap(&mut input, &mut output);
+      // finer details are left after the first iterations, so the color stopping gets stricter
+      color_sigma /= 2.0;
+    }
+
+    input
+  }
+
+  fn edge_weight(&self, p: usize, q: usize, color: &[Color], guide: &Guide, color_sigma: f32) -> f32 {
+    let color_dist = sq_distance(&color[p], &color[q]);
+    let albedo_dist = sq_distance(&guide.albedo[p], &guide.albedo[q]);
+    let normal_dist = (&guide.normal[p] - &guide.normal[q]).sq_length();
+    let (depth_p, depth_q) = (guide.depth[p], guide.depth[q]);
+    let depth_dist = (depth_p - depth_q).abs() / f32::max(f32::max(depth_p, depth_q), 1e-6);
+
+    f32::exp(
+      -color_dist / (color_sigma * color_sigma) -
+        albedo_dist / (self.albedo_sigma * self.albedo_sigma) -
+        normal_dist / (self.normal_sigma * self.normal_sigma) -
+        depth_dist * depth_dist / (self.depth_sigma * self.depth_sigma)
+    )
+  }
+}
+
+fn sq_distance(a: &Color, b: &Color) -> f32 {
+  let d = a - b;
+  d.r * d.r + d.g * d.g + d.b * d.b
+}
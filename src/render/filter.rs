@@ -0,0 +1,106 @@
+use std::f32::consts::PI;
+
+pub const MAX_FILTER_RADIUS: f32 = 4.0;
+
+const GAUSSIAN_ALPHA: f32 = 2.0;
+const MITCHELL_B: f32 = 1.0 / 3.0;
+const MITCHELL_C: f32 = 1.0 / 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterKind {
+  Box,
+  Tent,
+  Gaussian,
+  Mitchell,
+  Lanczos,
+}
+
+pub const FILTER_KINDS: [FilterKind; 5] = [
+  FilterKind::Box,
+  FilterKind::Tent,
+  FilterKind::Gaussian,
+  FilterKind::Mitchell,
+  FilterKind::Lanczos,
+];
+
+impl FilterKind {
+  pub fn from_name(name: &str) -> Option<FilterKind> {
+    FILTER_KINDS.iter().copied().find(|kind| kind.name() == name)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      FilterKind::Box => "box",
+      FilterKind::Tent => "tent",
+      FilterKind::Gaussian => "gaussian",
+      FilterKind::Mitchell => "mitchell",
+      FilterKind::Lanczos => "lanczos",
+    }
+  }
+}
+
+// separable pixel reconstruction filter, the radius is given in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Filter {
+  pub kind: FilterKind,
+  pub radius: f32,
+}
+
+impl Default for Filter {
+  // the box of the pixel size keeps every sample inside its own pixel
+  fn default() -> Filter {
+    Filter { kind: FilterKind::Box, radius: 0.5 }
+  }
+}
+
+impl Filter {
+  pub fn new(kind: FilterKind, radius: f32) -> Filter {
+    assert!(radius > 0.0 && radius <= MAX_FILTER_RADIUS, "Invalid argument");
+    Filter { kind, radius }
+  }
+
+  // weight of the sample at the given offset from the pixel center, Mitchell and Lanczos have negative lobes
+  pub fn weight(&self, dx: f32, dy: f32) -> f32 {
+    self.weight_1d(dx) * self.weight_1d(dy)
+  }
+
+  fn weight_1d(&self, x: f32) -> f32 {
+    let x = x.abs();
+    let r = self.radius;
+
+    if x > r {
+      return 0.0;
+    }
+
+    match self.kind {
+      FilterKind::Box => 1.0,
+      FilterKind::Tent => r - x,
+      FilterKind::Gaussian => f32::max(0.0, f32::exp(-GAUSSIAN_ALPHA * x * x) - f32::exp(-GAUSSIAN_ALPHA * r * r)),
+      FilterKind::Mitchell => mitchell(2.0 * x / r),
+      FilterKind::Lanczos => sinc(x) * sinc(x / r),
+    }
+  }
+}
+
+// Mitchell-Netravali cubic with the support [-2, 2]
+fn mitchell(x: f32) -> f32 {
+  let (b, c) = (MITCHELL_B, MITCHELL_C);
+
+  let value = if x < 1.0 {
+    (12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x + (6.0 - 2.0 * b)
+  } else if x < 2.0 {
+    (-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c)
+  } else {
+    0.0
+  };
+
+  value / 6.0
+}
+
+fn sinc(x: f32) -> f32 {
+  if x < 1e-5 {
+    1.0
+  } else {
+    f32::sin(PI * x) / (PI * x)
+  }
+}
@@ -0,0 +1,235 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::{Context, Error, Result};
+
+use super::{Color, Texture, TextureSource, MAX_IMAGE_SIZE};
+use super::math::clamp;
+use super::math::Vector3;
+
+// the larger image in the header means the file is corrupted, the sides are limited by the render image size
+// and the pixels by the equirectangular sky of that width
+const MAX_PIXEL_COUNT: usize = MAX_IMAGE_SIZE as usize * MAX_IMAGE_SIZE as usize / 2;
+
+// image with the float colors, keeps the sky brighter than the white of the ordinary textures,
+// rows go from the bottom to the top like the texture rows
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HdrImage {
+  pub width: u32,
+  pub height: u32,
+  pub pixels: Vec<Color>,
+}
+
+impl HdrImage {
+  // Radiance .hdr files are read as they are, the other images are converted from the texture colors
+  pub fn load_from_file(path: &Path) -> Result<HdrImage> {
+    let extension = path
+      .extension().ok_or(Error::msg("File has no extension"))?
+      .to_str().ok_or(Error::msg("Invalid file extension"))?;
+
+    match extension {
+      "hdr" => {
+        let file = File::open(path)?;
+        let stream = BufReader::new(file);
+
+        HdrImage::from_hdr(stream)
+      }
+      _ => Ok(HdrImage::from(&Texture::load_from_file(path)?)),
+    }
+  }
+
+  // RGBE pixels, flat or with the run length encoded scanlines
+  pub(in super) fn from_hdr(mut stream: impl BufRead) -> Result<HdrImage> {
+    let mut line = String::new();
+    stream.read_line(&mut line)?;
+
+    if !line.starts_with("#?") {
+      return Err(Error::msg("Not a Radiance HDR image"));
+    }
+
+    // header variables end with an empty line
+    loop {
+      line.clear();
+
+      if stream.read_line(&mut line)? == 0 {
+        return Err(Error::msg("Image size is missing"));
+      }
+
+      let line = line.trim();
+
+      if line.is_empty() {
+        break;
+      }
+
+      if let Some(format) = line.strip_prefix("FORMAT=") {
+        if format != "32-bit_rle_rgbe" {
+          return Err(Error::msg("Only RGBE images are supported"));
+        }
+      }
+    }
+
+    line.clear();
+    stream.read_line(&mut line)?;
+
+    let (is_top_down, width, height) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+      ["-Y", height, "+X", width] => (true, width, height),
+      ["+Y", height, "+X", width] => (false, width, height),
+      _ => return Err(Error::msg("Image orientation is not supported")),
+    };
+
+    let parse_size = |value: &str| value.parse::<u32>().map_err(|_| Error::msg("Invalid image size"));
+    let (width, height) = (parse_size(width)?, parse_size(height)?);
+
+    if width == 0 || height == 0 || width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE ||
+      width as usize * height as usize > MAX_PIXEL_COUNT
+    {
+      return Err(Error::msg("Invalid image size"));
+    }
+
+    let mut pixels = Vec::new();
+    let mut scanline = vec![[0u8; 4]; width as usize];
+
+    for row in 0..height {
+      read_scanline(&mut stream, &mut scanline)
+        .context("Failed to load pixels data. The file is possibly corrupted.")?;
+
+      // the image is allocated once the first scanline is read, so the header alone allocates nothing
+      if pixels.is_empty() {
+        pixels = vec![Color::default(); width as usize * height as usize];
+      }
+
+      let y = if is_top_down { height - 1 - row } else { row };
+      let start = y as usize * width as usize;
+
+      for (pixel, rgbe) in pixels[start..start + width as usize].iter_mut().zip(&scanline) {
+        *pixel = rgbe_to_color(rgbe);
+      }
+    }
+
+    Ok(HdrImage { width, height, pixels })
+  }
+
+  pub fn get_pixel_color(&self, x: u32, y: u32) -> Result<Color> {
+    if x >= self.width || y >= self.height {
+      return Result::Err(Error::msg("Pixel position out of bounds"));
+    }
+
+    Ok(self.pixels[(x + y * self.width) as usize].clone())
+  }
+
+  // bilinear filtering like the texture does
+  pub fn get_texel_color(&self, u: f32, v: f32) -> Result<Color> {
+    if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+      return Result::Err(Error::msg("Texel position out of bounds"));
+    }
+
+    let fx = clamp(u, 0.0, 1.0 - f32::EPSILON) * self.width as f32;
+    let fy = clamp(v, 0.0, 1.0 - f32::EPSILON) * self.height as f32;
+    let x = fx as u32;
+    let y = fy as u32;
+
+    if x + 1 >= self.width || y + 1 >= self.height {
+      return self.get_pixel_color(x, y);
+    }
+
+    let color_00 = self.get_pixel_color(x, y)?;
+    let color_01 = self.get_pixel_color(x, y + 1)?;
+    let color_10 = self.get_pixel_color(x + 1, y)?;
+    let color_11 = self.get_pixel_color(x + 1, y + 1)?;
+
+    let x_fract = fx.fract();
+    let y_fract = fy.fract();
+    let x_fract_inv = 1.0 - x_fract;
+    let y_fract_inv = 1.0 - y_fract;
+
+    Ok((color_00 * x_fract_inv + color_10 * x_fract) * y_fract_inv + (color_01 * x_fract_inv + color_11 * x_fract) * y_fract)
+  }
+}
+
+impl From<&Texture> for HdrImage {
+  fn from(texture: &Texture) -> HdrImage {
+    HdrImage {
+      width: texture.width,
+      height: texture.height,
+      pixels: texture.color_buffer.chunks(3).map(|rgb| Color::new(
+        rgb[0] as f32 / 255.0,
+        rgb[1] as f32 / 255.0,
+        rgb[2] as f32 / 255.0,
+      )).collect(),
+    }
+  }
+}
+
+impl TextureSource for HdrImage {
+  fn sample(&self, uv: (f32, f32), _position: &Vector3) -> Result<Color> {
+    self.get_texel_color(uv.0, uv.1)
+  }
+}
+
+fn read_scanline(stream: &mut impl BufRead, scanline: &mut [[u8; 4]]) -> Result<()> {
+  let width = scanline.len();
+
+  if width == 0 {
+    return Err(Error::msg("Empty scanline"));
+  }
+
+  let mut first = [0u8; 4];
+  stream.read_exact(&mut first)?;
+
+  // the run length encoded scanline starts with 2, 2 and the scanline width
+  let is_encoded = (8..0x8000).contains(&width) && first[0] == 2 && first[1] == 2 && first[2] & 0x80 == 0;
+
+  if !is_encoded {
+    scanline[0] = first;
+
+    for pixel in scanline.iter_mut().skip(1) {
+      stream.read_exact(pixel)?;
+    }
+
+    return Ok(());
+  }
+
+  if ((first[2] as usize) << 8 | first[3] as usize) != width {
+    return Err(Error::msg("Scanline width mismatch"));
+  }
+
+  // every channel is encoded separately, the counts above 128 are the runs of a single value
+  for channel in 0..4 {
+    let mut x = 0;
+
+    while x < width {
+      let mut count = [0u8; 1];
+      stream.read_exact(&mut count)?;
+      let (count, is_run) = if count[0] > 128 { (count[0] as usize - 128, true) } else { (count[0] as usize, false) };
+
+      if count == 0 || x + count > width {
+        return Err(Error::msg("Invalid scanline run"));
+      }
+
+      if is_run {
+        let mut value = [0u8; 1];
+        stream.read_exact(&mut value)?;
+        scanline[x..x + count].iter_mut().for_each(|pixel| pixel[channel] = value[0]);
+      } else {
+        let mut values = vec![0u8; count];
+        stream.read_exact(&mut values)?;
+        scanline[x..x + count].iter_mut().zip(values).for_each(|(pixel, value)| pixel[channel] = value);
+      }
+
+      x += count;
+    }
+  }
+
+  Ok(())
+}
+
+// the shared exponent scales all three mantissas
+fn rgbe_to_color(rgbe: &[u8; 4]) -> Color {
+  if rgbe[3] == 0 {
+    return Color::default();
+  }
+
+  let scale = f32::powi(2.0, rgbe[3] as i32 - (128 + 8));
+  Color::new(rgbe[0] as f32 * scale, rgbe[1] as f32 * scale, rgbe[2] as f32 * scale)
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Material, TextureSource};
+
+// indices of the library entries, the primitives keep them instead of the material and texture copies
+pub type MaterialId = u32;
+pub type TextureId = u32;
+
+// materials and textures of the scene, a texture is an image or a procedural pattern, equal materials and the same texture get a single entry,
+// the entries can be named to be referenced later
+#[derive(Debug, Default)]
+pub struct Library {
+  materials: Vec<Material>,
+  material_names: HashMap<String, MaterialId>,
+  textures: Vec<Rc<dyn TextureSource>>,
+  texture_names: HashMap<String, TextureId>,
+}
+
+impl Library {
+  pub fn add_material(&mut self, material: &Material) -> MaterialId {
+    match self.materials.iter().position(|known| known == material) {
+      Some(index) => index as MaterialId,
+      None => {
+        self.materials.push(material.clone());
+        (self.materials.len() - 1) as MaterialId
+      }
+    }
+  }
+
+  // the name is moved to the new material if it was used before
+  pub fn add_named_material(&mut self, name: &str, material: &Material) -> MaterialId {
+    let id = self.add_material(material);
+    self.material_names.insert(name.to_owned(), id);
+    id
+  }
+
+  pub fn find_material(&self, name: &str) -> Option<MaterialId> {
+    self.material_names.get(name).copied()
+  }
+
+  pub fn get_material(&self, id: MaterialId) -> &Material {
+    &self.materials[id as usize]
+  }
+
+  pub fn get_material_name(&self, id: MaterialId) -> Option<&str> {
+    self.material_names.iter().find(|(_, &known)| known == id).map(|(name, _)| name.as_str())
+  }
+
+  pub fn get_material_count(&self) -> usize {
+    self.materials.len()
+  }
+
+  // texture is shared, not copied, the same texture added again gets the same ID
+  pub fn add_texture(&mut self, texture: &Rc<dyn TextureSource>) -> TextureId {
+    match self.textures.iter().position(|known| Rc::ptr_eq(known, texture)) {
+      Some(index) => index as TextureId,
+      None => {
+        self.textures.push(Rc::clone(texture));
+        (self.textures.len() - 1) as TextureId
+      }
+    }
+  }
+
+  pub fn add_named_texture(&mut self, name: &str, texture: &Rc<dyn TextureSource>) -> TextureId {
+    let id = self.add_texture(texture);
+    self.texture_names.insert(name.to_owned(), id);
+    id
+  }
+
+  pub fn find_texture(&self, name: &str) -> Option<TextureId> {
+    self.texture_names.get(name).copied()
+  }
+
+  pub fn get_texture(&self, id: TextureId) -> &dyn TextureSource {
+    self.textures[id as usize].as_ref()
+  }
+
+  pub fn get_texture_count(&self) -> usize {
+    self.textures.len()
+  }
+}
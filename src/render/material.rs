@@ -1,10 +1,45 @@
+use std::f32::consts::PI;
+use std::fmt;
+use std::rc::Rc;
+
 use super::Color;
+use super::spectrum::{MAX_WAVELENGTH, MEAN_WAVELENGTH, MIN_WAVELENGTH};
+
+// wavelengths of the red, green and blue light in nanometers, the thin film interference is computed for them
+const WAVELENGTHS: [f32; 3] = [650.0, 550.0, 450.0];
+
+// material shared by many objects of the scene builder
+pub type MaterialHandle = Rc<Material>;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Kind {
   None,
   Metal,
   Dielectric,
+  // light enters the surface and leaves it at another point after the random walk inside the object
+  Subsurface,
+}
+
+pub const MATERIAL_KINDS: [Kind; 4] = [
+  Kind::None,
+  Kind::Metal,
+  Kind::Dielectric,
+  Kind::Subsurface,
+];
+
+impl Kind {
+  pub fn from_name(name: &str) -> Option<Kind> {
+    MATERIAL_KINDS.iter().find(|kind| kind.name() == name).cloned()
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Kind::None => "none",
+      Kind::Metal => "metal",
+      Kind::Dielectric => "dielectric",
+      Kind::Subsurface => "subsurface",
+    }
+  }
 }
 
 impl Default for Kind {
@@ -18,7 +53,42 @@ pub struct Material {
   pub kind: Kind,
   pub color: Color,
   pub reflectivity: f32,
+  // share of the light the dielectric lets through, the rest is reflected and lit like before
   pub transparency: f32,
+  // index of refraction of the transparent dielectric, the dispersive ones bend the wavelengths differently
+  pub ior: Ior,
+  // subsurface materials only, mean distance the light travels inside between the scatterings,
+  // the scattering color scales it per channel, so the red light of the skin goes deeper
+  pub scattering_distance: f32,
+  pub scattering_color: Color,
+  // clear dielectric layer over the material, it reflects by the Fresnel equations, the rest reaches the material
+  pub coat: Option<Coat>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coat {
+  pub ior: f32,
+  // spread of the coat reflections, zero is the mirror
+  pub roughness: f32,
+  pub thin_film: Option<ThinFilm>,
+}
+
+// the wavelengths of the Cauchy and Sellmeier formulas are in micrometers
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ior {
+  Constant(f32),
+  // n = a + b / wavelength^2
+  Cauchy { a: f32, b: f32 },
+  // n^2 = 1 + sum of b * wavelength^2 / (wavelength^2 - c)
+  Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+// film on the top of the coat, the light reflected by its two sides interferes and colors the reflections
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThinFilm {
+  // in nanometers, the soap bubble is a few hundred nanometers thick
+  pub thickness: f32,
+  pub ior: f32,
 }
 
 impl Material {
@@ -28,6 +98,258 @@ impl Material {
       color,
       reflectivity,
       transparency,
+      ior: Ior::default(),
+      scattering_distance: 0.0,
+      scattering_color: Color::new(1.0, 1.0, 1.0),
+      coat: None,
+    }
+  }
+
+  // clear dielectric letting all the light through, the color tints it
+  pub fn transparent(color: Color, ior: Ior) -> Material {
+    Material { ior, ..Material::new(Kind::Dielectric, color, 1.0, 1.0) }
+  }
+
+  pub fn with_coat(self, coat: Coat) -> Material {
+    Material { coat: Some(coat), ..self }
+  }
+
+  // the color is the share of the light coming back out of the object, like the color of the dielectric
+  pub fn subsurface(color: Color, scattering_color: Color, scattering_distance: f32, reflectivity: f32) -> Material {
+    Material {
+      scattering_distance,
+      scattering_color,
+      ..Material::new(Kind::Subsurface, color, reflectivity, 0.0)
+    }
+  }
+}
+
+// ready made materials, the reflectivity sets how sharp the reflections are
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+  Mirror,
+  Chrome,
+  BrushedMetal,
+  Gold,
+  Copper,
+  GlossyPlastic,
+  Matte,
+  Wax,
+  Skin,
+  Jade,
+  Marble,
+  CarPaint,
+  LacqueredWood,
+  SoapBubble,
+  Glass,
+  Diamond,
+}
+
+pub const MATERIAL_PRESETS: [Preset; 16] = [
+  Preset::Mirror,
+  Preset::Chrome,
+  Preset::BrushedMetal,
+  Preset::Gold,
+  Preset::Copper,
+  Preset::GlossyPlastic,
+  Preset::Matte,
+  Preset::Wax,
+  Preset::Skin,
+  Preset::Jade,
+  Preset::Marble,
+  Preset::CarPaint,
+  Preset::LacqueredWood,
+  Preset::SoapBubble,
+  Preset::Glass,
+  Preset::Diamond,
+];
+
+impl Preset {
+  pub fn from_name(name: &str) -> Option<Preset> {
+    MATERIAL_PRESETS.iter().copied().find(|preset| preset.name() == name)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Preset::Mirror => "mirror",
+      Preset::Chrome => "chrome",
+      Preset::BrushedMetal => "brushed_metal",
+      Preset::Gold => "gold",
+      Preset::Copper => "copper",
+      Preset::GlossyPlastic => "glossy_plastic",
+      Preset::Matte => "matte",
+      Preset::Wax => "wax",
+      Preset::Skin => "skin",
+      Preset::Jade => "jade",
+      Preset::Marble => "marble",
+      Preset::CarPaint => "car_paint",
+      Preset::LacqueredWood => "lacquered_wood",
+      Preset::SoapBubble => "soap_bubble",
+      Preset::Glass => "glass",
+      Preset::Diamond => "diamond",
+    }
+  }
+
+  pub fn material(&self) -> Material {
+    match self {
+      Preset::Mirror => Material::new(Kind::Metal, Color::new(1.0, 1.0, 1.0), 1.0, 0.0),
+      Preset::Chrome => Material::new(Kind::Metal, Color::new(0.9, 0.9, 0.9), 0.95, 0.0),
+      Preset::BrushedMetal => Material::new(Kind::Metal, Color::new(0.9, 0.9, 0.9), 0.5, 0.0),
+      Preset::Gold => Material::new(Kind::Metal, Color::new(1.0, 0.78, 0.34), 0.9, 0.0),
+      Preset::Copper => Material::new(Kind::Metal, Color::new(1.0, 0.65, 0.45), 0.85, 0.0),
+      Preset::GlossyPlastic => Material::new(Kind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.95, 0.0),
+      Preset::Matte => Material::new(Kind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.0, 0.0),
+      // the scattering distances are for the objects about a unit in size
+      Preset::Wax => Material::subsurface(Color::new(0.95, 0.9, 0.75), Color::new(1.0, 0.8, 0.5), 0.1, 0.6),
+      Preset::Skin => Material::subsurface(Color::new(0.85, 0.6, 0.5), Color::new(1.0, 0.4, 0.25), 0.05, 0.4),
+      Preset::Jade => Material::subsurface(Color::new(0.4, 0.85, 0.55), Color::new(0.5, 1.0, 0.6), 0.15, 0.9),
+      Preset::Marble => Material::subsurface(Color::new(0.9, 0.9, 0.88), Color::new(1.0, 1.0, 1.0), 0.03, 0.8),
+      // the lacquered wood takes its grain from the wood texture, the soap film has the air under it
+      Preset::CarPaint => Material::new(Kind::Metal, Color::new(0.7, 0.05, 0.05), 0.4, 0.0).with_coat(Coat::new(1.5, 0.02)),
+      Preset::LacqueredWood => Material::new(Kind::Dielectric, Color::new(0.6, 0.35, 0.15), 0.2, 0.0).with_coat(Coat::new(1.5, 0.1)),
+      Preset::SoapBubble => Material::new(Kind::Dielectric, Color::new(0.05, 0.05, 0.05), 0.0, 0.0)
+        .with_coat(Coat::new(1.0, 0.0).with_thin_film(380.0, 1.33)),
+      Preset::Glass => Material::transparent(Color::new(1.0, 1.0, 1.0), Ior::GLASS),
+      Preset::Diamond => Material::transparent(Color::new(1.0, 1.0, 1.0), Ior::DIAMOND),
+    }
+  }
+}
+
+impl Ior {
+  // crown glass BK7
+  pub const GLASS: Ior = Ior::Sellmeier { b: [1.039_612, 0.231_792_34, 1.010_469_5], c: [0.006_000_699, 0.020_017_914, 103.560_65] };
+  pub const DIAMOND: Ior = Ior::Sellmeier { b: [0.3306, 4.3356, 0.0], c: [0.030_625, 0.011_236, 0.0] };
+
+  // the wavelength is in nanometers
+  pub fn get(&self, wavelength: f32) -> f32 {
+    let micrometers = wavelength / 1000.0;
+    let sq = micrometers * micrometers;
+
+    match self {
+      Ior::Constant(ior) => *ior,
+      Ior::Cauchy { a, b } => a + b / sq,
+      Ior::Sellmeier { b, c } => f32::sqrt(1.0 + (0..3).map(|index| b[index] * sq / (sq - c[index])).sum::<f32>()),
     }
   }
+
+  pub fn get_mean(&self) -> f32 {
+    self.get(MEAN_WAVELENGTH)
+  }
+
+  // the IOR is in range [1, 4] at the ends and the middle of the visible spectrum,
+  // the Sellmeier poles can't fall into the spectrum
+  pub fn is_valid(&self) -> bool {
+    let sq = |wavelength: f32| (wavelength / 1000.0) * (wavelength / 1000.0);
+
+    if let Ior::Sellmeier { c, .. } = self {
+      if c.iter().any(|&c| (sq(MIN_WAVELENGTH)..=sq(MAX_WAVELENGTH)).contains(&c)) {
+        return false;
+      }
+    }
+
+    [MIN_WAVELENGTH, MEAN_WAVELENGTH, MAX_WAVELENGTH].iter().all(|&wavelength| (1.0..=4.0).contains(&self.get(wavelength)))
+  }
+
+  // "1.5", "cauchy 1.5046, 0.0042" or "sellmeier b1, b2, b3, c1, c2, c3"
+  pub fn parse(spec: &str) -> Option<Ior> {
+    let spec = spec.trim();
+    let (name, values) = spec.split_once(' ').unwrap_or(("", spec));
+    let values = values.split(',').map(|value| value.trim().parse::<f32>().ok()).collect::<Option<Vec<f32>>>()?;
+
+    match (name, &values[..]) {
+      ("", &[ior]) => Some(Ior::Constant(ior)),
+      ("cauchy", &[a, b]) => Some(Ior::Cauchy { a, b }),
+      ("sellmeier", &[b1, b2, b3, c1, c2, c3]) => Some(Ior::Sellmeier { b: [b1, b2, b3], c: [c1, c2, c3] }),
+      _ => None,
+    }
+  }
+}
+
+impl Default for Ior {
+  fn default() -> Ior {
+    Ior::Constant(1.5)
+  }
+}
+
+impl fmt::Display for Ior {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Ior::Constant(ior) => write!(f, "{}", ior),
+      Ior::Cauchy { a, b } => write!(f, "cauchy {}, {}", a, b),
+      Ior::Sellmeier { b, c } => write!(f, "sellmeier {}, {}, {}, {}, {}, {}", b[0], b[1], b[2], c[0], c[1], c[2]),
+    }
+  }
+}
+
+impl Coat {
+  pub fn new(ior: f32, roughness: f32) -> Coat {
+    Coat { ior, roughness, thin_film: None }
+  }
+
+  pub fn with_thin_film(self, thickness: f32, ior: f32) -> Coat {
+    Coat { thin_film: Some(ThinFilm { thickness, ior }), ..self }
+  }
+
+  // share of the light reflected by the coat per channel, the cosine is of the angle between the ray and the normal
+  pub fn get_reflectance(&self, cos: f32) -> Color {
+    let [r, g, b] = WAVELENGTHS.map(|wavelength| self.get_spectral_reflectance(cos, wavelength));
+    Color::new(r, g, b)
+  }
+
+  // share of the light of the wavelength in nanometers reflected by the coat
+  pub fn get_spectral_reflectance(&self, cos: f32, wavelength: f32) -> f32 {
+    let cos = cos.clamp(0.0, 1.0);
+
+    match &self.thin_film {
+      Some(film) => thin_film_reflectance(cos, wavelength, film, self.ior),
+      None => fresnel_dielectric(cos, self.ior),
+    }
+  }
+}
+
+// unpolarized Fresnel reflectance of the light coming from the air into the dielectric
+pub fn fresnel_dielectric(cos: f32, ior: f32) -> f32 {
+  let sin_sq = (1.0 - cos * cos) / (ior * ior);
+  let cos_t = f32::sqrt(f32::max(1.0 - sin_sq, 0.0));
+
+  let s = (cos - ior * cos_t) / (cos + ior * cos_t);
+  let p = (ior * cos - cos_t) / (ior * cos + cos_t);
+
+  0.5 * (s * s + p * p)
+}
+
+// Airy reflectance of the film between the air and the coat, both polarizations are averaged
+fn thin_film_reflectance(cos: f32, wavelength: f32, film: &ThinFilm, coat_ior: f32) -> f32 {
+  let sin_sq = 1.0 - cos * cos;
+  let film_cos_sq = 1.0 - sin_sq / (film.ior * film.ior);
+
+  if film_cos_sq <= 0.0 {
+    return 1.0;
+  }
+
+  let film_cos = film_cos_sq.sqrt();
+  let coat_cos = f32::sqrt(f32::max(1.0 - sin_sq / (coat_ior * coat_ior), 0.0));
+  let phase = 4.0 * PI * film.ior * film.thickness * film_cos / wavelength;
+
+  let airy = |r12: f32, r23: f32| {
+    let interference = 2.0 * r12 * r23 * phase.cos();
+    (r12 * r12 + r23 * r23 + interference) / (1.0 + r12 * r12 * r23 * r23 + interference)
+  };
+
+  let s = airy(
+    (cos - film.ior * film_cos) / (cos + film.ior * film_cos),
+    (film.ior * film_cos - coat_ior * coat_cos) / (film.ior * film_cos + coat_ior * coat_cos),
+  );
+  let p = airy(
+    (film.ior * cos - film_cos) / (film.ior * cos + film_cos),
+    (coat_ior * film_cos - film.ior * coat_cos) / (coat_ior * film_cos + film.ior * coat_cos),
+  );
+
+  (0.5 * (s + p)).clamp(0.0, 1.0)
+}
+
+impl From<Preset> for MaterialHandle {
+  fn from(preset: Preset) -> MaterialHandle {
+    Rc::new(preset.material())
+  }
 }
@@ -0,0 +1,142 @@
+use super::Color;
+use super::math::Vector3;
+
+// the exponents are kept in the float range, so the optical depth never becomes infinite minus infinite
+const MAX_EXPONENT: f32 = 80.0;
+// steps of the search of the scattering distance
+const DISTANCE_SEARCH_STEPS: u32 = 24;
+
+// fog filling the whole space, its density falls off exponentially with the height above the base,
+// the zero falloff makes the homogeneous fog
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fog {
+  // share of the extinguished light that is scattered, per color channel
+  pub color: Color,
+  // extinction per unit of the distance at the base height
+  pub density: f32,
+  pub height_falloff: f32,
+  pub base_height: f32,
+}
+
+// homogeneous scattering volume bounded by a sphere, like a cloud of smoke
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume {
+  pub center: Vector3,
+  pub radius: f32,
+  pub color: Color,
+  pub density: f32,
+}
+
+// participating media of the scene, the extinction is the same for all colors, the color is the scattering albedo
+#[derive(Debug, Default)]
+pub struct Media {
+  pub fog: Option<Fog>,
+  pub volumes: Vec<Volume>,
+}
+
+impl Fog {
+  pub fn get_density(&self, point: &Vector3) -> f32 {
+    self.density * f32::exp((-self.height_falloff * (point.y - self.base_height)).min(MAX_EXPONENT))
+  }
+
+  // direction is normalized
+  fn get_optical_depth(&self, origin: &Vector3, direction: &Vector3, distance: f32) -> f32 {
+    let k = self.height_falloff * direction.y;
+
+    if k.abs() < 1e-6 {
+      return self.get_density(origin) * distance;
+    }
+
+    let start = (-self.height_falloff * (origin.y - self.base_height)).clamp(-MAX_EXPONENT, MAX_EXPONENT);
+    let end = (start - k * distance).clamp(-MAX_EXPONENT, MAX_EXPONENT);
+
+    self.density * (f32::exp(start) - f32::exp(end)) / k
+  }
+}
+
+impl Volume {
+  // part of the segment inside the sphere
+  fn get_segment(&self, origin: &Vector3, direction: &Vector3, distance: f32) -> Option<(f32, f32)> {
+    let offset = origin - &self.center;
+    let b = direction * &offset;
+    let d = b * b - offset.sq_length() + self.radius * self.radius;
+
+    if d <= 0.0 {
+      return None;
+    }
+
+    let near = f32::max(-b - d.sqrt(), 0.0);
+    let far = f32::min(-b + d.sqrt(), distance);
+
+    if far > near { Some((near, far)) } else { None }
+  }
+
+  fn contains(&self, point: &Vector3) -> bool {
+    (point - &self.center).sq_length() < self.radius * self.radius
+  }
+}
+
+impl Media {
+  pub fn is_empty(&self) -> bool {
+    self.fog.is_none() && self.volumes.is_empty()
+  }
+
+  // share of the light passing the segment, the direction is normalized
+  pub fn get_transmittance(&self, origin: &Vector3, direction: &Vector3, distance: f32) -> f32 {
+    f32::exp(-self.get_optical_depth(origin, direction, distance))
+  }
+
+  pub fn get_optical_depth(&self, origin: &Vector3, direction: &Vector3, distance: f32) -> f32 {
+    let fog_depth = self.fog.as_ref().map_or(0.0, |fog| fog.get_optical_depth(origin, direction, distance));
+
+    self.volumes.iter()
+      .filter_map(|volume| volume.get_segment(origin, direction, distance).map(|(near, far)| volume.density * (far - near)))
+      .sum::<f32>() + fog_depth
+  }
+
+  // scattering albedo at the point, the colors of the overlapping media are mixed by their densities
+  pub fn get_albedo(&self, point: &Vector3) -> Color {
+    let mut sum = Color::default();
+    let mut density_sum = 0.0;
+
+    if let Some(fog) = &self.fog {
+      let density = fog.get_density(point);
+      sum += &fog.color * density;
+      density_sum += density;
+    }
+
+    for volume in self.volumes.iter().filter(|volume| volume.contains(point)) {
+      sum += &volume.color * volume.density;
+      density_sum += volume.density;
+    }
+
+    if density_sum > 0.0 { sum / density_sum } else { Color::default() }
+  }
+
+  // picks the scattering point by the extinction along the segment, returns its distance and the share
+  // of the light scattered on the whole segment, none if the segment is clear
+  pub fn sample_distance(&self, origin: &Vector3, direction: &Vector3, distance: f32, sample: f32) -> Option<(f32, f32)> {
+    let total_depth = self.get_optical_depth(origin, direction, distance);
+    let scattered = 1.0 - f32::exp(-total_depth);
+
+    if scattered <= 0.0 {
+      return None;
+    }
+
+    // optical depth grows along the segment, so the point is found by the bisection
+    let target_depth = -f32::ln(1.0 - sample * scattered);
+    let (mut near, mut far) = (0.0, distance);
+
+    for _ in 0..DISTANCE_SEARCH_STEPS {
+      let middle = 0.5 * (near + far);
+
+      if self.get_optical_depth(origin, direction, middle) <= target_depth {
+        near = middle;
+      } else {
+        far = middle;
+      }
+    }
+
+    Some((0.5 * (near + far), scattered))
+  }
+}
@@ -1,30 +1,66 @@
 use crate::math;
 
+pub use self::adaptive::AdaptiveSampling;
+pub use self::aov::AovKind;
+pub use self::debug::DebugMode;
+pub use self::filter::{Filter, FilterKind, FILTER_KINDS, MAX_FILTER_RADIUS};
 pub use self::camera::Camera;
 pub use self::color::Color;
 pub use self::material::Kind as MaterialKind;
-pub use self::material::Material;
-pub use self::render::Render;
-pub use self::scene::Scene;
-pub use self::skybox::Skybox;
+pub use self::material::MATERIAL_KINDS;
+pub use self::library::{Library, MaterialId, TextureId};
+pub use self::material::{Coat, Ior, Material, MaterialHandle, ThinFilm};
+pub use self::material::{Preset as MaterialPreset, MATERIAL_PRESETS};
+pub use self::render::{Render, MAX_IMAGE_SIZE, MAX_SAMPLES};
+pub use self::sampler::{SampleId, Sampler, SamplerKind, SampleStream, SAMPLER_KINDS};
+pub use self::scene::{FirstHit, PickInfo, Scene, SKY_DEPTH};
+pub use self::scene_builder::{SceneBuilder, TextureHandle};
+pub use self::scene_desc::{ObjectDesc, SceneDesc, TextureCache};
+pub use self::denoiser::{Denoiser, Guide};
+pub use self::hdr_image::HdrImage;
+pub use self::medium::{Fog, Volume};
+pub use self::physical_sky::PhysicalSky;
+pub use self::skybox::{SkyMapping, SkySample, Skybox, SKY_MAPPINGS};
 pub use self::sphere::Sphere;
 pub use self::spot_light::SpotLight;
 pub use self::texture::Texture;
+pub use self::texture_source::TextureSource;
+pub use self::procedural::{
+  Checker, Gradient, Marble, Noise, ProceduralKind, Space as ProceduralSpace, UvGrid, Wood, PROCEDURAL_KINDS,
+};
 pub use self::trace::Trace;
 pub use self::triangle::Triangle;
 
 mod color;
 mod material;
+mod library;
 mod trace;
 mod sphere;
 mod texture;
+mod texture_source;
+mod procedural;
+mod hdr_image;
+mod physical_sky;
 mod skybox;
+mod medium;
+mod subsurface;
+mod spectrum;
+mod refraction;
 mod spot_light;
 // TODO: move camera control to the render and make private
 pub mod camera;
 mod triangle;
 mod scene;
+mod scene_builder;
+mod scene_desc;
+mod sampler;
 mod render;
+mod adaptive;
+mod pixel;
+mod filter;
+mod denoiser;
+mod aov;
+mod debug;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,155 @@
+use std::f32::consts::PI;
+
+use super::Color;
+use super::math::Vector3;
+
+// Preetham sky luminance in kcd/m2 is scaled down, so the zenith of the high sun is about the texture white
+const SKY_LUMINANCE_SCALE: f32 = 0.05;
+// the view zenith cosine is kept above it, the Perez function grows without a bound at the horizon
+const MIN_VIEW_COS: f32 = 0.01;
+// zenith angle of the horizon color reflected by the ground
+const GROUND_VIEW_ANGLE: f32 = 0.49 * PI;
+// wavelengths of the red, green and blue sun light in micrometers
+const SUN_WAVELENGTHS: [f32; 3] = [0.65, 0.57, 0.475];
+
+// analytic daylight sky of A. J. Preetham, P. Shirley and B. Smits, "A Practical Analytic Model for Daylight",
+// the sky is clear, the ground below the horizon reflects the horizon color
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicalSky {
+  sun_direction: Vector3,
+  turbidity: f32,
+  ground_albedo: f32,
+  // Perez coefficients A..E of the luminance and the chromaticity
+  perez: [[f32; 5]; 3],
+  // zenith luminance and chromaticity divided by the Perez function at the zenith
+  zenith: [f32; 3],
+}
+
+impl PhysicalSky {
+  // turbidity goes from 2 for the very clear sky to 10 for the haze, the sun below the horizon gives the sky
+  // of the sun at the horizon, the twilight isn't modelled
+  pub fn new(sun_direction: &Vector3, turbidity: f32, ground_albedo: f32) -> PhysicalSky {
+    assert!(sun_direction.length() > 0.0, "Invalid argument");
+    assert!((1.0..=20.0).contains(&turbidity), "Invalid argument");
+    assert!((0.0..=1.0).contains(&ground_albedo), "Invalid argument");
+
+    let sun_direction = sun_direction.normalized();
+    let sun_zenith = f32::acos(sun_direction.y.clamp(0.0, 1.0));
+    let t = turbidity;
+
+    let perez = [
+      [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+      [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+      [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+    ];
+
+    let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_zenith);
+    let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+    let zenith_x = zenith_chromaticity(&[
+      [0.00166, -0.00375, 0.00209, 0.0],
+      [-0.02903, 0.06377, -0.03202, 0.00394],
+      [0.11693, -0.21196, 0.06052, 0.25886],
+    ], t, sun_zenith);
+    let zenith_y = zenith_chromaticity(&[
+      [0.00275, -0.00610, 0.00317, 0.0],
+      [-0.04214, 0.08970, -0.04153, 0.00516],
+      [0.15346, -0.26756, 0.06670, 0.26688],
+    ], t, sun_zenith);
+
+    let zenith_values = [zenith_luminance.max(0.0), zenith_x, zenith_y];
+    let mut zenith = [0.0; 3];
+
+    for (index, value) in zenith_values.iter().enumerate() {
+      zenith[index] = value / perez_function(&perez[index], 1.0, sun_zenith);
+    }
+
+    PhysicalSky { sun_direction, turbidity, ground_albedo, perez, zenith }
+  }
+
+  // direction from the elevation above the horizon and the azimuth from the +z axis towards the +x axis, in radians
+  pub fn sun_direction(elevation: f32, azimuth: f32) -> Vector3 {
+    Vector3::new(elevation.cos() * azimuth.sin(), elevation.sin(), elevation.cos() * azimuth.cos())
+  }
+
+  pub fn get_sun_direction(&self) -> &Vector3 {
+    &self.sun_direction
+  }
+
+  pub fn get_turbidity(&self) -> f32 {
+    self.turbidity
+  }
+
+  pub fn get_ground_albedo(&self) -> f32 {
+    self.ground_albedo
+  }
+
+  // share of the sun light passing the atmosphere by the Rayleigh and the aerosol scattering, black below the horizon
+  pub fn get_sun_color(&self) -> Color {
+    if self.sun_direction.y <= 0.0 {
+      return Color::default();
+    }
+
+    // relative optical air mass of Kasten and Young
+    let elevation_degrees = f32::asin(self.sun_direction.y).to_degrees();
+    let air_mass = 1.0 / (self.sun_direction.y + 0.50572 * f32::powf(elevation_degrees + 6.07995, -1.6364));
+    // Angstrom turbidity coefficient
+    let beta = 0.04608 * self.turbidity - 0.04586;
+
+    let [r, g, b] = SUN_WAVELENGTHS.map(|wavelength| {
+      let rayleigh = 0.008735 * f32::powf(wavelength, -4.08);
+      let aerosol = beta * f32::powf(wavelength, -1.3);
+      f32::exp(-air_mass * (rayleigh + aerosol))
+    });
+
+    Color::new(r, g, b)
+  }
+
+  pub fn get_sky_color(&self, direction: &Vector3) -> Color {
+    let direction = direction.normalized();
+
+    if direction.y < 0.0 {
+      let horizontal = Vector3::new(direction.x, 0.0, direction.z);
+      let horizontal = if horizontal.length() > 0.0 { horizontal.normalized() } else { Vector3::new(1.0, 0.0, 0.0) };
+      let horizon = horizontal * GROUND_VIEW_ANGLE.sin() + Vector3::new(0.0, GROUND_VIEW_ANGLE.cos(), 0.0);
+
+      return self.get_sky_color(&horizon) * self.ground_albedo;
+    }
+
+    let view_cos = direction.y.max(MIN_VIEW_COS);
+    let sun_angle = f32::acos((&direction * &self.sun_direction).clamp(-1.0, 1.0));
+
+    let [luminance, x, y] = [0, 1, 2].map(|index| self.zenith[index] * perez_function(&self.perez[index], view_cos, sun_angle));
+    xyy_to_rgb(x, y, luminance * SKY_LUMINANCE_SCALE)
+  }
+}
+
+fn zenith_chromaticity(matrix: &[[f32; 4]; 3], turbidity: f32, sun_zenith: f32) -> f32 {
+  let t = [turbidity * turbidity, turbidity, 1.0];
+  let angle = [sun_zenith.powi(3), sun_zenith.powi(2), sun_zenith, 1.0];
+
+  (0..3).map(|row| t[row] * (0..4).map(|column| matrix[row][column] * angle[column]).sum::<f32>()).sum()
+}
+
+// distribution of the sky values over the view zenith and the angle to the sun
+fn perez_function(coefficients: &[f32; 5], view_cos: f32, sun_angle: f32) -> f32 {
+  let [a, b, c, d, e] = *coefficients;
+  let sun_cos = sun_angle.cos();
+
+  (1.0 + a * f32::exp(b / view_cos)) * (1.0 + c * f32::exp(d * sun_angle) + e * sun_cos * sun_cos)
+}
+
+// CIE xyY to the linear sRGB, the colors out of the gamut lose the negative components
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Color {
+  if y <= 0.0 {
+    return Color::default();
+  }
+
+  let cie_x = x / y * luminance;
+  let cie_z = (1.0 - x - y) / y * luminance;
+
+  Color::new(
+    f32::max(3.2406 * cie_x - 1.5372 * luminance - 0.4986 * cie_z, 0.0),
+    f32::max(-0.9689 * cie_x + 1.8758 * luminance + 0.0415 * cie_z, 0.0),
+    f32::max(0.0557 * cie_x - 0.2040 * luminance + 1.0570 * cie_z, 0.0),
+  )
+}
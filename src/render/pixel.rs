@@ -0,0 +1,96 @@
+use super::{Color, FirstHit, SKY_DEPTH};
+use super::math::Vector3;
+
+// dark pixels are compared against this luminance, otherwise their relative error never gets small
+const MIN_LUMINANCE: f32 = 0.05;
+
+// accumulated image pixel: weighted sum of the filtered samples splatted into the pixel and the sum of
+// their weights, running mean and variance of the luminance of the pixel's own samples (Welford's algorithm)
+// and the sums of the first hit properties of the own samples
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Pixel {
+  pub color_sum: Color,
+  pub weight_sum: f32,
+  pub samples: u32,
+  pub mean: f32,
+  pub m2: f32,
+  pub albedo_sum: Color,
+  pub normal_sum: Vector3,
+  pub depth_sum: f32,
+  // render pass the pixel was accumulated in, a pixel from a previous pass is reset before use
+  pub pass: u32,
+}
+
+impl Pixel {
+  pub fn reset(&mut self) {
+    *self = Pixel { pass: self.pass, ..Pixel::default() };
+  }
+
+  // single color for the whole pixel without any statistics, used for the downsampled render
+  pub fn set_color(&mut self, color: &Color, first_hit: &FirstHit) {
+    self.reset();
+    self.add_sample(color, first_hit);
+    self.add_weighted(color, 1.0);
+  }
+
+  pub fn add_weighted(&mut self, color: &Color, weight: f32) {
+    self.color_sum += color * weight;
+    self.weight_sum += weight;
+  }
+
+  // counts the sample taken for the pixel, its color gets into the pixel separately with the filter weight
+  pub fn add_sample(&mut self, color: &Color, first_hit: &FirstHit) {
+    let luminance = color.luminance();
+    self.samples += 1;
+    self.albedo_sum += &first_hit.albedo;
+    self.normal_sum += &first_hit.normal;
+    self.depth_sum += first_hit.depth;
+
+    let delta = luminance - self.mean;
+    self.mean += delta / self.samples as f32;
+    self.m2 += delta * (luminance - self.mean);
+  }
+
+  pub fn get_color(&self) -> Color {
+    // negative filter lobes may leave no positive weight in the pixel
+    if self.weight_sum > 1e-6 {
+      &self.color_sum / self.weight_sum
+    } else {
+      Color::default()
+    }
+  }
+
+  pub fn get_albedo(&self) -> Color {
+    &self.albedo_sum / u32::max(self.samples, 1) as f32
+  }
+
+  // averaged normal is not normalized, so the pixels on the object edges have shorter normals
+  pub fn get_normal(&self) -> Vector3 {
+    &self.normal_sum / u32::max(self.samples, 1) as f32
+  }
+
+  pub fn get_depth(&self) -> f32 {
+    if self.samples > 0 {
+      self.depth_sum / self.samples as f32
+    } else {
+      SKY_DEPTH
+    }
+  }
+
+  pub fn get_variance(&self) -> f32 {
+    if self.samples > 1 {
+      self.m2 / (self.samples - 1) as f32
+    } else {
+      0.0
+    }
+  }
+
+  // relative standard error of the mean luminance, unknown until there are two samples
+  pub fn get_error(&self) -> f32 {
+    if self.samples < 2 {
+      return f32::INFINITY;
+    }
+
+    f32::sqrt(self.get_variance() / self.samples as f32) / f32::max(self.mean, MIN_LUMINANCE)
+  }
+}
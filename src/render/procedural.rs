@@ -0,0 +1,302 @@
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use super::{Color, TextureSource};
+use super::math::{hash, Vector3};
+
+// coordinates the procedural pattern is evaluated in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Space {
+  // texture coordinates of the surface, the pattern sticks to the object
+  Uv,
+  // world position of the surface point, the object looks carved from a solid block
+  World,
+}
+
+impl Space {
+  fn point(&self, uv: (f32, f32), position: &Vector3, scale: f32) -> Vector3 {
+    match self {
+      Space::Uv => Vector3::new(uv.0, uv.1, 0.0) * scale,
+      Space::World => position * scale,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checker {
+  pub space: Space,
+  pub scale: f32,
+  pub colors: [Color; 2],
+}
+
+impl TextureSource for Checker {
+  fn sample(&self, uv: (f32, f32), position: &Vector3) -> Result<Color> {
+    let point = self.space.point(uv, position, self.scale);
+    let parity = (point.x.floor() + point.y.floor() + point.z.floor()) as i64 & 1;
+
+    Ok(self.colors[parity as usize].clone())
+  }
+}
+
+// fractal Brownian motion, the sum of the noise octaves with the halving amplitude
+#[derive(Debug, Clone, PartialEq)]
+pub struct Noise {
+  pub space: Space,
+  pub scale: f32,
+  pub octaves: u32,
+  pub colors: [Color; 2],
+}
+
+impl TextureSource for Noise {
+  fn sample(&self, uv: (f32, f32), position: &Vector3) -> Result<Color> {
+    let point = self.space.point(uv, position, self.scale);
+    let value = 0.5 + 0.5 * fbm(&point, self.octaves);
+
+    Ok(mix(&self.colors, value))
+  }
+}
+
+// veins along the x axis, bent by the turbulence
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marble {
+  pub space: Space,
+  pub scale: f32,
+  pub turbulence: f32,
+  pub colors: [Color; 2],
+}
+
+impl TextureSource for Marble {
+  fn sample(&self, uv: (f32, f32), position: &Vector3) -> Result<Color> {
+    let point = self.space.point(uv, position, self.scale);
+    let phase = point.x + self.turbulence * turbulence(&point, MARBLE_OCTAVES);
+    let value = 0.5 + 0.5 * f32::sin(phase * std::f32::consts::PI);
+
+    Ok(mix(&self.colors, value))
+  }
+}
+
+// rings around the y axis, the noise makes them uneven
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wood {
+  pub space: Space,
+  pub scale: f32,
+  pub turbulence: f32,
+  pub colors: [Color; 2],
+}
+
+impl TextureSource for Wood {
+  fn sample(&self, uv: (f32, f32), position: &Vector3) -> Result<Color> {
+    let point = self.space.point(uv, position, self.scale);
+    let radius = f32::sqrt(point.x * point.x + point.z * point.z);
+    let ring = radius + self.turbulence * fbm(&point, WOOD_OCTAVES);
+    // the late wood of every ring is narrow and dark
+    let value = f32::powi(ring - ring.floor(), 3);
+
+    Ok(mix(&self.colors, value))
+  }
+}
+
+// linear blend of the colors along the direction, the pattern repeats every unit of the direction length
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+  pub space: Space,
+  pub direction: Vector3,
+  pub colors: [Color; 2],
+}
+
+impl TextureSource for Gradient {
+  fn sample(&self, uv: (f32, f32), position: &Vector3) -> Result<Color> {
+    let value = self.space.point(uv, position, 1.0) * &self.direction;
+    let value = if value >= 1.0 { 1.0 } else { value - value.floor() };
+
+    Ok(mix(&self.colors, value))
+  }
+}
+
+// cells colored by their texture coordinates with the grid lines, shows the texture mapping of the object
+#[derive(Debug, Clone, PartialEq)]
+pub struct UvGrid {
+  pub cells: u32,
+  pub line_width: f32,
+  pub line_color: Color,
+}
+
+impl TextureSource for UvGrid {
+  fn sample(&self, uv: (f32, f32), _position: &Vector3) -> Result<Color> {
+    let (u, v) = (uv.0 * self.cells as f32, uv.1 * self.cells as f32);
+    let distance = |value: f32| f32::min(value - value.floor(), value.ceil() - value);
+
+    if distance(u) < self.line_width || distance(v) < self.line_width {
+      return Ok(self.line_color.clone());
+    }
+
+    let cells = self.cells as f32;
+    Ok(Color::new((u.floor() + 0.5) / cells, (v.floor() + 0.5) / cells, 0.5))
+  }
+}
+
+const MARBLE_OCTAVES: u32 = 5;
+const WOOD_OCTAVES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProceduralKind {
+  Checker,
+  Noise,
+  Marble,
+  Wood,
+  Gradient,
+  UvGrid,
+}
+
+pub const PROCEDURAL_KINDS: [ProceduralKind; 6] = [
+  ProceduralKind::Checker,
+  ProceduralKind::Noise,
+  ProceduralKind::Marble,
+  ProceduralKind::Wood,
+  ProceduralKind::Gradient,
+  ProceduralKind::UvGrid,
+];
+
+impl ProceduralKind {
+  pub fn from_name(name: &str) -> Option<ProceduralKind> {
+    PROCEDURAL_KINDS.iter().copied().find(|kind| kind.name() == name)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      ProceduralKind::Checker => "checker",
+      ProceduralKind::Noise => "noise",
+      ProceduralKind::Marble => "marble",
+      ProceduralKind::Wood => "wood",
+      ProceduralKind::Gradient => "gradient",
+      ProceduralKind::UvGrid => "uv_grid",
+    }
+  }
+
+  // pattern with the default colors, the scale sets the pattern frequency
+  pub fn create(&self, scale: f32) -> Rc<dyn TextureSource> {
+    let white = Color::new(1.0, 1.0, 1.0);
+
+    match self {
+      ProceduralKind::Checker => Rc::new(Checker {
+        space: Space::Uv,
+        scale,
+        colors: [white, Color::new(0.2, 0.2, 0.2)],
+      }),
+      ProceduralKind::Noise => Rc::new(Noise {
+        space: Space::World,
+        scale,
+        octaves: 5,
+        colors: [Color::new(0.1, 0.1, 0.1), white],
+      }),
+      ProceduralKind::Marble => Rc::new(Marble {
+        space: Space::World,
+        scale,
+        turbulence: 4.0,
+        colors: [Color::new(0.35, 0.35, 0.4), Color::new(0.95, 0.95, 0.92)],
+      }),
+      ProceduralKind::Wood => Rc::new(Wood {
+        space: Space::World,
+        scale,
+        turbulence: 0.4,
+        colors: [Color::new(0.8, 0.55, 0.3), Color::new(0.45, 0.25, 0.1)],
+      }),
+      ProceduralKind::Gradient => Rc::new(Gradient {
+        space: Space::Uv,
+        direction: Vector3::new(0.0, scale, 0.0),
+        colors: [Color::new(0.1, 0.2, 0.6), white],
+      }),
+      ProceduralKind::UvGrid => Rc::new(UvGrid {
+        cells: scale.max(1.0) as u32,
+        line_width: 0.05,
+        line_color: white,
+      }),
+    }
+  }
+
+  // "marble" or "marble 4", the scale is 1 unless it's given
+  pub fn parse(spec: &str) -> Option<(ProceduralKind, f32)> {
+    let mut parts = spec.split_whitespace();
+    let kind = ProceduralKind::from_name(parts.next()?)?;
+
+    let scale = match parts.next() {
+      Some(scale) => scale.parse::<f32>().ok().filter(|scale| *scale > 0.0 && scale.is_finite())?,
+      None => 1.0,
+    };
+
+    if parts.next().is_some() {
+      return None;
+    }
+
+    Some((kind, scale))
+  }
+}
+
+fn mix(colors: &[Color; 2], value: f32) -> Color {
+  let value = value.clamp(0.0, 1.0);
+  &colors[0] * (1.0 - value) + &colors[1] * value
+}
+
+// gradient of the lattice point, one of the 12 directions to the cube edges
+fn lattice_gradient(x: i32, y: i32, z: i32) -> Vector3 {
+  const GRADIENTS: [(f32, f32, f32); 12] = [
+    (1.0, 1.0, 0.0), (-1.0, 1.0, 0.0), (1.0, -1.0, 0.0), (-1.0, -1.0, 0.0),
+    (1.0, 0.0, 1.0), (-1.0, 0.0, 1.0), (1.0, 0.0, -1.0), (-1.0, 0.0, -1.0),
+    (0.0, 1.0, 1.0), (0.0, -1.0, 1.0), (0.0, 1.0, -1.0), (0.0, -1.0, -1.0),
+  ];
+
+  // every coordinate keeps its own 21 bits, so the negative ones don't overwrite the others
+  let bits = |value: i32| value as u32 as u64 & 0x1F_FFFF;
+  let key = bits(x) | bits(y) << 21 | bits(z) << 42;
+  let (gx, gy, gz) = GRADIENTS[(hash(key) % 12) as usize];
+  Vector3::new(gx, gy, gz)
+}
+
+// Perlin gradient noise in range [-1, 1]
+pub fn perlin(point: &Vector3) -> f32 {
+  let (fx, fy, fz) = (point.x.floor(), point.y.floor(), point.z.floor());
+  let (x, y, z) = (fx as i32, fy as i32, fz as i32);
+  let local = Vector3::new(point.x - fx, point.y - fy, point.z - fz);
+  let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+  let (u, v, w) = (fade(local.x), fade(local.y), fade(local.z));
+  let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+  let corner = |dx: i32, dy: i32, dz: i32| {
+    let offset = Vector3::new(local.x - dx as f32, local.y - dy as f32, local.z - dz as f32);
+    lattice_gradient(x + dx, y + dy, z + dz) * offset
+  };
+
+  let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+  let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+  let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+  let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+
+  lerp(lerp(x00, x10, v), lerp(x01, x11, v), w).clamp(-1.0, 1.0)
+}
+
+// the octaves are normalized, so the sum stays in range [-1, 1]
+pub fn fbm(point: &Vector3, octaves: u32) -> f32 {
+  octave_sum(point, octaves, perlin)
+}
+
+// sum of the absolute noise octaves in range [0, 1], gives the sharp creases of the marble veins
+pub fn turbulence(point: &Vector3, octaves: u32) -> f32 {
+  octave_sum(point, octaves, |point| perlin(point).abs())
+}
+
+fn octave_sum(point: &Vector3, octaves: u32, noise: impl Fn(&Vector3) -> f32) -> f32 {
+  let mut sum = 0.0;
+  let mut amplitude = 1.0;
+  let mut amplitude_sum = 0.0;
+  let mut frequency = 1.0;
+
+  for _ in 0..u32::max(octaves, 1) {
+    sum += amplitude * noise(&(point * frequency));
+    amplitude_sum += amplitude;
+    amplitude *= 0.5;
+    frequency *= 2.0;
+  }
+
+  sum / amplitude_sum
+}
@@ -0,0 +1,65 @@
+use super::Trace;
+use super::material::fresnel_dielectric;
+use super::math::constants::DELTA;
+use super::math::{Rnd, Vector3};
+
+// the light reflected inside more times is absorbed
+const MAX_INTERNAL_REFLECTIONS: u32 = 8;
+
+// point where the refracted light leaves the object and its normalized direction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Passage {
+  pub point: Vector3,
+  pub direction: Vector3,
+}
+
+// direction of the normalized direction refracted by the surface with the normalized normal facing it,
+// eta is the ratio of the IOR of the coming side to the IOR of the other side, none for the total internal reflection
+pub fn refract(direction: &Vector3, norm: &Vector3, eta: f32) -> Option<Vector3> {
+  let cos = -(direction * norm);
+  let k = 1.0 - eta * eta * (1.0 - cos * cos);
+
+  if k < 0.0 {
+    return None;
+  }
+
+  Some(direction * eta + norm * (eta * cos - k.sqrt()))
+}
+
+// light entering the object at the surface point with the normalized outward normal, it's refracted into the object
+// and reflected inside by the Fresnel share until it leaves, the open objects like the triangles are passed straight,
+// none if the light doesn't leave
+pub fn trace_through(object: &dyn Trace, drop: &Vector3, norm: &Vector3, direction: &Vector3, ior: f32,
+                     rnd: &Rnd) -> Option<Passage>
+{
+  let direction = direction.normalized();
+  let straight = Passage { point: drop.clone(), direction: direction.clone() };
+
+  let mut direction = match refract(&direction, norm, 1.0 / ior) {
+    Some(refracted) => refracted.normalized(),
+    None => return None,
+  };
+  let mut point = drop - norm * DELTA;
+
+  if object.trace_exit(&point, &direction).is_none() {
+    return Some(straight);
+  }
+
+  for _ in 0..MAX_INTERNAL_REFLECTIONS {
+    let (distance, exit_norm) = object.trace_exit(&point, &direction)?;
+    let exit = &point + &direction * distance;
+    // seen from the inside the object is the air and the air is the dielectric
+    let reflectance = fresnel_dielectric(&direction * &exit_norm, 1.0 / ior);
+
+    if rnd.next_f32() >= reflectance {
+      if let Some(refracted) = refract(&direction, &-&exit_norm, ior) {
+        return Some(Passage { point: exit + &exit_norm * DELTA, direction: refracted.normalized() });
+      }
+    }
+
+    direction = direction.reflected(&exit_norm).normalized();
+    point = exit - &exit_norm * DELTA;
+  }
+
+  None
+}
@@ -1,21 +1,28 @@
-use std::path::Path;
-use std::rc::Rc;
+use std::io::{Read, Write};
 
-use anyhow::Result;
+use anyhow::{Error, Result};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::math::{Matrix33, Vector3};
-use crate::render::{Camera, Color, Material, MaterialKind, Scene, Skybox, Texture};
+use crate::render::{
+  AdaptiveSampling, AovKind, Camera, Color, DebugMode, Denoiser, Filter, FILTER_KINDS, FirstHit, Guide,
+  MAX_FILTER_RADIUS, PickInfo, SampleId, Sampler, SamplerKind, SampleStream, SAMPLER_KINDS, Scene, SKY_DEPTH, Texture,
+};
+
+use super::adaptive::heatmap_color;
+use super::aov::{depth_color, id_color, normal_color, position_color, AovPixel};
+use super::pixel::Pixel;
+use super::sampler::DIM_PIXEL;
+
+const STATE_SIGNATURE: u32 = 0x5053_4D52; // "RMSP"
+const STATE_VERSION: u32 = 8;
+// the largest width and height of the rendered image
+pub const MAX_IMAGE_SIZE: u32 = 16384;
+// the largest samples count per pixel side, the negative counts are the downsampled previews
+pub const MAX_SAMPLES: i32 = 1024;
 
-use super::math::constants::FAST_RAND_MAX;
-use super::math::Rnd;
-
-thread_local! {
-  pub static RND: Rc<Rnd> = Rc::new(Rnd::new());
-}
-
-#[derive(Default)]
 pub struct Render {
-  image: Vec<Color>,
+  image: Vec<Pixel>,
   cur_x: u32,
   cur_y: u32,
   max_reflections: u32,
@@ -23,6 +30,17 @@ pub struct Render {
   is_additive: bool,
   camera_view: Matrix33,
   camera_eye: Vector3,
+  camera_fov: f32,
+  sampler: Box<dyn Sampler>,
+  sampler_kind: SamplerKind,
+  max_pixel_samples: u32,
+  pass: u32,
+  denoiser: Option<Denoiser>,
+  // denoised image of the last completed frame
+  denoised: Vec<Color>,
+  // AOV buffer of the same size as the image, empty when the AOVs are disabled
+  aovs: Vec<AovPixel>,
+  is_aov_enabled: bool,
 
   pub camera: Camera,
   pub scene: Scene,
@@ -30,6 +48,21 @@ pub struct Render {
   pub image_height: u32,
   pub additive_counter: i32,
   pub is_complete: bool,
+  // the same seed and render parameters give the same image
+  pub seed: u64,
+  // samples are spent only on the noisy pixels when set
+  pub adaptive: Option<AdaptiveSampling>,
+  pub filter: Filter,
+  // debug shading replaces the full lighting when set
+  pub debug_mode: Option<DebugMode>,
+  // the paths carry single wavelengths instead of the RGB colors
+  pub spectral: bool,
+}
+
+impl Default for Render {
+  fn default() -> Render {
+    Render::new()
+  }
 }
 
 impl Render {
@@ -43,115 +76,82 @@ impl Render {
       is_additive: false,
       camera_view: Matrix33::default(),
       camera_eye: Vector3::default(),
+      camera_fov: 0.0,
+      sampler: SamplerKind::Sobol.create(),
+      sampler_kind: SamplerKind::Sobol,
+      max_pixel_samples: 0,
+      pass: 0,
+      denoiser: None,
+      denoised: Vec::new(),
+      aovs: Vec::new(),
+      is_aov_enabled: false,
       camera: Camera::default(),
       scene: Scene::default(),
       image_width: 0,
       image_height: 0,
       additive_counter: 0,
       is_complete: false,
+      seed: 0,
+      adaptive: None,
+      filter: Filter::default(),
+      debug_mode: None,
+      spectral: false,
     }
   }
 
-  pub fn init_scene(&mut self, root_path: &Path) -> Result<()> {
-    let skybox_texture_path = root_path.join("textures/skybox.tga");
-    let periodic_texture_path = root_path.join("textures/periodic.tga");
-    let skybox_texture = Texture::load_from_file(&skybox_texture_path)?;
-    let periodic_texture = Rc::new(Texture::load_from_file(&periodic_texture_path)?);
-
-    let eye_pos = Vector3::new(7.427, 3.494, -3.773);
-    let look_at = Vector3::new(6.5981, 3.127, -3.352);
-    self.camera = Camera::new(eye_pos, look_at, 1.05);
-    let skybox = Skybox::new(skybox_texture);
-    let diffuse_light_color = Color::new(0.95, 0.95, 1.0);
-    let diffuse_light_power = 0.15;
-    let mut scene = Scene::new(skybox, diffuse_light_color, diffuse_light_power);
-
-    scene.add_spot_light(Vector3::new(11.8e9, 4.26e9, 3.08e9), 3.48e8, Color::new(1.0, 1.0, 0.95), 0.85);
-    //scene.addLight(Vector3::new(-1.26e9, 11.8e9, 1.08e9), 6.96e8, Color::new(1.0, 0.5, 0.5), 0.2);
-    //scene.addLight(Vector3::new(11.8e9, 4.26e9, 3.08e9), 6.96e9, Color::new(1.0, 1.0, 0.95), 0.85);
-
-    scene.add_sphere(Vector3::new(-1.25, 1.5, -0.25), 1.5, Material::new(MaterialKind::Metal, Color::new(1.0, 1.0, 1.0), 1.0, 0.0));
-    scene.add_sphere(Vector3::new(0.15, 1.0, 1.75), 1.0, Material::new(MaterialKind::Metal, Color::new(1.0, 1.0, 1.0), 0.95, 0.0));
-
-    scene.add_sphere(Vector3::new(-3.0, 0.6, -3.0), 0.6, Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.0, 0.0));
-    scene.add_sphere(Vector3::new(-0.5, 0.5, -2.5), 0.5, Material::new(MaterialKind::Dielectric, Color::new(0.5, 1.0, 0.15), 0.75, 0.0));
-    scene.add_sphere(Vector3::new(1.0, 0.4, -1.5), 0.4, Material::new(MaterialKind::Dielectric, Color::new(0.0, 0.5, 1.0), 1.0, 0.0));
-
-    scene.add_sphere(Vector3::new(1.8, 0.4, 0.1), 0.4, Material::new(MaterialKind::Metal, Color::new(1.0, 0.65, 0.45), 1.0, 0.0));
-    scene.add_sphere(Vector3::new(1.7, 0.5, 1.9), 0.5, Material::new(MaterialKind::Metal, Color::new(1.0, 0.90, 0.60), 0.75, 0.0));
-    scene.add_sphere(Vector3::new(0.6, 0.6, 4.2), 0.6, Material::new(MaterialKind::Metal, Color::new(0.9, 0.9, 0.9), 0.0, 0.0));
-
-    let plane_vertices = (
-      Vector3::new(-14.0, 0.0, -10.0),
-      Vector3::new(-14.0, 0.0, 10.0),
-      Vector3::new(14.0, 0.0, 10.0),
-      Vector3::new(14.0, 0.0, -10.0),
-    );
-
-    let plane_uvs = (
-      (0.0, 0.0),
-      (0.0, 1.0),
-      (1.0, 1.0),
-      (1.0, 0.0),
-    );
-
-    scene.add_triangle([
-      &plane_vertices.0,
-      &plane_vertices.1,
-      &plane_vertices.3,
-    ], Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.95, 0.0),
-      Some((
-        Rc::clone(&periodic_texture), [
-          plane_uvs.0,
-          plane_uvs.1,
-          plane_uvs.3,
-        ])),
-    );
-
-    scene.add_triangle([
-      &plane_vertices.1,
-      &plane_vertices.2,
-      &plane_vertices.3,
-    ], Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.95, 0.0),
-      Some((
-        Rc::clone(&periodic_texture), [
-          plane_uvs.1,
-          plane_uvs.2,
-          plane_uvs.3,
-        ])),
-    );
-
-    self.scene = scene;
-
-    Ok(())
-  }
-
   pub fn resize_image(&mut self, width: u32, height: u32) {
     assert!(width > 0, "Invalid argument");
     assert!(height > 0, "Invalid argument");
 
-    let new_size = (width * height) as usize;
-    const ZERO_COLOR: Color = Color { r: 0.0, g: 0.0, b: 0.0 };
+    let new_size = width as usize * height as usize;
 
     if new_size > self.image.len() {
-      self.image.resize(new_size, ZERO_COLOR);
+      self.image.resize(new_size, Pixel::default());
+    }
+
+    if self.is_aov_enabled {
+      self.aovs.resize(self.image.len(), AovPixel::default());
     }
 
     self.image_width = width;
     self.image_height = height;
     self.additive_counter = 0;
+    self.max_pixel_samples = 0;
+    self.denoised.clear();
     self.is_complete = true;
     self.cur_x = 0;
     self.cur_y = 0;
   }
 
+  // whole image in a single call for the tools that embed the tracer without the interactive viewer
+  pub fn render_image(&mut self, width: u32, height: u32, reflections: u32, samples: i32) -> Result<Texture> {
+    if width == 0 || height == 0 {
+      return Err(Error::msg("Image size should be positive"));
+    }
+
+    self.resize_image(width, height);
+    self.begin_render(reflections, samples, false);
+
+    while !self.render(width * height)? {}
+
+    Ok(self.to_texture())
+  }
+
   pub fn to_texture(&self) -> Texture {
     assert!(self.image_width > 0);
     assert!(self.image_height > 0);
 
+    let colors = (0..self.image_height)
+      .flat_map(|y| (0..self.image_width).map(move |x| (x, y)))
+      .map(|(x, y)| self.get_pixel(x, y));
+
+    self.colors_to_texture(colors)
+  }
+
+  fn colors_to_texture(&self, colors: impl Iterator<Item = Color>) -> Texture {
     let size = self.image_width as usize * self.image_height as usize * 3;
     let mut color_buffer = Vec::with_capacity(size);
-    self.image.iter().for_each(|c| color_buffer.extend(&c.rgb()));
+    colors.for_each(|color| color_buffer.extend(&color.rgb()));
 
     Texture {
       width: self.image_width,
@@ -160,18 +160,155 @@ impl Render {
     }
   }
 
+  // AOV buffer is allocated for the following renders, depth, normal and albedo are always available
+  pub fn set_aov_enabled(&mut self, is_enabled: bool) {
+    self.is_aov_enabled = is_enabled;
+
+    if is_enabled {
+      self.aovs.resize(self.image.len(), AovPixel::default());
+    } else {
+      self.aovs = Vec::new();
+    }
+  }
+
+  pub fn is_aov_enabled(&self) -> bool {
+    self.is_aov_enabled
+  }
+
+  // image of the AOV for export, values out of [0, 1] are scaled to the range of the whole image
+  pub fn get_aov_texture(&self, kind: AovKind) -> Option<Texture> {
+    assert!(self.image_width > 0);
+    assert!(self.image_height > 0);
+
+    let size = self.image_width as usize * self.image_height as usize;
+    let pixels = &self.image[..size];
+
+    let colors: Vec<Color> = match kind {
+      AovKind::Depth => {
+        let max_depth = pixels.iter()
+          .map(|pixel| pixel.get_depth())
+          .filter(|&depth| depth < SKY_DEPTH)
+          .fold(0.0, f32::max);
+
+        pixels.iter().map(|pixel| depth_color(pixel.get_depth(), max_depth)).collect()
+      }
+      AovKind::Normal => pixels.iter().map(|pixel| normal_color(&pixel.get_normal())).collect(),
+      AovKind::Albedo => pixels.iter().map(|pixel| pixel.get_albedo()).collect(),
+      _ if !self.is_aov_enabled => return None,
+      AovKind::Position => {
+        let aovs = &self.aovs[..size];
+        let mut min = Vector3::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Vector3::new(f32::MIN, f32::MIN, f32::MIN);
+
+        for position in aovs.iter().filter(|aov| aov.object_id != 0).map(|aov| aov.get_position()) {
+          min = Vector3::new(f32::min(min.x, position.x), f32::min(min.y, position.y), f32::min(min.z, position.z));
+          max = Vector3::new(f32::max(max.x, position.x), f32::max(max.y, position.y), f32::max(max.z, position.z));
+        }
+
+        aovs.iter().map(|aov| position_color(&aov.get_position(), &min, &max)).collect()
+      }
+      AovKind::ObjectId => self.aovs[..size].iter().map(|aov| id_color(aov.object_id)).collect(),
+      AovKind::MaterialId => self.aovs[..size].iter().map(|aov| id_color(aov.material_id)).collect(),
+      AovKind::Direct => self.aovs[..size].iter().map(|aov| aov.get_direct()).collect(),
+      AovKind::Indirect => self.aovs[..size].iter().map(|aov| aov.get_indirect()).collect(),
+      AovKind::Shadow => self.aovs[..size].iter()
+        .map(|aov| Color::new(aov.get_shadow(), aov.get_shadow(), aov.get_shadow()))
+        .collect(),
+    };
+
+    Some(self.colors_to_texture(colors.into_iter()))
+  }
+
   pub fn get_pixel(&self, x: u32, y: u32) -> Color {
     assert!(x < self.image_width, "Invalid argument");
     assert!(y < self.image_height, "Invalid argument");
 
     let idx = x as usize + y as usize * self.image_width as usize;
-    let mut color = self.image[idx].clone();
 
-    if self.additive_counter > 1 {
-      color /= self.additive_counter as f32;
+    if !self.denoised.is_empty() {
+      return self.denoised[idx].clone();
     }
 
-    color
+    self.image[idx].get_color()
+  }
+
+  pub fn get_denoiser(&self) -> Option<Denoiser> {
+    self.denoiser
+  }
+
+  // the denoised image is shown after the frame is completed, until then the previous one stays
+  pub fn set_denoiser(&mut self, denoiser: Option<Denoiser>) {
+    self.denoiser = denoiser;
+    self.denoised.clear();
+
+    if self.is_complete {
+      self.update_denoised();
+    }
+  }
+
+  fn update_denoised(&mut self) {
+    let denoiser = match self.denoiser {
+      Some(denoiser) if self.image_width > 0 && self.image_height > 0 => denoiser,
+      _ => return,
+    };
+
+    let size = (self.image_width * self.image_height) as usize;
+    let pixels = &self.image[..size];
+    let color: Vec<Color> = pixels.iter().map(|pixel| pixel.get_color()).collect();
+    let albedo: Vec<Color> = pixels.iter().map(|pixel| pixel.get_albedo()).collect();
+    let normal: Vec<Vector3> = pixels.iter().map(|pixel| pixel.get_normal()).collect();
+    let depth: Vec<f32> = pixels.iter().map(|pixel| pixel.get_depth()).collect();
+    let guide = Guide { albedo: &albedo, normal: &normal, depth: &depth };
+
+    self.denoised = denoiser.denoise(self.image_width, self.image_height, &color, &guide);
+  }
+
+  pub fn get_pixel_samples(&self, x: u32, y: u32) -> u32 {
+    assert!(x < self.image_width, "Invalid argument");
+    assert!(y < self.image_height, "Invalid argument");
+
+    self.image[x as usize + y as usize * self.image_width as usize].samples
+  }
+
+  // sample count of the pixel relative to the most sampled pixel of the current render
+  pub fn get_sample_heatmap(&self, x: u32, y: u32) -> Color {
+    let samples = self.get_pixel_samples(x, y);
+    heatmap_color(samples as f32 / u32::max(self.max_pixel_samples, 1) as f32)
+  }
+
+  // traces the ray through the pixel center with the current camera, so the picked object matches the view
+  pub fn pick(&self, x: u32, y: u32) -> Result<Option<PickInfo>> {
+    assert!(x < self.image_width, "Invalid argument");
+    assert!(y < self.image_height, "Invalid argument");
+
+    let rx = x as f32 + 0.5 - self.image_width as f32 / 2.0;
+    let ry = y as f32 + 0.5 - self.image_height as f32 / 2.0;
+    let rz = self.image_width as f32 / 2.0 / f32::tan(self.camera.fov / 2.0);
+    let ray = &self.camera.view * Vector3::new(rx, ry, rz);
+
+    self.scene.pick(&self.camera.eye, &ray)
+  }
+
+  pub fn set_sampler(&mut self, kind: SamplerKind) {
+    if kind != self.sampler_kind {
+      self.sampler = kind.create();
+      self.sampler_kind = kind;
+    }
+  }
+
+  pub fn get_sampler(&self) -> SamplerKind {
+    self.sampler_kind
+  }
+
+  fn sample_id(&self, index: u32, count: u32) -> SampleId {
+    SampleId {
+      seed: self.seed,
+      frame: self.additive_counter as u32,
+      x: self.cur_x,
+      y: self.cur_y,
+      index,
+      count,
+    }
   }
 
   pub fn begin_render(&mut self, reflections: u32, samples: i32, is_additive: bool) {
@@ -186,12 +323,19 @@ impl Render {
     self.cur_y = 0;
     self.camera_view = self.camera.view.clone();
     self.camera_eye = self.camera.eye.clone();
+    self.camera_fov = self.camera.fov;
 
     if is_additive {
       self.additive_counter += 1;
     } else {
       self.additive_counter = 0;
     }
+
+    // a new accumulation starts unless the frame is blended with the previous ones
+    if self.additive_counter <= 1 {
+      self.max_pixel_samples = 0;
+      self.pass = self.pass.wrapping_add(1);
+    }
   }
 
   pub fn render(&mut self, pixels: u32) -> Result<bool> {
@@ -201,9 +345,8 @@ impl Render {
     assert!(self.cur_y < self.image_height, "Invalid state");
 
     let mut pixels = pixels;
-    let origin = &self.camera_eye;
     let sq_samples = i32::pow(self.samples, 2);
-    let rz = self.image_width as f32 / 2.0 / f32::tan(&self.camera.fov / 2.0);
+    let rz = self.image_width as f32 / 2.0 / f32::tan(self.camera_fov / 2.0);
     let image_width_half = self.image_width as f32 / 2.0;
     let image_height_half = self.image_height as f32 / 2.0;
 
@@ -219,45 +362,64 @@ impl Render {
           self.cur_y % down_samples == 0
         {
           ray = &self.camera_view * ray;
-          let traced_color = self.scene.trace(origin, &ray, self.max_reflections)?;
+          let mut samples = SampleStream::new(self.sampler.as_ref(), self.sample_id(0, 1));
+          let mut first_hit = FirstHit::default();
+          let traced_color = self.trace_ray(&ray, &mut samples, &mut first_hit)?;
           let end_qx = u32::min(self.image_width, self.cur_x + down_samples);
           let end_qy = u32::min(self.image_height, self.cur_y + down_samples);
 
           for qx in self.cur_x..end_qx {
             for qy in self.cur_y..end_qy {
               let idx = (qx + qy * self.image_width) as usize;
-              self.image[idx] = traced_color.clone();
+              self.touch_pixel(idx).set_color(&traced_color, &first_hit);
+
+              if let Some(aov) = self.aovs.get_mut(idx) {
+                *aov = AovPixel::default();
+                aov.add_sample(&traced_color, &first_hit);
+              }
             }
           }
         }
       } else {
-        let mut fin_color: Color;
-        let rnd = RND.with(|r| Rc::clone(r));
-        let rnd_x = if self.is_additive { rnd.fastrand() as f32 / FAST_RAND_MAX as f32 } else { 0.0 };
-        let rnd_y = if self.is_additive { rnd.fastrand() as f32 / FAST_RAND_MAX as f32 } else { 0.0 };
-        fin_color = Color::new(0.0, 0.0, 0.0);
-
-        for ssx in 0..self.samples {
-          for ssy in 0..self.samples {
-            let mut ray = Vector3::new(
-              rx + ssx as f32 / self.samples as f32 + rnd_x,
-              ry + ssy as f32 / self.samples as f32 + rnd_y,
-              rz,
-            );
-
-            ray = &self.camera_view * &ray;
-            fin_color += self.scene.trace(origin, &ray, self.max_reflections)?;
-          }
-        }
-
-        fin_color /= sq_samples as f32;
         let idx = (self.cur_x + self.cur_y * self.image_width) as usize;
+        let frame_samples = sq_samples as u32;
+        self.touch_pixel(idx);
+
+        match self.adaptive {
+          // converged pixels are skipped in the blended frames
+          Some(adaptive) if self.is_additive => {
+            if !adaptive.is_converged(&self.image[idx]) {
+              for index in 0..frame_samples {
+                self.sample_pixel(index, frame_samples, true)?;
+              }
+            }
+          }
+          // single frame is sampled in batches until the pixel reaches the noise level
+          Some(adaptive) => {
+            let mut index = 0;
+
+            loop {
+              for _ in 0..frame_samples {
+                self.sample_pixel(index, adaptive.max_samples, true)?;
+                index += 1;
+              }
+
+              if adaptive.is_converged(&self.image[idx]) {
+                break;
+              }
+            }
+          }
+          None => {
+            // a single static sample stays in the pixel corner, so the interactive image does not flicker
+            let is_jittered = self.is_additive || self.samples > 1;
 
-        if self.additive_counter > 1 {
-          self.image[idx] += fin_color;
-        } else {
-          self.image[idx] = fin_color;
+            for index in 0..frame_samples {
+              self.sample_pixel(index, frame_samples, is_jittered)?;
+            }
+          }
         }
+
+        self.max_pixel_samples = u32::max(self.max_pixel_samples, self.image[idx].samples);
       }
 
       pixels -= 1;
@@ -270,6 +432,7 @@ impl Render {
 
       if self.cur_y == self.image_height {
         self.is_complete = true;
+        self.update_denoised();
         break;
       }
     }
@@ -277,6 +440,332 @@ impl Render {
     Ok(self.is_complete)
   }
 
+  // pixel left from the previous render pass is cleared before the first sample gets into it
+  fn touch_pixel(&mut self, idx: usize) -> &mut Pixel {
+    let pixel = &mut self.image[idx];
+
+    if pixel.pass != self.pass {
+      pixel.reset();
+      pixel.pass = self.pass;
+
+      if let Some(aov) = self.aovs.get_mut(idx) {
+        *aov = AovPixel::default();
+      }
+    }
+
+    &mut self.image[idx]
+  }
+
+  fn sample_pixel(&mut self, index: u32, count: u32, is_jittered: bool) -> Result<()> {
+    // every sample has its own sequence, so the result does not depend on the render order
+    let mut samples = SampleStream::new(self.sampler.as_ref(), self.sample_id(index, count));
+    samples.set_dimension(DIM_PIXEL);
+    let (offset_x, offset_y) = if is_jittered { samples.next_2d() } else { (0.0, 0.0) };
+    let rx = self.cur_x as f32 - self.image_width as f32 / 2.0 + offset_x;
+    let ry = self.cur_y as f32 - self.image_height as f32 / 2.0 + offset_y;
+    let rz = self.image_width as f32 / 2.0 / f32::tan(self.camera_fov / 2.0);
+    let ray = &self.camera_view * Vector3::new(rx, ry, rz);
+    let mut first_hit = FirstHit::default();
+    let color = self.trace_ray(&ray, &mut samples, &mut first_hit)?;
+    let idx = (self.cur_x + self.cur_y * self.image_width) as usize;
+    self.touch_pixel(idx).add_sample(&color, &first_hit);
+
+    if let Some(aov) = self.aovs.get_mut(idx) {
+      aov.add_sample(&color, &first_hit);
+    }
+
+    if is_jittered {
+      self.splat(&color, self.cur_x as f32 + offset_x, self.cur_y as f32 + offset_y);
+    } else {
+      self.touch_pixel(idx).add_weighted(&color, 1.0);
+    }
+
+    Ok(())
+  }
+
+  fn trace_ray(&self, ray: &Vector3, samples: &mut SampleStream, first_hit: &mut FirstHit) -> Result<Color> {
+    let color = match self.debug_mode {
+      Some(mode) => self.scene.trace_debug(&self.camera_eye, ray, self.max_reflections, samples, mode),
+      None if self.spectral => self.scene.trace_spectral(&self.camera_eye, ray, self.max_reflections, samples, Some(first_hit)),
+      None => self.scene.trace(&self.camera_eye, ray, self.max_reflections, samples, Some(first_hit)),
+    }?;
+
+    // the depth is the distance along the forward axis of the camera, not along the ray
+    if first_hit.depth < SKY_DEPTH {
+      first_hit.depth *= ray * &self.camera_view.get_col(2) / ray.length();
+    }
+
+    Ok(color)
+  }
+
+  // adds the sample to every pixel within the filter radius, pixel centers are at half-integer coordinates
+  fn splat(&mut self, color: &Color, x: f32, y: f32) {
+    let radius = self.filter.radius;
+    let min_x = f32::max(0.0, (x - radius - 0.5).ceil()) as u32;
+    let max_x = f32::min(self.image_width as f32 - 1.0, (x + radius - 0.5).floor()) as u32;
+    let min_y = f32::max(0.0, (y - radius - 0.5).ceil()) as u32;
+    let max_y = f32::min(self.image_height as f32 - 1.0, (y + radius - 0.5).floor()) as u32;
+
+    for py in min_y..=max_y {
+      for px in min_x..=max_x {
+        let weight = self.filter.weight(px as f32 + 0.5 - x, py as f32 + 0.5 - y);
+
+        if weight != 0.0 {
+          let idx = (px + py * self.image_width) as usize;
+          self.touch_pixel(idx).add_weighted(color, weight);
+        }
+      }
+    }
+  }
+
+  // position of the next pixel to render, rows are rendered from the bottom of the image
+  pub fn get_cursor(&self) -> (u32, u32) {
+    (self.cur_x, self.cur_y)
+  }
+
+  pub fn is_pixel_rendered(&self, x: u32, y: u32) -> bool {
+    self.is_complete || y < self.cur_y || (y == self.cur_y && x < self.cur_x)
+  }
+
+  pub fn get_samples(&self) -> i32 {
+    self.samples
+  }
+
+  pub fn get_max_reflections(&self) -> u32 {
+    self.max_reflections
+  }
+
+  // saves the unfinished render progress to continue it later with the load_state
+  pub fn save_state(&self, mut stream: impl Write) -> Result<()> {
+    stream.write_u32::<LittleEndian>(STATE_SIGNATURE)?;
+    stream.write_u32::<LittleEndian>(STATE_VERSION)?;
+    stream.write_u32::<LittleEndian>(self.image_width)?;
+    stream.write_u32::<LittleEndian>(self.image_height)?;
+    stream.write_u32::<LittleEndian>(self.cur_x)?;
+    stream.write_u32::<LittleEndian>(self.cur_y)?;
+    stream.write_u32::<LittleEndian>(self.max_reflections)?;
+    stream.write_i32::<LittleEndian>(self.samples)?;
+    stream.write_u8(self.is_additive as u8)?;
+    stream.write_u8(self.is_complete as u8)?;
+    stream.write_i32::<LittleEndian>(self.additive_counter)?;
+    stream.write_u64::<LittleEndian>(self.seed)?;
+    stream.write_u8(SAMPLER_KINDS.iter().position(|&kind| kind == self.sampler_kind).unwrap() as u8)?;
+    stream.write_u8(self.adaptive.is_some() as u8)?;
+
+    if let Some(adaptive) = &self.adaptive {
+      stream.write_f32::<LittleEndian>(adaptive.threshold)?;
+      stream.write_u32::<LittleEndian>(adaptive.min_samples)?;
+      stream.write_u32::<LittleEndian>(adaptive.max_samples)?;
+    }
+
+    stream.write_u8(FILTER_KINDS.iter().position(|&kind| kind == self.filter.kind).unwrap() as u8)?;
+    stream.write_f32::<LittleEndian>(self.filter.radius)?;
+    stream.write_u8(self.denoiser.is_some() as u8)?;
+
+    if let Some(denoiser) = &self.denoiser {
+      stream.write_u32::<LittleEndian>(denoiser.iterations)?;
+      stream.write_f32::<LittleEndian>(denoiser.color_sigma)?;
+      stream.write_f32::<LittleEndian>(denoiser.albedo_sigma)?;
+      stream.write_f32::<LittleEndian>(denoiser.normal_sigma)?;
+      stream.write_f32::<LittleEndian>(denoiser.depth_sigma)?;
+    }
+
+    stream.write_u8(self.is_aov_enabled as u8)?;
+    stream.write_u8(self.spectral as u8)?;
+
+    for value in self.camera_view.iter() {
+      stream.write_f32::<LittleEndian>(*value)?;
+    }
+
+    stream.write_f32::<LittleEndian>(self.camera_eye.x)?;
+    stream.write_f32::<LittleEndian>(self.camera_eye.y)?;
+    stream.write_f32::<LittleEndian>(self.camera_eye.z)?;
+    stream.write_f32::<LittleEndian>(self.camera_fov)?;
+    stream.write_f32::<LittleEndian>(self.camera.yaw)?;
+    stream.write_f32::<LittleEndian>(self.camera.pitch)?;
+
+    let size = self.image_width as usize * self.image_height as usize;
+
+    for pixel in &self.image[..size] {
+      stream.write_f32::<LittleEndian>(pixel.color_sum.r)?;
+      stream.write_f32::<LittleEndian>(pixel.color_sum.g)?;
+      stream.write_f32::<LittleEndian>(pixel.color_sum.b)?;
+      stream.write_f32::<LittleEndian>(pixel.weight_sum)?;
+      stream.write_u32::<LittleEndian>(pixel.samples)?;
+      stream.write_f32::<LittleEndian>(pixel.mean)?;
+      stream.write_f32::<LittleEndian>(pixel.m2)?;
+      stream.write_f32::<LittleEndian>(pixel.albedo_sum.r)?;
+      stream.write_f32::<LittleEndian>(pixel.albedo_sum.g)?;
+      stream.write_f32::<LittleEndian>(pixel.albedo_sum.b)?;
+      stream.write_f32::<LittleEndian>(pixel.normal_sum.x)?;
+      stream.write_f32::<LittleEndian>(pixel.normal_sum.y)?;
+      stream.write_f32::<LittleEndian>(pixel.normal_sum.z)?;
+      stream.write_f32::<LittleEndian>(pixel.depth_sum)?;
+    }
+
+    if self.is_aov_enabled {
+      for aov in &self.aovs[..size] {
+        stream.write_f32::<LittleEndian>(aov.position_sum.x)?;
+        stream.write_f32::<LittleEndian>(aov.position_sum.y)?;
+        stream.write_f32::<LittleEndian>(aov.position_sum.z)?;
+        stream.write_f32::<LittleEndian>(aov.direct_sum.r)?;
+        stream.write_f32::<LittleEndian>(aov.direct_sum.g)?;
+        stream.write_f32::<LittleEndian>(aov.direct_sum.b)?;
+        stream.write_f32::<LittleEndian>(aov.indirect_sum.r)?;
+        stream.write_f32::<LittleEndian>(aov.indirect_sum.g)?;
+        stream.write_f32::<LittleEndian>(aov.indirect_sum.b)?;
+        stream.write_f32::<LittleEndian>(aov.shadow_sum)?;
+        stream.write_u32::<LittleEndian>(aov.object_id)?;
+        stream.write_u32::<LittleEndian>(aov.material_id)?;
+        stream.write_u32::<LittleEndian>(aov.samples)?;
+      }
+    }
+
+    stream.flush()?;
+
+    Ok(())
+  }
+
+  pub fn load_state(&mut self, mut stream: impl Read) -> Result<()> {
+    if stream.read_u32::<LittleEndian>()? != STATE_SIGNATURE {
+      return Err(Error::msg("Invalid render state signature"));
+    }
+
+    if stream.read_u32::<LittleEndian>()? != STATE_VERSION {
+      return Err(Error::msg("Unsupported render state version"));
+    }
+
+    let width = stream.read_u32::<LittleEndian>()?;
+    let height = stream.read_u32::<LittleEndian>()?;
+    let cur_x = stream.read_u32::<LittleEndian>()?;
+    let cur_y = stream.read_u32::<LittleEndian>()?;
+    let max_reflections = stream.read_u32::<LittleEndian>()?;
+    let samples = stream.read_i32::<LittleEndian>()?;
+    let is_additive = stream.read_u8()? != 0;
+    let is_complete = stream.read_u8()? != 0;
+    let additive_counter = stream.read_i32::<LittleEndian>()?;
+    let seed = stream.read_u64::<LittleEndian>()?;
+    let sampler_kind = *SAMPLER_KINDS.get(stream.read_u8()? as usize)
+      .ok_or_else(|| Error::msg("Invalid sampler"))?;
+
+    let adaptive = if stream.read_u8()? != 0 {
+      Some(AdaptiveSampling {
+        threshold: stream.read_f32::<LittleEndian>()?,
+        min_samples: stream.read_u32::<LittleEndian>()?,
+        max_samples: stream.read_u32::<LittleEndian>()?,
+      })
+    } else {
+      None
+    };
+
+    let filter_kind = *FILTER_KINDS.get(stream.read_u8()? as usize)
+      .ok_or_else(|| Error::msg("Invalid filter"))?;
+    let filter_radius = stream.read_f32::<LittleEndian>()?;
+
+    if !(filter_radius > 0.0 && filter_radius <= MAX_FILTER_RADIUS) {
+      return Err(Error::msg("Invalid filter radius"));
+    }
+
+    let denoiser = if stream.read_u8()? != 0 {
+      Some(Denoiser {
+        iterations: stream.read_u32::<LittleEndian>()?,
+        color_sigma: stream.read_f32::<LittleEndian>()?,
+        albedo_sigma: stream.read_f32::<LittleEndian>()?,
+        normal_sigma: stream.read_f32::<LittleEndian>()?,
+        depth_sigma: stream.read_f32::<LittleEndian>()?,
+      })
+    } else {
+      None
+    };
+
+    let is_aov_enabled = stream.read_u8()? != 0;
+    let spectral = stream.read_u8()? != 0;
+
+    if width == 0 || height == 0 || width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE || width.checked_mul(height).is_none() ||
+      cur_x >= width || cur_y > height || (cur_y == height && !is_complete) || max_reflections == 0 || samples == 0 ||
+      samples.unsigned_abs() > MAX_SAMPLES as u32
+    {
+      return Err(Error::msg("Render state is corrupted"));
+    }
+
+    let mut view_values = [0.0; 9];
+    stream.read_f32_into::<LittleEndian>(&mut view_values)?;
+    let mut eye_values = [0.0; 3];
+    stream.read_f32_into::<LittleEndian>(&mut eye_values)?;
+    let fov = stream.read_f32::<LittleEndian>()?;
+    let yaw = stream.read_f32::<LittleEndian>()?;
+    let pitch = stream.read_f32::<LittleEndian>()?;
+
+    self.set_aov_enabled(is_aov_enabled);
+    self.resize_image(width, height);
+    let size = width as usize * height as usize;
+
+    // loaded pixels continue the accumulation of the saved render pass
+    self.pass = self.pass.wrapping_add(1);
+
+    for pixel in &mut self.image[..size] {
+      pixel.pass = self.pass;
+      pixel.color_sum.r = stream.read_f32::<LittleEndian>()?;
+      pixel.color_sum.g = stream.read_f32::<LittleEndian>()?;
+      pixel.color_sum.b = stream.read_f32::<LittleEndian>()?;
+      pixel.weight_sum = stream.read_f32::<LittleEndian>()?;
+      pixel.samples = stream.read_u32::<LittleEndian>()?;
+      pixel.mean = stream.read_f32::<LittleEndian>()?;
+      pixel.m2 = stream.read_f32::<LittleEndian>()?;
+      pixel.albedo_sum.r = stream.read_f32::<LittleEndian>()?;
+      pixel.albedo_sum.g = stream.read_f32::<LittleEndian>()?;
+      pixel.albedo_sum.b = stream.read_f32::<LittleEndian>()?;
+      pixel.normal_sum.x = stream.read_f32::<LittleEndian>()?;
+      pixel.normal_sum.y = stream.read_f32::<LittleEndian>()?;
+      pixel.normal_sum.z = stream.read_f32::<LittleEndian>()?;
+      pixel.depth_sum = stream.read_f32::<LittleEndian>()?;
+    }
+
+    if is_aov_enabled {
+      for aov in &mut self.aovs[..size] {
+        aov.position_sum.x = stream.read_f32::<LittleEndian>()?;
+        aov.position_sum.y = stream.read_f32::<LittleEndian>()?;
+        aov.position_sum.z = stream.read_f32::<LittleEndian>()?;
+        aov.direct_sum.r = stream.read_f32::<LittleEndian>()?;
+        aov.direct_sum.g = stream.read_f32::<LittleEndian>()?;
+        aov.direct_sum.b = stream.read_f32::<LittleEndian>()?;
+        aov.indirect_sum.r = stream.read_f32::<LittleEndian>()?;
+        aov.indirect_sum.g = stream.read_f32::<LittleEndian>()?;
+        aov.indirect_sum.b = stream.read_f32::<LittleEndian>()?;
+        aov.shadow_sum = stream.read_f32::<LittleEndian>()?;
+        aov.object_id = stream.read_u32::<LittleEndian>()?;
+        aov.material_id = stream.read_u32::<LittleEndian>()?;
+        aov.samples = stream.read_u32::<LittleEndian>()?;
+      }
+    }
+
+    self.max_pixel_samples = self.image[..size].iter().map(|pixel| pixel.samples).max().unwrap_or(0);
+
+    self.cur_x = cur_x;
+    self.cur_y = cur_y;
+    self.max_reflections = max_reflections;
+    self.samples = samples;
+    self.is_additive = is_additive;
+    self.is_complete = is_complete;
+    self.additive_counter = additive_counter;
+    self.seed = seed;
+    self.set_sampler(sampler_kind);
+    self.adaptive = adaptive;
+    self.spectral = spectral;
+    self.filter = Filter::new(filter_kind, filter_radius);
+    self.camera_view = Matrix33::from(view_values);
+    self.camera_eye = eye_values.iter().collect();
+    self.camera_fov = fov;
+    self.camera.eye = self.camera_eye.clone();
+    self.camera.view = self.camera_view.clone();
+    self.camera.fov = fov;
+    self.camera.yaw = yaw;
+    self.camera.pitch = pitch;
+    self.set_denoiser(denoiser);
+
+    Ok(())
+  }
+
   pub fn get_progress(&self) -> f32 {
     (self.cur_x + self.cur_y * self.image_width) as f32 * 100.0 / self.image_width as f32 / self.image_height as f32
   }
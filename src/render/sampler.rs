@@ -0,0 +1,442 @@
+use super::math::{hash, Rnd};
+
+// layout of the sample dimensions, every bounce of a path gets its own light, BRDF, sky light, medium
+// scattering, subsurface walk, coat, refraction and internal reflections dimensions, dimensions 2 and 3 are reserved
+// for the lens, the spectral paths pick the wavelength by the dimension 4
+pub const DIM_PIXEL: u32 = 0;
+pub const DIM_WAVELENGTH: u32 = 4;
+pub const DIM_BOUNCE: u32 = 5;
+pub const DIMS_PER_BOUNCE: u32 = 13;
+
+const PRIMES: [u32; 32] = [
+  2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53,
+  59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131,
+];
+
+// primitive polynomials and initial direction numbers of the Sobol dimensions 1..3 (Joe and Kuo),
+// dimension 0 is the van der Corput sequence
+const SOBOL_POLYNOMIALS: [(u32, u32, [u32; 3]); 3] = [
+  (1, 0, [1, 0, 0]),
+  (2, 1, [1, 3, 0]),
+  (3, 1, [1, 3, 1]),
+];
+
+const BLUE_NOISE_SIZE: usize = 64;
+const BLUE_NOISE_SIGMA: f32 = 1.5;
+const GOLDEN_RATIO_FRACT: f32 = 0.618_034;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplerKind {
+  Independent,
+  Stratified,
+  Halton,
+  Sobol,
+  BlueNoise,
+}
+
+pub const SAMPLER_KINDS: [SamplerKind; 5] = [
+  SamplerKind::Independent,
+  SamplerKind::Stratified,
+  SamplerKind::Halton,
+  SamplerKind::Sobol,
+  SamplerKind::BlueNoise,
+];
+
+impl SamplerKind {
+  pub fn from_name(name: &str) -> Option<SamplerKind> {
+    SAMPLER_KINDS.iter().copied().find(|kind| kind.name() == name)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      SamplerKind::Independent => "independent",
+      SamplerKind::Stratified => "stratified",
+      SamplerKind::Halton => "halton",
+      SamplerKind::Sobol => "sobol",
+      SamplerKind::BlueNoise => "blue_noise",
+    }
+  }
+
+  pub fn create(&self) -> Box<dyn Sampler> {
+    match self {
+      SamplerKind::Independent => Box::new(IndependentSampler),
+      SamplerKind::Stratified => Box::new(StratifiedSampler),
+      SamplerKind::Halton => Box::new(HaltonSampler),
+      SamplerKind::Sobol => Box::new(SobolSampler::new()),
+      SamplerKind::BlueNoise => Box::new(BlueNoiseSampler::new()),
+    }
+  }
+}
+
+// identifies one sample of a pixel, "count" samples are taken per pixel in every frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleId {
+  pub seed: u64,
+  pub frame: u32,
+  pub x: u32,
+  pub y: u32,
+  pub index: u32,
+  pub count: u32,
+}
+
+impl SampleId {
+  // sample index through all blended frames, so progressive render continues the sequence
+  fn global_index(&self) -> u32 {
+    self.frame.wrapping_mul(self.count).wrapping_add(self.index)
+  }
+
+  fn pixel_seed(&self) -> u64 {
+    hash(self.seed ^ hash((self.y as u64) << 32 | self.x as u64))
+  }
+}
+
+pub trait Sampler {
+  // value of the given dimension of the sample, in range [0.0, 1.0)
+  fn get(&self, id: &SampleId, dimension: u32) -> f32;
+}
+
+// sequential access to the dimensions of one sample
+pub struct SampleStream<'a> {
+  sampler: &'a dyn Sampler,
+  id: SampleId,
+  dimension: u32,
+}
+
+impl<'a> SampleStream<'a> {
+  pub fn new(sampler: &'a dyn Sampler, id: SampleId) -> SampleStream<'a> {
+    SampleStream { sampler, id, dimension: 0 }
+  }
+
+  pub fn set_dimension(&mut self, dimension: u32) {
+    self.dimension = dimension;
+  }
+
+  pub fn next_1d(&mut self) -> f32 {
+    let value = self.sampler.get(&self.id, self.dimension);
+    self.dimension += 1;
+
+    value
+  }
+
+  pub fn next_2d(&mut self) -> (f32, f32) {
+    (self.next_1d(), self.next_1d())
+  }
+
+  pub fn next_3d(&mut self) -> [f32; 3] {
+    [self.next_1d(), self.next_1d(), self.next_1d()]
+  }
+
+  // generator of the dimension for the decisions taking any count of numbers, like the steps of the random walk,
+  // seeded by the sample and the dimension so every sample gets its own sequence
+  pub fn next_rnd(&mut self) -> Rnd {
+    let id = &self.id;
+    let rnd = Rnd::for_sample(id.seed ^ hash(!(self.dimension as u64)), id.frame, id.x | id.y << 16, id.index);
+    self.dimension += 1;
+
+    rnd
+  }
+}
+
+pub struct IndependentSampler;
+
+impl Sampler for IndependentSampler {
+  fn get(&self, id: &SampleId, dimension: u32) -> f32 {
+    Rnd::for_sample(id.seed ^ hash(dimension as u64), id.frame, id.x | id.y << 16, id.index).next_f32()
+  }
+}
+
+// Latin hypercube: the samples of a frame fall into different strata in every dimension,
+// the strata are shuffled independently per pixel and dimension
+pub struct StratifiedSampler;
+
+impl Sampler for StratifiedSampler {
+  fn get(&self, id: &SampleId, dimension: u32) -> f32 {
+    let seed = hash(id.pixel_seed() ^ hash((id.frame as u64) << 32 | dimension as u64));
+    let stratum = permute(id.index % id.count, id.count, seed as u32);
+    let jitter = to_unit_float(hash(seed ^ id.index as u64) as u32);
+
+    f32::min((stratum as f32 + jitter) / id.count as f32, ONE_MINUS_EPSILON)
+  }
+}
+
+// Halton sequence with a random per pixel and dimension shift (Cranley-Patterson rotation),
+// dimensions beyond the prime table fall back to independent samples
+pub struct HaltonSampler;
+
+impl Sampler for HaltonSampler {
+  fn get(&self, id: &SampleId, dimension: u32) -> f32 {
+    if dimension as usize >= PRIMES.len() {
+      return IndependentSampler.get(id, dimension);
+    }
+
+    let value = radical_inverse(id.global_index(), PRIMES[dimension as usize]);
+    let shift = to_unit_float(hash(id.pixel_seed() ^ dimension as u64) as u32);
+
+    wrap_unit(value + shift)
+  }
+}
+
+// Owen-scrambled Sobol sequence, higher dimensions are padded with the shuffled
+// 4-dimensional sequence (Burley, "Practical Hash-based Owen Scrambling")
+pub struct SobolSampler {
+  directions: [[u32; 32]; 4],
+}
+
+impl SobolSampler {
+  pub fn new() -> SobolSampler {
+    let mut directions = [[0u32; 32]; 4];
+
+    for (bit, direction) in directions[0].iter_mut().enumerate() {
+      *direction = 1 << (31 - bit);
+    }
+
+    for (dim, &(degree, coefs, initial)) in SOBOL_POLYNOMIALS.iter().enumerate() {
+      let v = &mut directions[dim + 1];
+      let s = degree as usize;
+
+      for k in 0..s {
+        v[k] = initial[k] << (31 - k);
+      }
+
+      for k in s..32 {
+        let mut value = v[k - s] ^ (v[k - s] >> s);
+
+        for j in 1..s {
+          if (coefs >> (s - 1 - j)) & 1 != 0 {
+            value ^= v[k - j];
+          }
+        }
+
+        v[k] = value;
+      }
+    }
+
+    SobolSampler { directions }
+  }
+
+  fn sobol(&self, index: u32, dimension: usize) -> u32 {
+    let mut result = 0;
+    let mut index = index;
+    let mut bit = 0;
+
+    while index != 0 {
+      if index & 1 != 0 {
+        result ^= self.directions[dimension][bit];
+      }
+
+      index >>= 1;
+      bit += 1;
+    }
+
+    result
+  }
+}
+
+impl Sampler for SobolSampler {
+  fn get(&self, id: &SampleId, dimension: u32) -> f32 {
+    let pixel_seed = id.pixel_seed();
+    let shuffle_seed = hash(pixel_seed ^ (dimension / 4) as u64) as u32;
+    let index = nested_uniform_scramble(id.global_index(), shuffle_seed);
+    let value = self.sobol(index, (dimension % 4) as usize);
+    let scramble_seed = hash(pixel_seed ^ hash(dimension as u64)) as u32;
+
+    to_unit_float(nested_uniform_scramble(value, scramble_seed))
+  }
+}
+
+// blue noise dither mask tiled over the image with a different offset per dimension,
+// consecutive samples of a pixel are shifted by the golden ratio
+pub struct BlueNoiseSampler {
+  mask: Vec<f32>,
+}
+
+impl BlueNoiseSampler {
+  pub fn new() -> BlueNoiseSampler {
+    BlueNoiseSampler { mask: generate_blue_noise(BLUE_NOISE_SIZE, 1) }
+  }
+}
+
+impl Sampler for BlueNoiseSampler {
+  fn get(&self, id: &SampleId, dimension: u32) -> f32 {
+    let offset = hash(id.seed ^ hash(dimension as u64));
+    let size = BLUE_NOISE_SIZE as u64;
+    let x = (id.x as u64 + offset % size) % size;
+    let y = (id.y as u64 + (offset >> 32) % size) % size;
+    let value = self.mask[(x + y * size) as usize];
+
+    wrap_unit(value + id.global_index() as f32 * GOLDEN_RATIO_FRACT)
+  }
+}
+
+const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+fn to_unit_float(bits: u32) -> f32 {
+  (bits >> 8) as f32 / (1u32 << 24) as f32
+}
+
+fn wrap_unit(value: f32) -> f32 {
+  f32::min(value - value.floor(), ONE_MINUS_EPSILON)
+}
+
+fn radical_inverse(index: u32, base: u32) -> f32 {
+  let inv_base = 1.0 / base as f64;
+  let mut index = index;
+  let mut result = 0.0;
+  let mut factor = inv_base;
+
+  while index > 0 {
+    result += (index % base) as f64 * factor;
+    index /= base;
+    factor *= inv_base;
+  }
+
+  f32::min(result as f32, ONE_MINUS_EPSILON)
+}
+
+// hash-based Owen scrambling (Laine and Karras permutation of the reversed bits)
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+  let mut x = value.reverse_bits();
+  x = x.wrapping_add(seed);
+  x ^= x.wrapping_mul(0x6c50_b47c);
+  x ^= x.wrapping_mul(0xb82f_1e52);
+  x ^= x.wrapping_mul(0xc7af_e638);
+  x ^= x.wrapping_mul(0x8d22_f6e6);
+
+  x.reverse_bits()
+}
+
+// random permutation of range 0..length without tables (Kensler, "Correlated Multi-Jittered Sampling")
+fn permute(index: u32, length: u32, seed: u32) -> u32 {
+  let mut mask = length - 1;
+  mask |= mask >> 1;
+  mask |= mask >> 2;
+  mask |= mask >> 4;
+  mask |= mask >> 8;
+  mask |= mask >> 16;
+
+  let p = seed;
+  let mut i = index;
+
+  loop {
+    i ^= p;
+    i = i.wrapping_mul(0xe170_893d);
+    i ^= p >> 16;
+    i ^= (i & mask) >> 4;
+    i ^= p >> 8;
+    i = i.wrapping_mul(0x0929_eb3f);
+    i ^= p >> 23;
+    i ^= (i & mask) >> 1;
+    i = i.wrapping_mul(1 | p >> 27);
+    i = i.wrapping_mul(0x6935_fa69);
+    i ^= (i & mask) >> 11;
+    i = i.wrapping_mul(0x74dc_b303);
+    i ^= (i & mask) >> 2;
+    i = i.wrapping_mul(0x9e50_1cc3);
+    i ^= (i & mask) >> 2;
+    i = i.wrapping_mul(0xc860_a3df);
+    i &= mask;
+    i ^= i >> 5;
+
+    if i < length {
+      break;
+    }
+  }
+
+  (i + p % length) % length
+}
+
+// void and cluster method (Ulichney), returns thresholds in range (0.0, 1.0) for size x size tile
+fn generate_blue_noise(size: usize, seed: u64) -> Vec<f32> {
+  let n = size * size;
+  let rnd = Rnd::new(seed);
+
+  // energy contribution of a point to the pixels around it, distances wrap over the tile borders
+  let kernel: Vec<f32> = (0..n)
+    .map(|i| {
+      let dx = usize::min(i % size, size - i % size) as f32;
+      let dy = usize::min(i / size, size - i / size) as f32;
+      f32::exp(-(dx * dx + dy * dy) / (2.0 * BLUE_NOISE_SIGMA * BLUE_NOISE_SIGMA))
+    })
+    .collect();
+
+  let update_energy = |energy: &mut [f32], point: usize, sign: f32| {
+    let (px, py) = (point % size, point / size);
+
+    for (i, e) in energy.iter_mut().enumerate() {
+      let dx = (i % size + size - px) % size;
+      let dy = (i / size + size - py) % size;
+      *e += sign * kernel[dx + dy * size];
+    }
+  };
+
+  let tightest_cluster = |energy: &[f32], pattern: &[bool]| {
+    (0..n).filter(|&i| pattern[i]).fold(None, |best: Option<usize>, i| {
+      match best {
+        Some(b) if energy[b] >= energy[i] => Some(b),
+        _ => Some(i),
+      }
+    }).unwrap()
+  };
+
+  let largest_void = |energy: &[f32], pattern: &[bool]| {
+    (0..n).filter(|&i| !pattern[i]).fold(None, |best: Option<usize>, i| {
+      match best {
+        Some(b) if energy[b] <= energy[i] => Some(b),
+        _ => Some(i),
+      }
+    }).unwrap()
+  };
+
+  let mut energy = vec![0.0; n];
+  let mut pattern = vec![false; n];
+  let initial_count = n / 10;
+  let mut count = 0;
+
+  while count < initial_count {
+    let point = rnd.next_u32() as usize % n;
+
+    if !pattern[point] {
+      pattern[point] = true;
+      update_energy(&mut energy, point, 1.0);
+      count += 1;
+    }
+  }
+
+  // spread the initial points evenly by moving points from the clusters to the voids
+  for _ in 0..n {
+    let cluster = tightest_cluster(&energy, &pattern);
+    pattern[cluster] = false;
+    update_energy(&mut energy, cluster, -1.0);
+
+    let void = largest_void(&energy, &pattern);
+    pattern[void] = true;
+    update_energy(&mut energy, void, 1.0);
+
+    if void == cluster {
+      break;
+    }
+  }
+
+  let mut ranks = vec![0; n];
+  let initial_pattern = pattern.clone();
+  let initial_energy = energy.clone();
+
+  for rank in (0..initial_count).rev() {
+    let cluster = tightest_cluster(&energy, &pattern);
+    pattern[cluster] = false;
+    update_energy(&mut energy, cluster, -1.0);
+    ranks[cluster] = rank;
+  }
+
+  pattern = initial_pattern;
+  energy = initial_energy;
+
+  for rank in initial_count..n {
+    let void = largest_void(&energy, &pattern);
+    pattern[void] = true;
+    update_energy(&mut energy, void, 1.0);
+    ranks[void] = rank;
+  }
+
+  ranks.iter().map(|&rank| (rank as f32 + 0.5) / n as f32).collect()
+}
@@ -1,19 +1,119 @@
-use std::rc::Rc;
+use std::f32::consts::PI;
 
 use anyhow::Result;
 
-use super::{Color, Material, MaterialKind, Skybox, Sphere, SpotLight, Texture, Trace, Triangle};
+use super::{
+  Color, Fog, Library, Material, MaterialId, MaterialKind, SampleStream, Skybox, Sphere, SpotLight, TextureId, Trace,
+  Triangle, Volume,
+};
 use super::math::{clamp, Vector3};
 use super::math::constants::VERY_SMALL_NUMBER;
+use super::adaptive::heatmap_color;
+use super::aov::normal_color;
+use super::debug::{debug_depth_color, material_kind_color, DebugMode};
+use super::material::fresnel_dielectric;
+use super::medium::Media;
+use super::refraction::{trace_through, Passage};
+use super::spectrum::{color_to_spectrum, sample_wavelength, wavelength_to_color};
+use super::subsurface::{random_walk, Exit};
+use super::sampler::{DIM_BOUNCE, DIMS_PER_BOUNCE, DIM_WAVELENGTH};
+
+// depth of the primary rays that miss all objects
+pub const SKY_DEPTH: f32 = 1.0e6;
+// the coat or the material under it is always followed by some paths
+const MIN_COAT_PROBABILITY: f32 = 0.05;
+// isotropic phase function scaled like the diffuse surfaces, which reflect the light without the 1/pi
+const MEDIUM_PHASE: f32 = 0.25;
+
+// surface seen by the primary ray, guides the denoiser and fills the AOV buffers,
+// object and material IDs start from 1, zero means the sky
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirstHit {
+  pub albedo: Color,
+  pub normal: Vector3,
+  // distance along the primary ray, the render turns it into the distance along the forward axis of the camera
+  pub depth: f32,
+  pub position: Vector3,
+  pub object_id: u32,
+  pub material_id: u32,
+  // light reflected by the first surface straight from the light sources and the diffuse light
+  pub direct: Color,
+  // share of the light sources facing the surface that are blocked by other objects
+  pub shadow: f32,
+}
+
+impl Default for FirstHit {
+  fn default() -> FirstHit {
+    FirstHit {
+      albedo: Color::default(),
+      normal: Vector3::default(),
+      depth: SKY_DEPTH,
+      position: Vector3::default(),
+      object_id: 0,
+      material_id: 0,
+      direct: Color::default(),
+      shadow: 0.0,
+    }
+  }
+}
+
+// object under the cursor, the material of a textured object has the texel color
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickInfo {
+  pub object_index: usize,
+  pub object_type: &'static str,
+  pub position: Vector3,
+  pub normal: Vector3,
+  pub distance: f32,
+  pub uv: (f32, f32),
+  pub material_id: u32,
+  pub material_name: Option<String>,
+  pub material: Material,
+}
+
+// closest intersection found by the scene
+struct Hit {
+  index: usize,
+  drop: Vector3,
+  norm: Vector3,
+  reflect: Vector3,
+  distance: f32,
+  material_id: MaterialId,
+  // material color or the texel color of a textured object
+  color: Color,
+}
+
+// the spectral path carries its wavelength in nanometers, the rest is counted along the path
+#[derive(Default)]
+struct PathState {
+  wavelength: Option<f32>,
+  bounces: u32,
+  intersection_tests: u32,
+}
+
+impl PathState {
+  // the spectral path takes every surface, texture and light color at its wavelength, the gray color carries the value
+  fn upsample(&self, color: Color) -> Color {
+    match self.wavelength {
+      Some(wavelength) => {
+        let value = color_to_spectrum(&color, wavelength);
+        Color::new(value, value, value)
+      }
+      None => color,
+    }
+  }
+}
 
 #[derive(Default)]
 pub struct Scene {
   skybox: Skybox,
   skybox_color: Color,
   trace_objects: Vec<Box<dyn Trace>>,
+  library: Library,
   spot_lights: Vec<SpotLight>,
   diff_light_color: Color,
   diff_light_power: f32,
+  media: Media,
 }
 
 impl Scene {
@@ -22,20 +122,39 @@ impl Scene {
       skybox,
       skybox_color: &diff_light_color * diff_light_power,
       trace_objects: Vec::new(),
+      library: Library::default(),
       spot_lights: Vec::new(),
       diff_light_color,
       diff_light_power,
+      media: Media::default(),
     }
   }
 
-  pub fn add_sphere(&mut self, center: Vector3, radius: f32, material: Material) {
-    let sphere = Sphere::new(center, radius, material);
+  // materials and textures of the objects are added to the library first
+  pub fn get_library(&self) -> &Library {
+    &self.library
+  }
+
+  pub fn get_library_mut(&mut self) -> &mut Library {
+    &mut self.library
+  }
+
+  pub fn add_sphere(&mut self, center: Vector3, radius: f32, material: MaterialId, texture: Option<TextureId>) {
+    assert!((material as usize) < self.library.get_material_count(), "Invalid argument");
+    let mut sphere = Sphere::new(center, radius, material);
+
+    if let Some(texture) = texture {
+      assert!((texture as usize) < self.library.get_texture_count(), "Invalid argument");
+      sphere.set_texture(texture);
+    }
+
     self.trace_objects.push(Box::new(sphere));
   }
 
-  pub fn add_triangle(&mut self, vertices: [&Vector3; 3], material: Material,
-                      texture_data: Option<(Rc<Texture>, [(f32, f32); 3])>)
+  pub fn add_triangle(&mut self, vertices: [&Vector3; 3], material: MaterialId,
+                      texture_data: Option<(TextureId, [(f32, f32); 3])>)
   {
+    assert!((material as usize) < self.library.get_material_count(), "Invalid argument");
     let mut triangle = Triangle::new(vertices, material);
 
     if let Some((
@@ -45,6 +164,7 @@ impl Scene {
       (u2, v2),
       ])) = texture_data
     {
+      assert!((texture as usize) < self.library.get_texture_count(), "Invalid argument");
       triangle.set_texture(texture, [u0, u1, u2], [v0, v1, v2]);
     }
 
@@ -57,47 +177,318 @@ impl Scene {
     self.spot_lights.push(spot_light);
   }
 
-  pub fn trace(&self, origin: &Vector3, ray: &Vector3, max_reflections: u32) -> Result<Color> {
+  pub fn set_fog(&mut self, fog: Option<Fog>) {
+    if let Some(fog) = &fog {
+      assert!(fog.density >= 0.0 && fog.height_falloff >= 0.0, "Invalid argument");
+    }
+
+    self.media.fog = fog;
+  }
+
+  pub fn get_fog(&self) -> Option<&Fog> {
+    self.media.fog.as_ref()
+  }
+
+  pub fn add_volume(&mut self, volume: Volume) {
+    assert!(volume.radius > 0.0 && volume.density >= 0.0, "Invalid argument");
+    self.media.volumes.push(volume);
+  }
+
+  pub fn get_volumes(&self) -> &[Volume] {
+    &self.media.volumes
+  }
+
+  pub fn trace(&self, origin: &Vector3, ray: &Vector3, max_reflections: u32, samples: &mut SampleStream,
+               out_first_hit: Option<&mut FirstHit>) -> Result<Color>
+  {
+    self.trace_path(origin, ray, max_reflections, samples, out_first_hit, &mut PathState::default())
+  }
+
+  // the path carries the single wavelength, it's refracted by the IOR of the wavelength and the colors it meets
+  // are upsampled to their spectral values there, the pixel mean of the paths gives back the RGB color
+  pub fn trace_spectral(&self, origin: &Vector3, ray: &Vector3, max_reflections: u32, samples: &mut SampleStream,
+                        out_first_hit: Option<&mut FirstHit>) -> Result<Color>
+  {
+    let mut out_first_hit = out_first_hit;
+    samples.set_dimension(DIM_WAVELENGTH);
+    let wavelength = sample_wavelength(samples.next_1d());
+    let mut state = PathState { wavelength: Some(wavelength), ..PathState::default() };
+    let value = self.trace_path(origin, ray, max_reflections, samples, out_first_hit.as_deref_mut(), &mut state)?.r;
+    let color = wavelength_to_color(wavelength);
+
+    if let Some(first_hit) = out_first_hit {
+      first_hit.direct = &color * first_hit.direct.r;
+    }
+
+    Ok(color * value)
+  }
+
+  // debug shading of the surface seen by the ray, the path modes trace the whole path like the trace does
+  pub fn trace_debug(&self, origin: &Vector3, ray: &Vector3, max_reflections: u32, samples: &mut SampleStream,
+                     mode: DebugMode) -> Result<Color>
+  {
+    if mode == DebugMode::Bounces || mode == DebugMode::Cost {
+      let mut stats = PathState::default();
+      self.trace_path(origin, ray, max_reflections, samples, None, &mut stats)?;
+
+      // the most expensive path tests every object for the hit and for every light in every bounce
+      let value = if mode == DebugMode::Bounces {
+        stats.bounces as f32 / max_reflections as f32
+      } else {
+        let max_tests = self.trace_objects.len() * (1 + self.spot_lights.len()) * max_reflections as usize;
+        stats.intersection_tests as f32 / usize::max(max_tests, 1) as f32
+      };
+
+      return Ok(heatmap_color(value));
+    }
+
+    let hit = match self.find_hit(origin, ray, &mut 0)? {
+      Some(hit) => hit,
+      None => return Ok(Color::default()),
+    };
+
+    let object = &self.trace_objects[hit.index];
+
+    let color = match mode {
+      DebugMode::Normal => normal_color(&hit.norm.normalized()),
+      DebugMode::Uv => {
+        let (u, v) = object.get_uv(&hit.drop);
+        Color::new(u - u.floor(), v - v.floor(), 0.0)
+      }
+      // objects without vertices are dark gray
+      DebugMode::Barycentric => match object.get_barycentric(&hit.drop) {
+        Some(weights) => Color::new(weights.x, weights.y, weights.z),
+        None => Color::new(0.1, 0.1, 0.1),
+      },
+      DebugMode::Depth => debug_depth_color(hit.distance),
+      DebugMode::MaterialKind => material_kind_color(&self.library.get_material(hit.material_id).kind),
+      // lit surface is white, shadowed is black, the surface facing away from all lights is dark blue
+      DebugMode::Occlusion => {
+        let mut facing_lights = 0;
+        let mut visible_lights = 0;
+
+        for light in &self.spot_lights {
+          let drop_to_light = &light.origin - &hit.drop;
+
+          if &drop_to_light * &hit.norm > VERY_SMALL_NUMBER {
+            facing_lights += 1;
+
+            if !self.is_in_shadow(&hit.drop, &drop_to_light, hit.index, &mut 0)? {
+              visible_lights += 1;
+            }
+          }
+        }
+
+        if facing_lights > 0 {
+          let value = visible_lights as f32 / facing_lights as f32;
+          Color::new(value, value, value)
+        } else {
+          Color::new(0.0, 0.0, 0.25)
+        }
+      }
+      DebugMode::Bounces | DebugMode::Cost => unreachable!(),
+    };
+
+    Ok(color)
+  }
+
+  pub fn pick(&self, origin: &Vector3, ray: &Vector3) -> Result<Option<PickInfo>> {
+    let hit = match self.find_hit(origin, ray, &mut 0)? {
+      Some(hit) => hit,
+      None => return Ok(None),
+    };
+
+    let object = &self.trace_objects[hit.index];
+
+    Ok(Some(PickInfo {
+      object_index: hit.index,
+      object_type: object.get_type_name(),
+      normal: hit.norm.normalized(),
+      distance: hit.distance,
+      uv: object.get_uv(&hit.drop),
+      material_id: hit.material_id + 1,
+      material_name: self.library.get_material_name(hit.material_id).map(str::to_owned),
+      material: Material { color: hit.color, ..self.library.get_material(hit.material_id).clone() },
+      position: hit.drop,
+    }))
+  }
+
+  // closest intersection of the ray with the scene objects, only the closest one gets its color
+  fn find_hit(&self, origin: &Vector3, ray: &Vector3, intersection_tests: &mut u32) -> Result<Option<Hit>> {
+    let mut closest: Option<(usize, Vector3, Vector3, Vector3, f32)> = None;
+    let mut min_distance = f32::MAX;
+
+    for (index, obj) in self.trace_objects.iter().enumerate() {
+      let mut drop = Vector3::default();
+      let mut norm = Vector3::default();
+      let mut reflect = Vector3::default();
+      let mut distance: f32 = 0.0;
+      *intersection_tests += 1;
+      let is_hit = obj.trace(origin, ray, Some(&mut drop), Some(&mut norm), Some(&mut reflect), Some(&mut distance))?;
+
+      if is_hit && distance < min_distance {
+        min_distance = distance;
+        closest = Some((index, drop, norm, reflect, distance));
+      }
+    }
+
+    let (index, drop, norm, reflect, distance) = match closest {
+      Some(closest) => closest,
+      None => return Ok(None),
+    };
+
+    let object = &self.trace_objects[index];
+    let material_id = object.get_material_id();
+
+    let color = match object.get_texture_id() {
+      Some(texture_id) => {
+        let (u, v) = object.get_uv(&drop);
+        self.library.get_texture(texture_id).sample((u, v), &drop)?
+      }
+      None => self.library.get_material(material_id).color.clone(),
+    };
+
+    Ok(Some(Hit { index, drop, norm, reflect, distance, material_id, color }))
+  }
+
+  // checking whether the drop point is in the shadow of some scene object, the object that was hit is skipped
+  fn is_in_shadow(&self, drop: &Vector3, drop_to_light: &Vector3, hit_index: usize, intersection_tests: &mut u32) -> Result<bool> {
+    for (index, obj) in self.trace_objects.iter().enumerate() {
+      if index == hit_index {
+        continue;
+      }
+
+      *intersection_tests += 1;
+
+      if obj.trace(drop, drop_to_light, None, None, None, None)? {
+        return Ok(true);
+      }
+    }
+
+    Ok(false)
+  }
+
+  // share of the light passing the media from the point along the vector, the length of the vector is the distance,
+  // the media end at the sky depth like the sky rays do, so the far lights like the sun aren't swallowed by the fog
+  fn get_transmittance(&self, point: &Vector3, vector: &Vector3) -> f32 {
+    if self.media.is_empty() {
+      return 1.0;
+    }
+
+    let distance = vector.length();
+
+    if distance > VERY_SMALL_NUMBER {
+      self.media.get_transmittance(point, &(vector / distance), distance.min(SKY_DEPTH))
+    } else {
+      1.0
+    }
+  }
+
+  // light leaving the subsurface object at the exit point, lit like the diffuse surface there
+  fn get_exit_light(&self, exit: &Exit, object_index: usize, light_vec: &Vector3, sky_sample: (f32, f32),
+                    stats: &mut PathState) -> Result<Color>
+  {
+    if exit.weight == Color::default() {
+      return Ok(Color::default());
+    }
+
+    let mut light_color = &self.diff_light_color * self.diff_light_power;
+
+    for light in &self.spot_lights {
+      let exit_to_light = &light.origin - &exit.point + light_vec * light.radius;
+      let distance = exit_to_light.length();
+      let light_cos = if distance > VERY_SMALL_NUMBER { &exit_to_light * &exit.norm / distance } else { 0.0 };
+
+      if light_cos > VERY_SMALL_NUMBER && !self.is_in_shadow(&exit.point, &exit_to_light, object_index, &mut stats.intersection_tests)? {
+        light_color += &light.color * (light_cos * light.power * self.get_transmittance(&exit.point, &exit_to_light));
+      }
+    }
+
+    if let Some(sky) = self.skybox.sample_light(sky_sample)? {
+      let sky_cos = &sky.direction * &exit.norm;
+
+      if sky_cos > VERY_SMALL_NUMBER && sky.pdf > VERY_SMALL_NUMBER &&
+        !self.is_in_shadow(&exit.point, &sky.direction, object_index, &mut stats.intersection_tests)?
+      {
+        let sky_transmittance = self.get_transmittance(&exit.point, &(&sky.direction * SKY_DEPTH));
+        light_color += sky.color * (sky_cos * sky_transmittance / (sky.pdf * PI));
+      }
+    }
+
+    Ok(stats.upsample(exit.weight.clone()) * stats.upsample(light_color))
+  }
+
+  // single scattering of the spot lights and the diffuse light at a point of the ray segment chosen by the extinction,
+  // the light shafts come from the shadow rays of the scattering points
+  fn scatter_light(&self, origin: &Vector3, direction: &Vector3, distance: f32, sample: f32, light_vec: &Vector3,
+                   stats: &mut PathState) -> Result<Color>
+  {
+    let (scatter_distance, scattered) = match self.media.sample_distance(origin, direction, distance, sample) {
+      Some(scattering) => scattering,
+      None => return Ok(Color::default()),
+    };
+
+    let point = origin + direction * scatter_distance;
+    let mut light_color = &self.diff_light_color * self.diff_light_power;
+
+    for light in &self.spot_lights {
+      let point_to_light = &light.origin - &point + light_vec * light.radius;
+
+      // no object is skipped, the point is not on a surface
+      if !self.is_in_shadow(&point, &point_to_light, usize::MAX, &mut stats.intersection_tests)? {
+        light_color += &light.color * (light.power * MEDIUM_PHASE * self.get_transmittance(&point, &point_to_light));
+      }
+    }
+
+    Ok(stats.upsample(self.media.get_albedo(&point)) * stats.upsample(light_color) * scattered)
+  }
+
+  fn trace_path(&self, origin: &Vector3, ray: &Vector3, max_reflections: u32, samples: &mut SampleStream,
+                out_first_hit: Option<&mut FirstHit>, stats: &mut PathState) -> Result<Color>
+  {
+    let mut out_first_hit = out_first_hit;
     let mut origin = origin.clone();
     let mut ray = ray.clone();
-    let random_vec = Vector3::random_inside_sphere(1.0);
     let mut color_multiplier = Color::new(1.0, 1.0, 1.0);
     let mut output_color = Color::new(0.0, 0.0, 0.0);
 
     // going deep up to maxReflections
-    for _ in 0..max_reflections {
-      let mut min_distance = f32::MAX;
-      let mut hit_object: Option<&Box<dyn Trace>> = None;
-      let mut drop = Vector3::default();
-      let mut norm = Vector3::default();
-      let mut reflect = Vector3::default();
-      let mut drop_material = Material::new(MaterialKind::Metal, Color::new(0.0, 0.0, 0.0), 0.0, 0.0);
-
-      // tracing intersections with all scene objects and select closest
-      for obj in &self.trace_objects {
-        let mut cur_drop = Vector3::default();
-        let mut cur_norm = Vector3::default();
-        let mut cur_reflect = Vector3::default();
-        let mut cur_drop_material = Material::new(MaterialKind::Metal, Color::new(0.0, 0.0, 0.0), 0.0, 0.0);
-        let mut cur_dist: f32 = 0.0;
-        let hit = obj.trace(&origin, &ray, Some(&mut cur_drop), Some(&mut cur_norm), Some(&mut cur_reflect), Some(&mut cur_dist), Some(&mut cur_drop_material))?;
-
-        if hit && cur_dist < min_distance {
-          min_distance = cur_dist;
-          drop = cur_drop;
-          norm = cur_norm;
-          reflect = cur_reflect;
-          drop_material = cur_drop_material;
-          hit_object = Some(obj);
-        }
+    for bounce in 0..max_reflections {
+      // every bounce randomizes light and reflection independently
+      samples.set_dimension(DIM_BOUNCE + bounce * DIMS_PER_BOUNCE);
+      let light_vec = Vector3::inside_sphere(1.0, samples.next_3d());
+      let brdf_vec = Vector3::inside_sphere(1.0, samples.next_3d());
+      let sky_sample = samples.next_2d();
+      let medium_sample = samples.next_1d();
+      let walk_rnd = samples.next_rnd();
+      let coat_sample = samples.next_1d();
+      let refraction_sample = samples.next_1d();
+      let reflections_rnd = samples.next_rnd();
+      stats.bounces = bounce + 1;
+
+      let hit = self.find_hit(&origin, &ray, &mut stats.intersection_tests)?;
+
+      // the segment to the hit or to the sky scatters the light into the ray and dims what is behind it
+      let mut medium_color = Color::default();
+
+      if !self.media.is_empty() {
+        let direction = ray.normalized();
+        let distance = hit.as_ref().map_or(SKY_DEPTH, |hit| hit.distance);
+        medium_color = &color_multiplier * self.scatter_light(&origin, &direction, distance, medium_sample, &light_vec, stats)?;
+        output_color += &medium_color;
+        color_multiplier *= self.media.get_transmittance(&origin, &direction, distance);
       }
 
-      if let Some(hit_object) = hit_object {
+      if let Some(hit) = hit {
+        let Hit { index: hit_index, drop, norm, reflect, material_id, color, .. } = hit;
+        let drop_material = self.library.get_material(material_id);
         let ray_length = ray.length();
         let norm_length = norm.length();
-        let reflect_length = reflect.length();
         let mut sum_light_color = Color::new(0.0, 0.0, 0.0);
         let mut sum_spec_color = Color::new(0.0, 0.0, 0.0);
+        let mut sum_coat_color = Color::new(0.0, 0.0, 0.0);
+        let mut facing_lights = 0;
+        let mut shadowed_lights = 0;
 
         // tracing each light source visibility
         for lt in &self.spot_lights {
@@ -106,35 +497,24 @@ impl Scene {
 
           // check only if drop point faced to light source
           if &drop_to_light * &norm > VERY_SMALL_NUMBER {
+            facing_lights += 1;
             // make randomization within a radius of light source for smooth shadows
             let light_radius = light.radius;
-            let drop_to_light_randomized = &drop_to_light + &random_vec * light_radius;
-            let mut in_shadow = false;
+            let drop_to_light_randomized = &drop_to_light + &light_vec * light_radius;
+            let in_shadow = self.is_in_shadow(&drop, &drop_to_light_randomized, hit_index, &mut stats.intersection_tests)?;
+            let light_transmittance = self.get_transmittance(&drop, &drop_to_light_randomized);
 
-            // checking whether we are in the shadow of some scene object
-            for obj in &self.trace_objects {
-              let obj_ptr = obj as *const Box<dyn Trace>;
-              let hit_obj_ptr = hit_object as *const Box<dyn Trace>;
-
-              // skip the object that was hit from shadow check
-              if std::ptr::eq(obj_ptr, hit_obj_ptr) {
-                continue;
-              }
-
-              if obj.trace(&drop, &drop_to_light_randomized, None, None, None, None, None)?
-              {
-                in_shadow = true;
-                break;
-              }
+            if in_shadow {
+              shadowed_lights += 1;
             }
 
             // if we are not in the shadow - proceed illumination
             if !in_shadow {
               // calc illumination from current light source
               let drop_to_light_length = drop_to_light.length();
-              let light_color = &light.color;
+              let light_color = &(&light.color * light_transmittance);
               let light_power = light.power;
-              let mut a = drop_to_light_length * norm_length;
+              let a = drop_to_light_length * norm_length;
 
               let light_drop_cos = if a > VERY_SMALL_NUMBER {
                 &drop_to_light * &norm / a
@@ -146,60 +526,92 @@ impl Scene {
                 sum_light_color += light_color * light_drop_cos * light_power;
               }
 
-              // calc specular reflection from current light source
-              a = drop_to_light.sq_length();
+              // calc specular reflection from current light source, the coat has its own sharper or wider highlight
+              sum_spec_color += light_color * get_specular_power(&drop_to_light, &reflect, &brdf_vec, drop_material.reflectivity, light_radius);
 
-              let light_angular_radius_sq_cos = if a > VERY_SMALL_NUMBER {
-                1.0 - light_radius * light_radius / a
-              } else {
-                0.0
-              };
+              if let Some(coat) = &drop_material.coat {
+                sum_coat_color += light_color * get_specular_power(&drop_to_light, &reflect, &brdf_vec, 1.0 - coat.roughness, light_radius);
+              }
+            }
+          }
+        }
 
-              if light_angular_radius_sq_cos > 0.0 {
-                let drop_to_light_randomized = drop_to_light.normalized() + &random_vec * (1.0 - drop_material.reflectivity);
-                a = drop_to_light_randomized.length() * reflect_length;
+        // the lighting sky is sampled by its brightness, the white sky of the unit radiance gives the unit light
+        if let Some(sky) = self.skybox.sample_light(sky_sample)? {
+          let sky_drop_cos = &sky.direction * &norm / norm_length;
 
-                let mut reflect_specular_cos = if a > VERY_SMALL_NUMBER {
-                  drop_to_light_randomized * &reflect / a
-                } else {
-                  0.0
-                };
+          if sky_drop_cos > VERY_SMALL_NUMBER && sky.pdf > VERY_SMALL_NUMBER &&
+            !self.is_in_shadow(&drop, &sky.direction, hit_index, &mut stats.intersection_tests)?
+          {
+            let sky_transmittance = self.get_transmittance(&drop, &(&sky.direction * SKY_DEPTH));
+            sum_light_color += sky.color * (sky_drop_cos * sky_transmittance / (sky.pdf * PI));
+          }
+        }
 
-                reflect_specular_cos = clamp(reflect_specular_cos + (1.0 - light_angular_radius_sq_cos.sqrt()), 0.0, 1.0);
+        let reflectivity = drop_material.reflectivity;
+        let albedo = color;
+        let color = &stats.upsample(albedo.clone());
 
-                if reflect_specular_cos > VERY_SMALL_NUMBER {
-                  let mut spec_power = reflect_specular_cos;
+        sum_light_color = stats.upsample(&self.diff_light_color * self.diff_light_power + sum_light_color);
+        sum_spec_color = stats.upsample(sum_spec_color);
+        sum_coat_color = stats.upsample(sum_coat_color);
 
-                  if light_radius > VERY_SMALL_NUMBER {
-                    spec_power = f32::powf(spec_power, 1.0 + 3.0 * drop_material.reflectivity * drop_to_light_length / light_radius) * drop_material.reflectivity;
-                    sum_spec_color += light_color * spec_power;
-                  }
-                }
-              }
-            }
+        // the subsurface light leaves the object at the end of the walk, the objects without inside are shaded like the dielectric
+        let mut diffuse_color = color * &sum_light_color;
+
+        if drop_material.kind == MaterialKind::Subsurface {
+          let object = self.trace_objects[hit_index].as_ref();
+
+          let material = Material { scattering_color: stats.upsample(drop_material.scattering_color.clone()), ..drop_material.clone() };
+
+          if let Some(exit) = random_walk(object, &drop, &(&norm / norm_length), color, &material, &walk_rnd) {
+            diffuse_color = self.get_exit_light(&exit, hit_index, &light_vec, sky_sample, stats)?;
           }
         }
 
-        let reflectivity = drop_material.reflectivity;
-        let color = &drop_material.color;
-        let kind = drop_material.kind;
+        let path_multiplier = color_multiplier.clone();
+        let mut fin_color: Color;
+        let mut passage: Option<Passage> = None;
 
-        sum_light_color = &self.diff_light_color * self.diff_light_power + sum_light_color;
+        if drop_material.kind == MaterialKind::Dielectric && drop_material.transparency > 0.0 {
+          // the transparent share reflects and refracts by the Fresnel equations at the IOR of the wavelength,
+          // the path follows one of them by their shares
+          let a = ray_length * norm_length;
+          let drop_angle_cos = if a > VERY_SMALL_NUMBER { clamp(&ray * -&norm / a, 0.0, 1.0) } else { 0.0 };
+          let transparency = drop_material.transparency;
+          let ior = stats.wavelength.map_or_else(|| drop_material.ior.get_mean(), |wavelength| drop_material.ior.get(wavelength));
 
-        let mut fin_color: Color;
-        if kind == MaterialKind::Dielectric {
+          let opaque_reflectivity = 0.2 + 0.8 * f32::powi(1.0 - drop_angle_cos, 3);
+          let fresnel = fresnel_dielectric(drop_angle_cos, ior);
+          let reflected_share = (1.0 - transparency) * opaque_reflectivity + transparency * fresnel;
+          let refracted_share = transparency * (1.0 - fresnel);
+          let refraction_probability = refracted_share / (reflected_share + refracted_share);
+
+          fin_color = (1.0 - transparency) * (1.0 - opaque_reflectivity) * diffuse_color + sum_spec_color;
+          fin_color *= &color_multiplier;
+
+          if refraction_sample < refraction_probability {
+            let object = self.trace_objects[hit_index].as_ref();
+            passage = trace_through(object, &drop, &(&norm / norm_length), &ray, ior, &reflections_rnd);
+
+            // the light trapped inside is absorbed
+            color_multiplier *= if passage.is_some() { color * (refracted_share / refraction_probability) } else { Color::default() };
+          } else {
+            color_multiplier *= reflected_share / (1.0 - refraction_probability);
+          }
+        } else if drop_material.kind == MaterialKind::Dielectric || drop_material.kind == MaterialKind::Subsurface {
           // for dielectric materials count reflectivity using rough approximation of the Fresnel curve
           let a = ray_length * norm_length;
 
           let drop_angle_cos = if a > VERY_SMALL_NUMBER {
-            clamp(&ray * -norm / a, 0.0, 1.0)
+            clamp(&ray * -&norm / a, 0.0, 1.0)
           } else {
             0.0
           };
 
           let reflectivity = 0.2 + 0.8 * f32::powi(1.0 - drop_angle_cos, 3);
 
-          fin_color = (1.0 - reflectivity) * color * sum_light_color + sum_spec_color;
+          fin_color = (1.0 - reflectivity) * diffuse_color + sum_spec_color;
           fin_color *= &color_multiplier;
 
           // multiply colorMul with counted reflectivity to reduce subsequent reflections impact
@@ -216,6 +628,44 @@ impl Scene {
           color_multiplier *= color * reflectivity;
         }
 
+        // the coat reflects its share of the light, the rest reaches the material and comes back through the coat,
+        // the path goes on from the coat or from the material by their shares
+        let mut ray_spread = 1.0 - reflectivity;
+
+        if let Some(coat) = &drop_material.coat {
+          let a = ray_length * norm_length;
+          let view_cos = if a > VERY_SMALL_NUMBER { clamp(&ray * -&norm / a, 0.0, 1.0) } else { 0.0 };
+          let coat_color = match stats.wavelength {
+            Some(wavelength) => Color::new(1.0, 1.0, 1.0) * coat.get_spectral_reflectance(view_cos, wavelength),
+            None => coat.get_reflectance(view_cos),
+          };
+          let base_share = Color::new(1.0, 1.0, 1.0) - &coat_color;
+          let coat_probability = clamp((coat_color.r + coat_color.g + coat_color.b) / 3.0, MIN_COAT_PROBABILITY, 1.0 - MIN_COAT_PROBABILITY);
+
+          fin_color = fin_color * &base_share + &path_multiplier * &coat_color * &sum_coat_color;
+
+          if coat_sample < coat_probability {
+            color_multiplier = path_multiplier * coat_color / coat_probability;
+            ray_spread = coat.roughness;
+            passage = None;
+          } else {
+            color_multiplier *= base_share / (1.0 - coat_probability);
+          }
+        }
+
+        if let Some(first_hit) = out_first_hit.take() {
+          *first_hit = FirstHit {
+            albedo: albedo.clone(),
+            normal: norm.normalized(),
+            depth: (&drop - &origin).length(),
+            position: drop.clone(),
+            object_id: hit_index as u32 + 1,
+            material_id: material_id + 1,
+            direct: &fin_color + &medium_color,
+            shadow: if facing_lights > 0 { shadowed_lights as f32 / facing_lights as f32 } else { 0.0 },
+          };
+        }
+
         // summarize reflected colors
         output_color += fin_color;
         output_color.clamp();
@@ -225,12 +675,31 @@ impl Scene {
           break;
         }
 
-        // select reflection as new ray for tracing and randomize it depending on reflectivity of object
-        origin = drop;
-        ray = reflect.normalized() + &random_vec * (1.0 - reflectivity);
+        // select reflection as new ray for tracing and randomize it depending on reflectivity of object,
+        // the refracted light goes on from the other side
+        if let Some(Passage { point, direction }) = passage {
+          origin = point;
+          ray = direction;
+        } else {
+          origin = drop;
+          ray = reflect.normalized() + &brdf_vec * ray_spread;
+        }
       } else {
-        // no intersections, tracing skybox
-        output_color += &color_multiplier * self.skybox.trace(&ray)? * &self.skybox_color;
+        // no intersections, tracing skybox, the lighting sky is seen as it is
+        let sky_color = if self.skybox.is_lighting() {
+          self.skybox.trace(&ray)?
+        } else {
+          self.skybox.trace(&ray)? * &self.skybox_color
+        };
+
+        let albedo = sky_color.clone();
+        let sky_color = stats.upsample(sky_color);
+
+        if let Some(first_hit) = out_first_hit.take() {
+          *first_hit = FirstHit { albedo, direct: &color_multiplier * &sky_color + &medium_color, ..FirstHit::default() };
+        }
+
+        output_color += &color_multiplier * sky_color;
         output_color.clamp();
         break;
       }
@@ -238,4 +707,37 @@ impl Scene {
 
     Ok(output_color)
   }
-}
\ No newline at end of file
+}
+
+// highlight of the light source of the given radius in the reflection, the sharper surfaces give the smaller highlights
+fn get_specular_power(drop_to_light: &Vector3, reflect: &Vector3, brdf_vec: &Vector3, sharpness: f32, light_radius: f32) -> f32 {
+  let sq_distance = drop_to_light.sq_length();
+  let reflect_length = reflect.length();
+
+  let light_angular_radius_sq_cos = if sq_distance > VERY_SMALL_NUMBER {
+    1.0 - light_radius * light_radius / sq_distance
+  } else {
+    0.0
+  };
+
+  if light_angular_radius_sq_cos <= 0.0 || light_radius <= VERY_SMALL_NUMBER {
+    return 0.0;
+  }
+
+  let drop_to_light_randomized = drop_to_light.normalized() + brdf_vec * (1.0 - sharpness);
+  let a = drop_to_light_randomized.length() * reflect_length;
+
+  let reflect_specular_cos = if a > VERY_SMALL_NUMBER {
+    drop_to_light_randomized * reflect / a
+  } else {
+    0.0
+  };
+
+  let reflect_specular_cos = clamp(reflect_specular_cos + (1.0 - light_angular_radius_sq_cos.sqrt()), 0.0, 1.0);
+
+  if reflect_specular_cos > VERY_SMALL_NUMBER {
+    f32::powf(reflect_specular_cos, 1.0 + 3.0 * sharpness * sq_distance.sqrt() / light_radius) * sharpness
+  } else {
+    0.0
+  }
+}
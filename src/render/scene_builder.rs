@@ -0,0 +1,489 @@
+use std::rc::Rc;
+
+use anyhow::{Error, Result};
+
+use super::{
+  Camera, Color, Fog, HdrImage, Material, MaterialHandle, MaterialKind, PhysicalSky, Scene, SkyMapping, Skybox, Texture,
+  TextureSource, Volume,
+};
+use super::math::Vector3;
+
+// image or procedural texture shared by many objects of the scene builder
+pub type TextureHandle = Rc<dyn TextureSource>;
+
+// sun is a spot light that far away, so its rays are parallel across the scene
+const SUN_DISTANCE: f32 = 1.0e10;
+// angular radius of the sun disk, gives the soft shadows of the built-in scene
+const SUN_ANGULAR_RADIUS: f32 = 0.027;
+
+const QUAD_UVS: [(f32, f32); 4] = [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)];
+
+enum Object {
+  Sphere {
+    center: Vector3,
+    radius: f32,
+    material: MaterialHandle,
+    texture: Option<TextureHandle>,
+  },
+  Triangle {
+    vertices: [Vector3; 3],
+    material: MaterialHandle,
+    texture: Option<(TextureHandle, [(f32, f32); 3])>,
+  },
+}
+
+struct Light {
+  origin: Vector3,
+  radius: f32,
+  color: Color,
+  power: f32,
+}
+
+// fluent scene construction, the first invalid argument is kept and returned by the build,
+// so the chain of calls is never interrupted by a panic
+pub struct SceneBuilder {
+  skybox: Option<Skybox>,
+  sky_rotation: f32,
+  sky_intensity: f32,
+  sky_lighting: bool,
+  // power of the sun light matching the physical sky
+  sky_sun_power: Option<f32>,
+  diffuse_light: (Color, f32),
+  lights: Vec<Light>,
+  fog: Option<Fog>,
+  volumes: Vec<Volume>,
+  objects: Vec<Object>,
+  named_materials: Vec<(String, MaterialHandle)>,
+  named_textures: Vec<(String, TextureHandle)>,
+  camera: Option<Camera>,
+  error: Option<Error>,
+}
+
+impl Default for SceneBuilder {
+  fn default() -> SceneBuilder {
+    SceneBuilder::new()
+  }
+}
+
+impl SceneBuilder {
+  pub fn new() -> SceneBuilder {
+    SceneBuilder {
+      skybox: None,
+      sky_rotation: 0.0,
+      sky_intensity: 1.0,
+      sky_lighting: false,
+      sky_sun_power: None,
+      diffuse_light: (Color::new(1.0, 1.0, 1.0), 0.0),
+      lights: Vec::new(),
+      fog: None,
+      volumes: Vec::new(),
+      objects: Vec::new(),
+      named_materials: Vec::new(),
+      named_textures: Vec::new(),
+      camera: None,
+      error: None,
+    }
+  }
+
+  // named material can be found in the scene library by its name, the objects use it like any other material
+  pub fn material(mut self, name: &str, material: impl Into<MaterialHandle>) -> SceneBuilder {
+    let material = material.into();
+
+    if let Err(err) = validate_material(&material) {
+      return self.fail(err);
+    }
+
+    self.named_materials.push((name.to_owned(), material));
+    self
+  }
+
+  pub fn texture(mut self, name: &str, texture: &TextureHandle) -> SceneBuilder {
+    self.named_textures.push((name.to_owned(), Rc::clone(texture)));
+    self
+  }
+
+  // skybox texture is a cube unfolded into a 4x3 tiles cross
+  pub fn sky(mut self, texture: &Texture) -> SceneBuilder {
+    if texture.width == 0 || texture.height == 0 {
+      return self.fail("Sky texture is empty");
+    }
+
+    self.skybox = Some(Skybox::new(texture.clone()));
+    self.sky_sun_power = None;
+    self
+  }
+
+  // HDR or ordinary image in the cross or the latitude-longitude layout
+  pub fn sky_image(mut self, image: &Rc<HdrImage>, mapping: SkyMapping) -> SceneBuilder {
+    if image.width == 0 || image.height == 0 {
+      return self.fail("Sky texture is empty");
+    }
+
+    self.skybox = match mapping {
+      SkyMapping::Cross => Some(Skybox::cross(Rc::clone(image))),
+      SkyMapping::Equirectangular => Some(Skybox::equirectangular(Rc::clone(image))),
+      SkyMapping::Faces => return self.fail("Sky faces mapping needs six textures"),
+      SkyMapping::Physical => return self.fail("Physical sky has no texture"),
+    };
+
+    self.sky_sun_power = None;
+    self
+  }
+
+  // faces go in the order of the axes: +x, -x, +y, -y, +z, -z
+  pub fn sky_faces(mut self, faces: &[Rc<HdrImage>; 6]) -> SceneBuilder {
+    if faces.iter().any(|face| face.width == 0 || face.height == 0) {
+      return self.fail("Sky texture is empty");
+    }
+
+    self.skybox = Some(Skybox::from_faces(faces.clone()));
+    self.sky_sun_power = None;
+    self
+  }
+
+  // analytic daylight sky with the sun light shining from the sky sun direction,
+  // the sun color is the sunlight passed through the atmosphere
+  pub fn physical_sky(mut self, sky: PhysicalSky, sun_power: f32) -> SceneBuilder {
+    if !is_valid_power(sun_power) {
+      return self.fail("Sun power can't be negative");
+    }
+
+    self.skybox = Some(Skybox::physical(sky));
+    self.sky_sun_power = Some(sun_power);
+    self
+  }
+
+  // radians around the vertical axis
+  pub fn sky_rotation(mut self, rotation: f32) -> SceneBuilder {
+    if !rotation.is_finite() {
+      return self.fail("Sky rotation should be finite");
+    }
+
+    self.sky_rotation = rotation;
+    self
+  }
+
+  pub fn sky_intensity(mut self, intensity: f32) -> SceneBuilder {
+    if !is_valid_power(intensity) {
+      return self.fail("Sky intensity can't be negative");
+    }
+
+    self.sky_intensity = intensity;
+    self
+  }
+
+  // the lighting sky lights the scene by itself, its brightest parts cast the shadows
+  pub fn sky_lighting(mut self, is_lighting: bool) -> SceneBuilder {
+    self.sky_lighting = is_lighting;
+    self
+  }
+
+  pub fn sky_color(mut self, color: Color) -> SceneBuilder {
+    if !is_valid_color(&color) {
+      return self.fail("Sky color can't be negative");
+    }
+
+    self.skybox = Some(Skybox::from_color(&color));
+    self.sky_sun_power = None;
+    self
+  }
+
+  // light coming from the whole sky, added to the sky color together with the light sources
+  pub fn diffuse_light(mut self, color: Color, power: f32) -> SceneBuilder {
+    if !is_valid_color(&color) || !is_valid_power(power) {
+      return self.fail("Diffuse light color and power can't be negative");
+    }
+
+    self.diffuse_light = (color, power);
+    self
+  }
+
+  pub fn light(mut self, origin: Vector3, radius: f32, color: Color, power: f32) -> SceneBuilder {
+    if !(radius >= 0.0 && radius.is_finite()) {
+      return self.fail("Light radius can't be negative");
+    }
+
+    if !is_valid_color(&color) || !is_valid_power(power) {
+      return self.fail("Light color and power can't be negative");
+    }
+
+    self.lights.push(Light { origin, radius, color, power });
+    self
+  }
+
+  // direction points from the scene to the sun
+  pub fn sun(self, direction: Vector3, color: Color, power: f32) -> SceneBuilder {
+    if direction.length() <= 0.0 {
+      return self.fail("Sun direction can't be zero");
+    }
+
+    let origin = direction.normalized() * SUN_DISTANCE;
+    self.light(origin, SUN_DISTANCE * SUN_ANGULAR_RADIUS, color, power)
+  }
+
+  // homogeneous fog filling the whole scene, the color is the share of the scattered light
+  pub fn fog(self, color: Color, density: f32) -> SceneBuilder {
+    self.height_fog(color, density, 0.0, 0.0)
+  }
+
+  // fog of the given density at the base height, getting thinner upwards by the falloff per unit of the height
+  pub fn height_fog(mut self, color: Color, density: f32, base_height: f32, height_falloff: f32) -> SceneBuilder {
+    if !is_valid_albedo(&color) {
+      return self.fail("Fog color should be in range [0, 1]");
+    }
+
+    if !is_valid_power(density) || !is_valid_power(height_falloff) || !base_height.is_finite() {
+      return self.fail("Fog density and height falloff can't be negative");
+    }
+
+    self.fog = Some(Fog { color, density, height_falloff, base_height });
+    self
+  }
+
+  // sphere of the homogeneous medium like smoke, it isn't an object, so it's never hit by the rays
+  pub fn volume(mut self, center: Vector3, radius: f32, color: Color, density: f32) -> SceneBuilder {
+    if !(radius > 0.0 && radius.is_finite()) {
+      return self.fail("Volume radius should be positive");
+    }
+
+    if !is_valid_albedo(&color) {
+      return self.fail("Volume color should be in range [0, 1]");
+    }
+
+    if !is_valid_power(density) {
+      return self.fail("Volume density can't be negative");
+    }
+
+    self.volumes.push(Volume { center, radius, color, density });
+    self
+  }
+
+  pub fn sphere(self, center: Vector3, radius: f32, material: impl Into<MaterialHandle>) -> SceneBuilder {
+    self.add_sphere(center, radius, material.into(), None)
+  }
+
+  // the texture is wrapped around the sphere by the longitude and latitude
+  pub fn textured_sphere(self, center: Vector3, radius: f32, material: impl Into<MaterialHandle>,
+                         texture: &TextureHandle) -> SceneBuilder
+  {
+    self.add_sphere(center, radius, material.into(), Some(Rc::clone(texture)))
+  }
+
+  pub fn triangle(self, vertices: [Vector3; 3], material: impl Into<MaterialHandle>) -> SceneBuilder {
+    self.add_triangle(vertices, material.into(), None)
+  }
+
+  pub fn textured_triangle(self, vertices: [Vector3; 3], uvs: [(f32, f32); 3], material: impl Into<MaterialHandle>,
+                           texture: &TextureHandle) -> SceneBuilder
+  {
+    self.add_triangle(vertices, material.into(), Some((Rc::clone(texture), uvs)))
+  }
+
+  // corners go around the quad, it is split into two triangles along the second and the fourth corners
+  pub fn quad(self, corners: [Vector3; 4], material: impl Into<MaterialHandle>) -> SceneBuilder {
+    let material = material.into();
+    let [a, b, c, d] = corners;
+
+    self.add_triangle([a, b.clone(), d.clone()], Rc::clone(&material), None)
+      .add_triangle([b, c, d], material, None)
+  }
+
+  // the texture is stretched over the whole quad, the first corner gets the texture origin
+  pub fn textured_quad(self, corners: [Vector3; 4], material: impl Into<MaterialHandle>,
+                       texture: &TextureHandle) -> SceneBuilder
+  {
+    let material = material.into();
+    let [a, b, c, d] = corners;
+    let [uv_a, uv_b, uv_c, uv_d] = QUAD_UVS;
+
+    self.add_triangle([a, b.clone(), d.clone()], Rc::clone(&material), Some((Rc::clone(texture), [uv_a, uv_b, uv_d])))
+      .add_triangle([b, c, d], material, Some((Rc::clone(texture), [uv_b, uv_c, uv_d])))
+  }
+
+  // indexed triangle list sharing a single material
+  pub fn mesh(mut self, vertices: &[Vector3], triangles: &[[usize; 3]], material: impl Into<MaterialHandle>) -> SceneBuilder {
+    let material = material.into();
+
+    for indices in triangles {
+      if indices.iter().any(|&index| index >= vertices.len()) {
+        return self.fail(format!("Mesh vertex index is out of range 0..{}", vertices.len()));
+      }
+
+      let [a, b, c] = *indices;
+      let triangle = [vertices[a].clone(), vertices[b].clone(), vertices[c].clone()];
+      self = self.add_triangle(triangle, Rc::clone(&material), None);
+    }
+
+    self
+  }
+
+  pub fn camera(mut self, eye: Vector3, look_at: Vector3, fov: f32) -> SceneBuilder {
+    if (&look_at - &eye).length() <= 0.0 {
+      return self.fail("Camera can't look at its own position");
+    }
+
+    if !(fov > 0.0 && fov < std::f32::consts::PI) {
+      return self.fail("Camera fov should be in range (0, pi)");
+    }
+
+    self.camera = Some(Camera::new(eye, look_at, fov));
+    self
+  }
+
+  // the sky is black unless it's set, the camera has to be set
+  pub fn build(self) -> Result<(Scene, Camera)> {
+    if let Some(error) = self.error {
+      return Err(error);
+    }
+
+    let camera = self.camera.ok_or_else(|| Error::msg("Camera is not set"))?;
+    let mut skybox = self.skybox.unwrap_or_else(|| Skybox::from_color(&Color::default()));
+    skybox.set_rotation(self.sky_rotation);
+    skybox.set_intensity(self.sky_intensity);
+    skybox.set_lighting(self.sky_lighting)?;
+
+    // the sun is turned together with the sky, it's not lit below the horizon
+    let sun = self.sky_sun_power.and_then(|power| skybox.get_sun().map(|(direction, color)| (direction, color, power)));
+
+    let (diffuse_light_color, diffuse_light_power) = self.diffuse_light;
+    let mut scene = Scene::new(skybox, diffuse_light_color, diffuse_light_power);
+
+    for light in self.lights {
+      scene.add_spot_light(light.origin, light.radius, light.color, light.power);
+    }
+
+    if let Some((direction, color, power)) = sun {
+      if direction.y > 0.0 {
+        scene.add_spot_light(direction * SUN_DISTANCE, SUN_DISTANCE * SUN_ANGULAR_RADIUS, color, power);
+      }
+    }
+
+    scene.set_fog(self.fog);
+
+    for volume in self.volumes {
+      scene.add_volume(volume);
+    }
+
+    let library = scene.get_library_mut();
+
+    for (name, material) in &self.named_materials {
+      library.add_named_material(name, material);
+    }
+
+    for (name, texture) in &self.named_textures {
+      library.add_named_texture(name, texture);
+    }
+
+    // equal materials and the same textures get a single library entry
+    for object in self.objects {
+      match object {
+        Object::Sphere { center, radius, material, texture } => {
+          let library = scene.get_library_mut();
+          let material = library.add_material(&material);
+          let texture = texture.map(|texture| library.add_texture(&texture));
+          scene.add_sphere(center, radius, material, texture);
+        }
+        Object::Triangle { vertices, material, texture } => {
+          let library = scene.get_library_mut();
+          let material = library.add_material(&material);
+          let texture = texture.map(|(texture, uvs)| (library.add_texture(&texture), uvs));
+          scene.add_triangle([&vertices[0], &vertices[1], &vertices[2]], material, texture);
+        }
+      }
+    }
+
+    Ok((scene, camera))
+  }
+
+  fn add_sphere(mut self, center: Vector3, radius: f32, material: MaterialHandle,
+                texture: Option<TextureHandle>) -> SceneBuilder
+  {
+    if !(radius > 0.0 && radius.is_finite()) {
+      return self.fail("Sphere radius should be positive");
+    }
+
+    if let Err(err) = validate_material(&material) {
+      return self.fail(err);
+    }
+
+    self.objects.push(Object::Sphere { center, radius, material, texture });
+    self
+  }
+
+  fn add_triangle(mut self, vertices: [Vector3; 3], material: MaterialHandle,
+                  texture: Option<(TextureHandle, [(f32, f32); 3])>) -> SceneBuilder
+  {
+    let normal = (&vertices[1] - &vertices[0]) % (&vertices[2] - &vertices[0]);
+
+    if normal.length() <= 0.0 {
+      return self.fail("Triangle is degenerate");
+    }
+
+    if let Err(err) = validate_material(&material) {
+      return self.fail(err);
+    }
+
+    if let Some((_, uvs)) = &texture {
+      if uvs.iter().any(|&(u, v)| !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v)) {
+        return self.fail("Texture coordinates should be in range [0, 1]");
+      }
+    }
+
+    self.objects.push(Object::Triangle { vertices, material, texture });
+    self
+  }
+
+  fn fail(mut self, message: impl std::fmt::Display) -> SceneBuilder {
+    if self.error.is_none() {
+      self.error = Some(Error::msg(message.to_string()));
+    }
+
+    self
+  }
+}
+
+fn is_valid_color(color: &Color) -> bool {
+  [color.r, color.g, color.b].iter().all(|&value| value >= 0.0 && value.is_finite())
+}
+
+fn is_valid_albedo(color: &Color) -> bool {
+  [color.r, color.g, color.b].iter().all(|value| (0.0..=1.0).contains(value))
+}
+
+fn is_valid_power(power: f32) -> bool {
+  power >= 0.0 && power.is_finite()
+}
+
+fn validate_material(material: &Material) -> Result<()> {
+  if !is_valid_color(&material.color) {
+    return Err(Error::msg("Material color can't be negative"));
+  }
+
+  if !(0.0..=1.0).contains(&material.reflectivity) || !(0.0..=1.0).contains(&material.transparency) {
+    return Err(Error::msg("Material reflectivity and transparency should be in range [0, 1]"));
+  }
+
+  if !material.ior.is_valid() {
+    return Err(Error::msg("Material IOR should be in range [1, 4] over the visible spectrum"));
+  }
+
+  if material.kind == MaterialKind::Subsurface &&
+    !(material.scattering_distance > 0.0 && material.scattering_distance.is_finite() && is_valid_color(&material.scattering_color))
+  {
+    return Err(Error::msg("Subsurface scattering distance should be positive and scattering color can't be negative"));
+  }
+
+  if let Some(coat) = &material.coat {
+    if !(1.0..=3.0).contains(&coat.ior) || !(0.0..=1.0).contains(&coat.roughness) {
+      return Err(Error::msg("Coat IOR should be in range [1, 3] and roughness in range [0, 1]"));
+    }
+
+    if let Some(film) = &coat.thin_film {
+      if !(film.thickness >= 0.0 && film.thickness.is_finite() && (1.0..=3.0).contains(&film.ior)) {
+        return Err(Error::msg("Thin film thickness can't be negative and its IOR should be in range [1, 3]"));
+      }
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,736 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use anyhow::{Context, Error, Result};
+
+use super::{
+  Camera, Coat, Color, Fog, HdrImage, Ior, Material, MaterialKind, PhysicalSky, ProceduralKind, Scene, SceneBuilder, SkyMapping,
+  TextureHandle, ThinFilm, Volume,
+};
+use super::math::Vector3;
+
+// textures are loaded once and shared by the scene rebuilds, the key is the path relative to the scene root
+pub type TextureCache = HashMap<String, Rc<HdrImage>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraDesc {
+  pub eye: Vector3,
+  pub look_at: Vector3,
+  pub fov: f32,
+}
+
+// sun of the physical sky, the angles are in degrees, the azimuth goes from the +z axis towards the +x axis
+#[derive(Debug, Clone, PartialEq)]
+pub struct SunDesc {
+  pub elevation: f32,
+  pub azimuth: f32,
+  pub turbidity: f32,
+  pub ground_albedo: f32,
+  pub power: f32,
+}
+
+impl Default for SunDesc {
+  fn default() -> SunDesc {
+    SunDesc {
+      elevation: 30.0,
+      azimuth: 45.0,
+      turbidity: 3.0,
+      ground_albedo: 0.3,
+      power: 1.0,
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LightDesc {
+  pub origin: Vector3,
+  pub radius: f32,
+  pub color: Color,
+  pub power: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+  Sphere {
+    center: Vector3,
+    radius: f32,
+    texture: Option<String>,
+  },
+  Triangle {
+    vertices: [Vector3; 3],
+    texture: Option<String>,
+    uvs: [(f32, f32); 3],
+  },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectDesc {
+  pub shape: Shape,
+  pub material: Material,
+}
+
+impl ObjectDesc {
+  pub fn get_center(&self) -> Vector3 {
+    match &self.shape {
+      Shape::Sphere { center, .. } => center.clone(),
+      Shape::Triangle { vertices, .. } => (&vertices[0] + &vertices[1] + &vertices[2]) / 3.0,
+    }
+  }
+
+  pub fn translate(&mut self, offset: &Vector3) {
+    match &mut self.shape {
+      Shape::Sphere { center, .. } => *center += offset,
+      Shape::Triangle { vertices, .. } => vertices.iter_mut().for_each(|vertex| *vertex += offset),
+    }
+  }
+
+  // scales the object around its center
+  pub fn scale(&mut self, factor: f32) {
+    let object_center = self.get_center();
+
+    match &mut self.shape {
+      Shape::Sphere { radius, .. } => *radius *= factor,
+      Shape::Triangle { vertices, .. } => vertices.iter_mut()
+        .for_each(|vertex| *vertex = &object_center + (&*vertex - &object_center) * factor),
+    }
+  }
+
+  // texture file path or procedural texture spec like "marble 2"
+  pub fn get_texture(&self) -> Option<&str> {
+    match &self.shape {
+      Shape::Sphere { texture, .. } | Shape::Triangle { texture, .. } => texture.as_deref(),
+    }
+  }
+
+  pub fn get_type_name(&self) -> &'static str {
+    match self.shape {
+      Shape::Sphere { .. } => "sphere",
+      Shape::Triangle { .. } => "triangle",
+    }
+  }
+}
+
+// scene file contents, the scene is rebuilt from it after every change
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneDesc {
+  pub camera: CameraDesc,
+  // the faces mapping takes six comma separated textures: right, left, top, bottom, front and back
+  pub skybox_texture: String,
+  pub sky_mapping: SkyMapping,
+  // degrees around the vertical axis
+  pub sky_rotation: f32,
+  pub sky_intensity: f32,
+  pub sky_lighting: bool,
+  // used by the physical sky only
+  pub sun: SunDesc,
+  pub diffuse_light_color: Color,
+  pub diffuse_light_power: f32,
+  pub lights: Vec<LightDesc>,
+  pub fog: Option<Fog>,
+  pub volumes: Vec<Volume>,
+  pub objects: Vec<ObjectDesc>,
+}
+
+impl Default for SceneDesc {
+  // built-in scene used when there is no scene file
+  fn default() -> SceneDesc {
+    let metal = |r, g, b, reflectivity| Material::new(MaterialKind::Metal, Color::new(r, g, b), reflectivity, 0.0);
+    let dielectric = |r, g, b, reflectivity| Material::new(MaterialKind::Dielectric, Color::new(r, g, b), reflectivity, 0.0);
+    let sphere = |x, y, z, radius, material| ObjectDesc { shape: Shape::Sphere { center: Vector3::new(x, y, z), radius, texture: None }, material };
+
+    let plane_vertices = [
+      Vector3::new(-14.0, 0.0, -10.0),
+      Vector3::new(-14.0, 0.0, 10.0),
+      Vector3::new(14.0, 0.0, 10.0),
+      Vector3::new(14.0, 0.0, -10.0),
+    ];
+
+    let plane_uvs = [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)];
+
+    let plane_triangle = |a: usize, b: usize, c: usize| ObjectDesc {
+      shape: Shape::Triangle {
+        vertices: [plane_vertices[a].clone(), plane_vertices[b].clone(), plane_vertices[c].clone()],
+        texture: Some(String::from("textures/periodic.tga")),
+        uvs: [plane_uvs[a], plane_uvs[b], plane_uvs[c]],
+      },
+      material: dielectric(1.0, 1.0, 1.0, 0.95),
+    };
+
+    SceneDesc {
+      camera: CameraDesc {
+        eye: Vector3::new(7.427, 3.494, -3.773),
+        look_at: Vector3::new(6.5981, 3.127, -3.352),
+        fov: 1.05,
+      },
+      skybox_texture: String::from("textures/skybox.tga"),
+      sky_mapping: SkyMapping::Cross,
+      sky_rotation: 0.0,
+      sky_intensity: 1.0,
+      sky_lighting: false,
+      sun: SunDesc::default(),
+      diffuse_light_color: Color::new(0.95, 0.95, 1.0),
+      diffuse_light_power: 0.15,
+      lights: vec![
+        LightDesc { origin: Vector3::new(11.8e9, 4.26e9, 3.08e9), radius: 3.48e8, color: Color::new(1.0, 1.0, 0.95), power: 0.85 },
+      ],
+      fog: None,
+      volumes: Vec::new(),
+      objects: vec![
+        sphere(-1.25, 1.5, -0.25, 1.5, metal(1.0, 1.0, 1.0, 1.0)),
+        sphere(0.15, 1.0, 1.75, 1.0, metal(1.0, 1.0, 1.0, 0.95)),
+        sphere(-3.0, 0.6, -3.0, 0.6, dielectric(1.0, 1.0, 1.0, 0.0)),
+        sphere(-0.5, 0.5, -2.5, 0.5, dielectric(0.5, 1.0, 0.15, 0.75)),
+        sphere(1.0, 0.4, -1.5, 0.4, dielectric(0.0, 0.5, 1.0, 1.0)),
+        sphere(1.8, 0.4, 0.1, 0.4, metal(1.0, 0.65, 0.45, 1.0)),
+        sphere(1.7, 0.5, 1.9, 0.5, metal(1.0, 0.90, 0.60, 0.75)),
+        sphere(0.6, 0.6, 4.2, 0.6, metal(0.9, 0.9, 0.9, 0.0)),
+        plane_triangle(0, 1, 3),
+        plane_triangle(1, 2, 3),
+      ],
+    }
+  }
+}
+
+impl SceneDesc {
+  pub fn load_from_file(path: &Path) -> Result<SceneDesc> {
+    let text = fs::read_to_string(path)
+      .with_context(|| format!("Failed to read scene file {}", path.display()))?;
+
+    SceneDesc::parse(&text)
+      .with_context(|| format!("Failed to parse scene file {}", path.display()))
+  }
+
+  pub fn save_to_file(&self, path: &Path) -> Result<()> {
+    fs::write(path, self.to_text())
+      .with_context(|| format!("Failed to write scene file {}", path.display()))
+  }
+
+  // INI-like scene file: the "[camera]", "[sky]" and "[fog]" sections set up the scene, every "[light]", "[volume]",
+  // "[sphere]" and "[triangle]" section adds a new light, volume or object, vectors and colors are comma separated
+  pub fn parse(text: &str) -> Result<SceneDesc> {
+    let mut scene = SceneDesc {
+      skybox_texture: String::new(),
+      lights: Vec::new(),
+      objects: Vec::new(),
+      ..SceneDesc::default()
+    };
+
+    let mut section = String::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+      let line = line.split(['#', ';']).next().unwrap().trim();
+
+      if line.is_empty() {
+        continue;
+      }
+
+      let result = if line.starts_with('[') && line.ends_with(']') {
+        section = line[1..line.len() - 1].trim().to_owned();
+        scene.add_section(&section)
+      } else if let Some((key, value)) = line.split_once('=') {
+        scene.set_value(&section, key.trim(), value.trim())
+      } else {
+        Err(Error::msg("Expected \"key = value\" or \"[section]\""))
+      };
+
+      result.with_context(|| format!("Line {}: {}", line_index + 1, line))?;
+    }
+
+    scene.validate()?;
+
+    Ok(scene)
+  }
+
+  fn add_section(&mut self, section: &str) -> Result<()> {
+    let material = Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.0, 0.0);
+
+    match section {
+      "camera" | "sky" => {}
+      "light" => self.lights.push(LightDesc { color: Color::new(1.0, 1.0, 1.0), power: 1.0, ..LightDesc::default() }),
+      "fog" => {
+        self.fog = Some(Fog { color: Color::new(1.0, 1.0, 1.0), density: 0.05, height_falloff: 0.0, base_height: 0.0 });
+      }
+      "volume" => self.volumes.push(Volume {
+        center: Vector3::default(),
+        radius: 1.0,
+        color: Color::new(1.0, 1.0, 1.0),
+        density: 1.0,
+      }),
+      "sphere" => self.objects.push(ObjectDesc {
+        shape: Shape::Sphere { center: Vector3::default(), radius: 1.0, texture: None },
+        material,
+      }),
+      "triangle" => self.objects.push(ObjectDesc {
+        shape: Shape::Triangle { vertices: Default::default(), texture: None, uvs: [(0.0, 0.0); 3] },
+        material,
+      }),
+      _ => return Err(Error::msg("Unknown section")),
+    }
+
+    Ok(())
+  }
+
+  fn set_value(&mut self, section: &str, key: &str, value: &str) -> Result<()> {
+    match (section, key) {
+      ("camera", "eye") => self.camera.eye = parse_vector(value)?,
+      ("camera", "look_at") => self.camera.look_at = parse_vector(value)?,
+      ("camera", "fov") => self.camera.fov = parse_value(value)?,
+      ("sky", "texture") => self.skybox_texture = value.to_owned(),
+      ("sky", "mapping") => {
+        self.sky_mapping = SkyMapping::from_name(value)
+          .ok_or_else(|| Error::msg(format!("Unknown sky mapping \"{}\"", value)))?;
+      }
+      ("sky", "rotation") => self.sky_rotation = parse_value(value)?,
+      ("sky", "intensity") => self.sky_intensity = parse_value(value)?,
+      ("sky", "lighting") => self.sky_lighting = parse_value(value)?,
+      ("sky", "sun_elevation") => self.sun.elevation = parse_value(value)?,
+      ("sky", "sun_azimuth") => self.sun.azimuth = parse_value(value)?,
+      ("sky", "turbidity") => self.sun.turbidity = parse_value(value)?,
+      ("sky", "ground_albedo") => self.sun.ground_albedo = parse_value(value)?,
+      ("sky", "sun_power") => self.sun.power = parse_value(value)?,
+      ("sky", "diffuse_color") => self.diffuse_light_color = parse_color(value)?,
+      ("sky", "diffuse_power") => self.diffuse_light_power = parse_value(value)?,
+      ("light", key) => {
+        let light = self.lights.last_mut().unwrap();
+
+        match key {
+          "origin" => light.origin = parse_vector(value)?,
+          "radius" => light.radius = parse_value(value)?,
+          "color" => light.color = parse_color(value)?,
+          "power" => light.power = parse_value(value)?,
+          _ => return Err(Error::msg("Unknown setting")),
+        }
+      }
+      ("fog", key) => {
+        let fog = self.fog.as_mut().unwrap();
+
+        match key {
+          "color" => fog.color = parse_color(value)?,
+          "density" => fog.density = parse_value(value)?,
+          "height_falloff" => fog.height_falloff = parse_value(value)?,
+          "base_height" => fog.base_height = parse_value(value)?,
+          _ => return Err(Error::msg("Unknown setting")),
+        }
+      }
+      ("volume", key) => {
+        let volume = self.volumes.last_mut().unwrap();
+
+        match key {
+          "center" => volume.center = parse_vector(value)?,
+          "radius" => volume.radius = parse_value(value)?,
+          "color" => volume.color = parse_color(value)?,
+          "density" => volume.density = parse_value(value)?,
+          _ => return Err(Error::msg("Unknown setting")),
+        }
+      }
+      ("sphere", key) | ("triangle", key) => {
+        let object = self.objects.last_mut().unwrap();
+
+        match (&mut object.shape, key) {
+          (_, "material") => {
+            object.material.kind = MaterialKind::from_name(value)
+              .ok_or_else(|| Error::msg(format!("Unknown material kind \"{}\"", value)))?;
+          }
+          (_, "color") => object.material.color = parse_color(value)?,
+          (_, "reflectivity") => object.material.reflectivity = parse_value(value)?,
+          (_, "transparency") => object.material.transparency = parse_value(value)?,
+          (_, "ior") => object.material.ior = Ior::parse(value).ok_or_else(|| Error::msg(format!("Invalid IOR \"{}\"", value)))?,
+          (_, "scattering_distance") => object.material.scattering_distance = parse_value(value)?,
+          (_, "scattering_color") => object.material.scattering_color = parse_color(value)?,
+          (_, "coat_ior") => object.material.coat.get_or_insert_with(default_coat).ior = parse_value(value)?,
+          (_, "coat_roughness") => object.material.coat.get_or_insert_with(default_coat).roughness = parse_value(value)?,
+          (_, "film_thickness") => get_thin_film(&mut object.material).thickness = parse_value(value)?,
+          (_, "film_ior") => get_thin_film(&mut object.material).ior = parse_value(value)?,
+          (Shape::Sphere { texture, .. }, "texture") | (Shape::Triangle { texture, .. }, "texture") => {
+            *texture = Some(value.to_owned());
+          }
+          (Shape::Sphere { center, .. }, "center") => *center = parse_vector(value)?,
+          (Shape::Sphere { radius, .. }, "radius") => *radius = parse_value(value)?,
+          (Shape::Triangle { vertices, .. }, "v0") => vertices[0] = parse_vector(value)?,
+          (Shape::Triangle { vertices, .. }, "v1") => vertices[1] = parse_vector(value)?,
+          (Shape::Triangle { vertices, .. }, "v2") => vertices[2] = parse_vector(value)?,
+          (Shape::Triangle { uvs, .. }, "uv0") => uvs[0] = parse_uv(value)?,
+          (Shape::Triangle { uvs, .. }, "uv1") => uvs[1] = parse_uv(value)?,
+          (Shape::Triangle { uvs, .. }, "uv2") => uvs[2] = parse_uv(value)?,
+          _ => return Err(Error::msg("Unknown setting")),
+        }
+      }
+      _ => return Err(Error::msg("Unknown setting")),
+    }
+
+    Ok(())
+  }
+
+  fn validate(&self) -> Result<()> {
+    if self.sky_mapping != SkyMapping::Physical && self.skybox_texture.is_empty() {
+      return Err(Error::msg("Sky texture is not set"));
+    }
+
+    if self.sky_mapping == SkyMapping::Physical {
+      let sun = &self.sun;
+
+      if !(-90.0..=90.0).contains(&sun.elevation) || !sun.azimuth.is_finite() {
+        return Err(Error::msg("Sun elevation should be in range [-90, 90]"));
+      }
+
+      if !(1.0..=20.0).contains(&sun.turbidity) || !(0.0..=1.0).contains(&sun.ground_albedo) {
+        return Err(Error::msg("Sky turbidity should be in range [1, 20] and ground albedo in range [0, 1]"));
+      }
+
+      if !(sun.power >= 0.0 && sun.power.is_finite()) {
+        return Err(Error::msg("Sun power can't be negative"));
+      }
+    }
+
+    if self.sky_mapping == SkyMapping::Faces && self.get_sky_texture_names().len() != 6 {
+      return Err(Error::msg("Sky faces mapping needs six textures"));
+    }
+
+    if !(self.sky_intensity >= 0.0 && self.sky_intensity.is_finite()) {
+      return Err(Error::msg("Sky intensity can't be negative"));
+    }
+
+    if !self.sky_rotation.is_finite() {
+      return Err(Error::msg("Sky rotation should be finite"));
+    }
+
+    if (&self.camera.look_at - &self.camera.eye).length() <= 0.0 {
+      return Err(Error::msg("Camera can't look at its own position"));
+    }
+
+    if !(self.camera.fov > 0.0 && self.camera.fov < std::f32::consts::PI) {
+      return Err(Error::msg("Camera fov should be in range (0, pi)"));
+    }
+
+    if self.lights.iter().any(|light| light.radius < 0.0) {
+      return Err(Error::msg("Light radius can't be negative"));
+    }
+
+    if let Some(fog) = &self.fog {
+      if !is_albedo(&fog.color) {
+        return Err(Error::msg("Fog color should be in range [0, 1]"));
+      }
+
+      if !(fog.density >= 0.0 && fog.height_falloff >= 0.0 && fog.base_height.is_finite()) {
+        return Err(Error::msg("Fog density and height falloff can't be negative"));
+      }
+    }
+
+    for volume in &self.volumes {
+      if !is_albedo(&volume.color) {
+        return Err(Error::msg("Volume color should be in range [0, 1]"));
+      }
+
+      if volume.radius <= 0.0 || volume.density < 0.0 {
+        return Err(Error::msg("Volume radius should be positive and density can't be negative"));
+      }
+    }
+
+    // the materials are validated by the scene builder
+    for object in &self.objects {
+      if let Shape::Sphere { radius, .. } = object.shape {
+        if radius <= 0.0 {
+          return Err(Error::msg("Sphere radius should be positive"));
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn to_text(&self) -> String {
+    let mut text = String::new();
+    let camera = &self.camera;
+
+    // writing to a string never fails
+    writeln!(text, "[camera]").unwrap();
+    writeln!(text, "eye = {}", format_vector(&camera.eye)).unwrap();
+    writeln!(text, "look_at = {}", format_vector(&camera.look_at)).unwrap();
+    writeln!(text, "fov = {}", camera.fov).unwrap();
+    writeln!(text).unwrap();
+    writeln!(text, "[sky]").unwrap();
+    if !self.skybox_texture.is_empty() {
+      writeln!(text, "texture = {}", self.skybox_texture).unwrap();
+    }
+
+    writeln!(text, "mapping = {}", self.sky_mapping.name()).unwrap();
+
+    if self.sky_mapping == SkyMapping::Physical {
+      writeln!(text, "sun_elevation = {}", self.sun.elevation).unwrap();
+      writeln!(text, "sun_azimuth = {}", self.sun.azimuth).unwrap();
+      writeln!(text, "turbidity = {}", self.sun.turbidity).unwrap();
+      writeln!(text, "ground_albedo = {}", self.sun.ground_albedo).unwrap();
+      writeln!(text, "sun_power = {}", self.sun.power).unwrap();
+    }
+
+    writeln!(text, "rotation = {}", self.sky_rotation).unwrap();
+    writeln!(text, "intensity = {}", self.sky_intensity).unwrap();
+    writeln!(text, "lighting = {}", self.sky_lighting).unwrap();
+    writeln!(text, "diffuse_color = {}", format_color(&self.diffuse_light_color)).unwrap();
+    writeln!(text, "diffuse_power = {}", self.diffuse_light_power).unwrap();
+
+    for light in &self.lights {
+      writeln!(text).unwrap();
+      writeln!(text, "[light]").unwrap();
+      writeln!(text, "origin = {}", format_vector(&light.origin)).unwrap();
+      writeln!(text, "radius = {}", light.radius).unwrap();
+      writeln!(text, "color = {}", format_color(&light.color)).unwrap();
+      writeln!(text, "power = {}", light.power).unwrap();
+    }
+
+    if let Some(fog) = &self.fog {
+      writeln!(text).unwrap();
+      writeln!(text, "[fog]").unwrap();
+      writeln!(text, "color = {}", format_color(&fog.color)).unwrap();
+      writeln!(text, "density = {}", fog.density).unwrap();
+      writeln!(text, "height_falloff = {}", fog.height_falloff).unwrap();
+      writeln!(text, "base_height = {}", fog.base_height).unwrap();
+    }
+
+    for volume in &self.volumes {
+      writeln!(text).unwrap();
+      writeln!(text, "[volume]").unwrap();
+      writeln!(text, "center = {}", format_vector(&volume.center)).unwrap();
+      writeln!(text, "radius = {}", volume.radius).unwrap();
+      writeln!(text, "color = {}", format_color(&volume.color)).unwrap();
+      writeln!(text, "density = {}", volume.density).unwrap();
+    }
+
+    for object in &self.objects {
+      writeln!(text).unwrap();
+      writeln!(text, "[{}]", object.get_type_name()).unwrap();
+
+      match &object.shape {
+        Shape::Sphere { center, radius, texture } => {
+          writeln!(text, "center = {}", format_vector(center)).unwrap();
+          writeln!(text, "radius = {}", radius).unwrap();
+
+          if let Some(texture) = texture {
+            writeln!(text, "texture = {}", texture).unwrap();
+          }
+        }
+        Shape::Triangle { vertices, texture, uvs } => {
+          for (index, vertex) in vertices.iter().enumerate() {
+            writeln!(text, "v{} = {}", index, format_vector(vertex)).unwrap();
+          }
+
+          if let Some(texture) = texture {
+            writeln!(text, "texture = {}", texture).unwrap();
+
+            for (index, (u, v)) in uvs.iter().enumerate() {
+              writeln!(text, "uv{} = {}, {}", index, u, v).unwrap();
+            }
+          }
+        }
+      }
+
+      let material = &object.material;
+      writeln!(text, "material = {}", material.kind.name()).unwrap();
+      writeln!(text, "color = {}", format_color(&material.color)).unwrap();
+      writeln!(text, "reflectivity = {}", material.reflectivity).unwrap();
+      writeln!(text, "transparency = {}", material.transparency).unwrap();
+
+      if material.ior != Ior::default() {
+        writeln!(text, "ior = {}", material.ior).unwrap();
+      }
+
+      if material.kind == MaterialKind::Subsurface {
+        writeln!(text, "scattering_distance = {}", material.scattering_distance).unwrap();
+        writeln!(text, "scattering_color = {}", format_color(&material.scattering_color)).unwrap();
+      }
+
+      if let Some(coat) = &material.coat {
+        writeln!(text, "coat_ior = {}", coat.ior).unwrap();
+        writeln!(text, "coat_roughness = {}", coat.roughness).unwrap();
+
+        if let Some(film) = &coat.thin_film {
+          writeln!(text, "film_thickness = {}", film.thickness).unwrap();
+          writeln!(text, "film_ior = {}", film.ior).unwrap();
+        }
+      }
+    }
+
+    text
+  }
+
+  pub fn get_camera(&self) -> Camera {
+    Camera::new(self.camera.eye.clone(), self.camera.look_at.clone(), self.camera.fov)
+  }
+
+  // the camera pose is stored with the scene, so the saved scene opens with the same view
+  pub fn set_camera(&mut self, camera: &Camera) {
+    self.camera = CameraDesc {
+      eye: camera.eye.clone(),
+      look_at: &camera.eye + &camera.view * Vector3::new(0.0, 0.0, 1.0),
+      fov: camera.fov,
+    };
+  }
+
+  // textures are taken from the cache, the missing ones are loaded relative to the root path,
+  // procedural textures are created for every build
+  pub fn build(&self, root_path: &Path, textures: &mut TextureCache) -> Result<Scene> {
+    let camera = &self.camera;
+    let mut builder = SceneBuilder::new();
+
+    builder = match self.sky_mapping {
+      SkyMapping::Faces => {
+        let faces = self.get_sky_texture_names().into_iter()
+          .map(|name| load_texture(root_path, name, textures))
+          .collect::<Result<Vec<_>>>()?;
+
+        builder.sky_faces(&faces.try_into().map_err(|_| Error::msg("Sky faces mapping needs six textures"))?)
+      }
+      SkyMapping::Physical => {
+        let sun = &self.sun;
+        let direction = PhysicalSky::sun_direction(sun.elevation.to_radians(), sun.azimuth.to_radians());
+        builder.physical_sky(PhysicalSky::new(&direction, sun.turbidity, sun.ground_albedo), sun.power)
+      }
+      mapping => builder.sky_image(&load_texture(root_path, &self.skybox_texture, textures)?, mapping),
+    };
+
+    builder = builder
+      .sky_rotation(self.sky_rotation.to_radians())
+      .sky_intensity(self.sky_intensity)
+      .sky_lighting(self.sky_lighting)
+      .diffuse_light(self.diffuse_light_color.clone(), self.diffuse_light_power)
+      .camera(camera.eye.clone(), camera.look_at.clone(), camera.fov);
+
+    let mut object_textures = HashMap::<&str, TextureHandle>::new();
+
+    for name in self.get_texture_names() {
+      let texture: TextureHandle = load_texture(root_path, name, textures)?;
+      builder = builder.texture(name, &texture);
+      object_textures.insert(name, texture);
+    }
+
+    for object in &self.objects {
+      if let Some((spec, (kind, scale))) = object.get_texture().and_then(|spec| Some((spec, ProceduralKind::parse(spec)?))) {
+        if !object_textures.contains_key(spec) {
+          let texture = kind.create(scale);
+          builder = builder.texture(spec, &texture);
+          object_textures.insert(spec, texture);
+        }
+      }
+    }
+
+    for light in &self.lights {
+      builder = builder.light(light.origin.clone(), light.radius, light.color.clone(), light.power);
+    }
+
+    if let Some(fog) = &self.fog {
+      builder = builder.height_fog(fog.color.clone(), fog.density, fog.base_height, fog.height_falloff);
+    }
+
+    for volume in &self.volumes {
+      builder = builder.volume(volume.center.clone(), volume.radius, volume.color.clone(), volume.density);
+    }
+
+    for object in &self.objects {
+      let material = object.material.clone();
+
+      builder = match &object.shape {
+        Shape::Sphere { center, radius, texture: None } => builder.sphere(center.clone(), *radius, material),
+        Shape::Sphere { center, radius, texture: Some(texture) } => {
+          builder.textured_sphere(center.clone(), *radius, material, &object_textures[texture.as_str()])
+        }
+        Shape::Triangle { vertices, texture: None, .. } => builder.triangle(vertices.clone(), material),
+        Shape::Triangle { vertices, texture: Some(texture), uvs } => {
+          builder.textured_triangle(vertices.clone(), *uvs, material, &object_textures[texture.as_str()])
+        }
+      };
+    }
+
+    // the viewer keeps its own camera pose, the camera of the description is taken by the get_camera
+    let (scene, _) = builder.build()?;
+
+    Ok(scene)
+  }
+
+  // texture files used by the scene, relative to the root path, the procedural textures have no files
+  pub fn get_texture_names(&self) -> Vec<&str> {
+    let mut names = self.get_sky_texture_names();
+
+    for texture in self.objects.iter().filter_map(ObjectDesc::get_texture) {
+      if ProceduralKind::parse(texture).is_none() && !names.contains(&texture) {
+        names.push(texture);
+      }
+    }
+
+    names
+  }
+
+  pub fn get_sky_texture_names(&self) -> Vec<&str> {
+    match self.sky_mapping {
+      SkyMapping::Faces => self.skybox_texture.split(',').map(str::trim).collect(),
+      SkyMapping::Physical => Vec::new(),
+      _ => vec![self.skybox_texture.as_str()],
+    }
+  }
+}
+
+fn load_texture(root_path: &Path, name: &str, textures: &mut TextureCache) -> Result<Rc<HdrImage>> {
+  if let Some(texture) = textures.get(name) {
+    return Ok(Rc::clone(texture));
+  }
+
+  let texture = HdrImage::load_from_file(&root_path.join(name))
+    .with_context(|| format!("Failed to load texture {}", name))?;
+  let texture = Rc::new(texture);
+  textures.insert(name.to_owned(), Rc::clone(&texture));
+
+  Ok(texture)
+}
+
+fn parse_value<T: FromStr>(value: &str) -> Result<T> {
+  value.parse::<T>().map_err(|_| Error::msg(format!("Invalid value \"{}\"", value)))
+}
+
+fn parse_numbers<const N: usize>(value: &str) -> Result<[f32; N]> {
+  let numbers = value.split(',')
+    .map(|number| parse_value::<f32>(number.trim()))
+    .collect::<Result<Vec<f32>>>()?;
+
+  numbers.try_into().map_err(|_| Error::msg(format!("Expected {} comma separated numbers", N)))
+}
+
+fn parse_vector(value: &str) -> Result<Vector3> {
+  let [x, y, z] = parse_numbers(value)?;
+  Ok(Vector3::new(x, y, z))
+}
+
+fn parse_color(value: &str) -> Result<Color> {
+  let [r, g, b] = parse_numbers(value)?;
+  Ok(Color::new(r, g, b))
+}
+
+fn parse_uv(value: &str) -> Result<(f32, f32)> {
+  let [u, v] = parse_numbers(value)?;
+  Ok((u, v))
+}
+
+// the coat keys of an object add the lacquer coat, the film keys add the soap film to it
+fn default_coat() -> Coat {
+  Coat::new(1.5, 0.0)
+}
+
+fn get_thin_film(material: &mut Material) -> &mut ThinFilm {
+  let coat = material.coat.get_or_insert_with(default_coat);
+  coat.thin_film.get_or_insert(ThinFilm { thickness: 400.0, ior: 1.33 })
+}
+
+fn is_albedo(color: &Color) -> bool {
+  [color.r, color.g, color.b].iter().all(|value| (0.0..=1.0).contains(value))
+}
+
+fn format_vector(vector: &Vector3) -> String {
+  format!("{}, {}, {}", vector.x, vector.y, vector.z)
+}
+
+fn format_color(color: &Color) -> String {
+  format!("{}, {}, {}", color.r, color.g, color.b)
+}
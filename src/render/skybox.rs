@@ -1,74 +1,384 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+
 use anyhow::Result;
 
-use super::{Color, Texture};
+use super::{Color, HdrImage, PhysicalSky, Texture};
 use super::math::constants::VERY_SMALL_NUMBER;
 use super::math::Vector3;
 
-#[derive(Debug, Default)]
+// resolution of the latitude-longitude brightness map used to pick the sky light directions
+const LIGHT_MAP_WIDTH: usize = 64;
+const LIGHT_MAP_HEIGHT: usize = 32;
+// cube skies are sampled several times per map cell to catch the small bright spots like the sun
+const LIGHT_MAP_SUPERSAMPLES: usize = 4;
+// share of the mean brightness added to every cell, so the dark parts of the sky still can be picked
+const LIGHT_MAP_UNIFORM_SHARE: f32 = 0.01;
+
+// where the sky colors come from: the image wrapped around the scene or the analytic sky
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SkyMapping {
+  // cube unfolded into a 4x3 tiles cross
+  Cross,
+  // latitude-longitude image, the longitude goes along the width
+  Equirectangular,
+  // six separate cube face images: right, left, top, bottom, front and back
+  Faces,
+  // analytic daylight sky lit by the sun, needs no image
+  Physical,
+}
+
+pub const SKY_MAPPINGS: [SkyMapping; 4] = [
+  SkyMapping::Cross,
+  SkyMapping::Equirectangular,
+  SkyMapping::Faces,
+  SkyMapping::Physical,
+];
+
+impl SkyMapping {
+  pub fn from_name(name: &str) -> Option<SkyMapping> {
+    SKY_MAPPINGS.iter().copied().find(|mapping| mapping.name() == name)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      SkyMapping::Cross => "cross",
+      SkyMapping::Equirectangular => "equirectangular",
+      SkyMapping::Faces => "faces",
+      SkyMapping::Physical => "physical",
+    }
+  }
+}
+
+#[derive(Debug)]
+enum Source {
+  Cross {
+    half_tile_width: f32,
+    half_tile_height: f32,
+    image: Rc<HdrImage>,
+  },
+  Equirectangular(Rc<HdrImage>),
+  Faces(Vec<Rc<HdrImage>>),
+  Physical(PhysicalSky),
+}
+
+// direction to the sky light picked by its brightness
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkySample {
+  pub direction: Vector3,
+  pub color: Color,
+  // probability density per solid angle
+  pub pdf: f32,
+}
+
+// piecewise constant distribution of the sky brightness over the latitude-longitude cells,
+// the rows go from the bottom to the top
+#[derive(Debug)]
+struct LightMap {
+  weights: Vec<f32>,
+  row_cdf: Vec<f32>,
+  cell_cdfs: Vec<Vec<f32>>,
+}
+
+#[derive(Debug)]
 pub struct Skybox {
-  half_tile_width: f32,
-  half_tile_height: f32,
-  texture: Texture,
+  source: Source,
+  rotation: f32,
+  intensity: f32,
+  light_map: Option<LightMap>,
+}
+
+impl Default for Skybox {
+  fn default() -> Skybox {
+    Skybox::from_color(&Color::default())
+  }
 }
 
 impl Skybox {
   pub fn new(texture: Texture) -> Skybox {
+    Skybox::cross(Rc::new(HdrImage::from(&texture)))
+  }
+
+  // single color sky of the minimal texture size that still has all the cube sides
+  pub fn from_color(color: &Color) -> Skybox {
+    let texture = Texture {
+      width: 8,
+      height: 6,
+      color_buffer: color.rgb().repeat(8 * 6),
+    };
+
+    Skybox::new(texture)
+  }
+
+  pub fn cross(image: Rc<HdrImage>) -> Skybox {
+    Skybox::from_source(Source::Cross {
+      half_tile_width: 1.0 / 8.0 - 1.0 / image.width as f32 - f32::EPSILON,
+      half_tile_height: 1.0 / 6.0 - 1.0 / image.height as f32 - f32::EPSILON,
+      image,
+    })
+  }
+
+  pub fn equirectangular(image: Rc<HdrImage>) -> Skybox {
+    Skybox::from_source(Source::Equirectangular(image))
+  }
+
+  // faces go in the order of the axes: +x, -x, +y, -y, +z, -z
+  pub fn from_faces(faces: [Rc<HdrImage>; 6]) -> Skybox {
+    Skybox::from_source(Source::Faces(faces.to_vec()))
+  }
+
+  pub fn physical(sky: PhysicalSky) -> Skybox {
+    Skybox::from_source(Source::Physical(sky))
+  }
+
+  fn from_source(source: Source) -> Skybox {
     Skybox {
-      half_tile_width: 1.0 / 8.0 - 1.0 / texture.width as f32 - f32::EPSILON,
-      half_tile_height: 1.0 / 6.0 - 1.0 / texture.height as f32 - f32::EPSILON,
-      texture,
+      source,
+      rotation: 0.0,
+      intensity: 1.0,
+      light_map: None,
+    }
+  }
+
+  pub fn get_mapping(&self) -> SkyMapping {
+    match self.source {
+      Source::Cross { .. } => SkyMapping::Cross,
+      Source::Equirectangular(_) => SkyMapping::Equirectangular,
+      Source::Faces(_) => SkyMapping::Faces,
+      Source::Physical(_) => SkyMapping::Physical,
     }
   }
 
+  // direction to the sun of the physical sky turned with the sky and the color of the sun light
+  pub fn get_sun(&self) -> Option<(Vector3, Color)> {
+    match &self.source {
+      Source::Physical(sky) => Some((rotate_y(sky.get_sun_direction(), self.rotation), sky.get_sun_color())),
+      _ => None,
+    }
+  }
+
+  // rotation of the sky around the vertical axis in radians
+  pub fn set_rotation(&mut self, rotation: f32) {
+    self.rotation = rotation;
+  }
+
+  pub fn get_rotation(&self) -> f32 {
+    self.rotation
+  }
+
+  // multiplier of the sky colors
+  pub fn set_intensity(&mut self, intensity: f32) {
+    assert!(intensity >= 0.0 && intensity.is_finite(), "Invalid argument");
+    self.intensity = intensity;
+  }
+
+  pub fn get_intensity(&self) -> f32 {
+    self.intensity
+  }
+
+  // the lighting sky is a light source of the scene, its colors are the radiance and aren't scaled by the scene lights
+  pub fn set_lighting(&mut self, is_lighting: bool) -> Result<()> {
+    self.light_map = if is_lighting { Some(LightMap::new(self)?) } else { None };
+    Ok(())
+  }
+
+  pub fn is_lighting(&self) -> bool {
+    self.light_map.is_some()
+  }
+
   pub fn trace(&self, ray: &Vector3) -> Result<Color> {
-    //uv origins of cube sides centers in skybox texture
-    const LEFT_U: f32 = 1.0 / 8.0;
-    const LEFT_V: f32 = 3.0 / 6.0;
-    const FRONT_U: f32 = 3.0 / 8.0;
-    const FRONT_V: f32 = 3.0 / 6.0;
-    const RIGHT_U: f32 = 5.0 / 8.0;
-    const RIGHT_V: f32 = 3.0 / 6.0;
-    const BACK_U: f32 = 7.0 / 8.0;
-    const BACK_V: f32 = 3.0 / 6.0;
-    const TOP_U: f32 = 3.0 / 8.0;
-    const TOP_V: f32 = 5.0 / 6.0;
-    const BOTTOM_U: f32 = 3.0 / 8.0;
-    const BOTTOM_V: f32 = 1.0 / 6.0;
-
-    let ray = ray.normalized();
-    let ax = ray.x.abs() + VERY_SMALL_NUMBER;
-    let ay = ray.y.abs() + VERY_SMALL_NUMBER;
-    let az = ray.z.abs() + VERY_SMALL_NUMBER;
-    let u: f32;
-    let v: f32;
-
-    if az >= ax && az >= ay {
-      if ray.z > 0.0 {
-        u = FRONT_U + ray.x / az * self.half_tile_width;
-        v = FRONT_V + ray.y / az * self.half_tile_height;
-      } else {
-        u = BACK_U - ray.x / az * self.half_tile_width;
-        v = BACK_V + ray.y / az * self.half_tile_height;
+    let ray = rotate_y(&ray.normalized(), -self.rotation);
+    Ok(self.trace_unrotated(&ray)? * self.intensity)
+  }
+
+  // picks a direction to the sky, the bright parts are picked more often, none if the sky doesn't light the scene
+  pub fn sample_light(&self, sample: (f32, f32)) -> Result<Option<SkySample>> {
+    let light_map = match &self.light_map {
+      Some(light_map) => light_map,
+      None => return Ok(None),
+    };
+
+    let (u, v, cell_pdf) = match light_map.sample(sample) {
+      Some(cell) => cell,
+      None => return Ok(None),
+    };
+
+    let latitude_cos = f32::cos((v - 0.5) * PI);
+
+    if latitude_cos < VERY_SMALL_NUMBER {
+      return Ok(None);
+    }
+
+    let direction = rotate_y(&equirectangular_direction(u, v), self.rotation);
+    let color = self.trace(&direction)?;
+    let pdf = cell_pdf / (2.0 * PI * PI * latitude_cos);
+
+    Ok(Some(SkySample { direction, color, pdf }))
+  }
+
+  fn trace_unrotated(&self, ray: &Vector3) -> Result<Color> {
+    //uv origins of cube sides centers in skybox texture: right, left, top, bottom, front, back
+    const CROSS_CENTERS: [(f32, f32); 6] = [
+      (5.0 / 8.0, 3.0 / 6.0),
+      (1.0 / 8.0, 3.0 / 6.0),
+      (3.0 / 8.0, 5.0 / 6.0),
+      (3.0 / 8.0, 1.0 / 6.0),
+      (3.0 / 8.0, 3.0 / 6.0),
+      (7.0 / 8.0, 3.0 / 6.0),
+    ];
+
+    match &self.source {
+      Source::Cross { half_tile_width, half_tile_height, image } => {
+        let (face, s, t) = cube_face(ray);
+        let (center_u, center_v) = CROSS_CENTERS[face];
+        image.get_texel_color(center_u + s * half_tile_width, center_v + t * half_tile_height)
       }
-    } else if ax >= ay && ax >= az {
-      if ray.x > 0.0 {
-        u = RIGHT_U - ray.z / ax * self.half_tile_width;
-        v = RIGHT_V + ray.y / ax * self.half_tile_height;
-      } else {
-        u = LEFT_U + ray.z / ax * self.half_tile_width;
-        v = LEFT_V + ray.y / ax * self.half_tile_height;
+      Source::Equirectangular(image) => {
+        let u = 0.5 + f32::atan2(ray.z, ray.x) / (2.0 * PI);
+        let v = 0.5 + f32::asin(ray.y.clamp(-1.0, 1.0)) / PI;
+        image.get_texel_color(u.clamp(0.0, 1.0), v.clamp(0.0, 1.0))
       }
-    } else {
-      if ray.y > 0.0 {
-        u = TOP_U + ray.x / ay * self.half_tile_width;
-        v = TOP_V - ray.z / ay * self.half_tile_height;
-      } else {
-        u = BOTTOM_U + ray.x / ay * self.half_tile_width;
-        v = BOTTOM_V + ray.z / ay * self.half_tile_height;
+      Source::Faces(faces) => {
+        let (face, s, t) = cube_face(ray);
+        faces[face].get_texel_color((0.5 + 0.5 * s).clamp(0.0, 1.0), (0.5 + 0.5 * t).clamp(0.0, 1.0))
+      }
+      Source::Physical(sky) => Ok(sky.get_sky_color(ray)),
+    }
+  }
+}
+
+impl LightMap {
+  fn new(skybox: &Skybox) -> Result<LightMap> {
+    let mut weights = vec![0.0; LIGHT_MAP_WIDTH * LIGHT_MAP_HEIGHT];
+
+    for row in 0..LIGHT_MAP_HEIGHT {
+      for column in 0..LIGHT_MAP_WIDTH {
+        weights[column + row * LIGHT_MAP_WIDTH] = cell_luminance(skybox, column, row)?;
+      }
+    }
+
+    let mean = weights.iter().sum::<f32>() / weights.len() as f32;
+
+    // the cells near the poles cover the smaller solid angle
+    for row in 0..LIGHT_MAP_HEIGHT {
+      let latitude_cos = f32::cos(((row as f32 + 0.5) / LIGHT_MAP_HEIGHT as f32 - 0.5) * PI);
+
+      for weight in &mut weights[row * LIGHT_MAP_WIDTH..(row + 1) * LIGHT_MAP_WIDTH] {
+        *weight = (*weight + mean * LIGHT_MAP_UNIFORM_SHARE) * latitude_cos;
       }
     }
 
-    self.texture.get_texel_color(u, v)
+    let cell_cdfs: Vec<Vec<f32>> = weights.chunks(LIGHT_MAP_WIDTH).map(cumulative_sum).collect();
+    let row_weights: Vec<f32> = cell_cdfs.iter().map(|cdf| cdf[LIGHT_MAP_WIDTH]).collect();
+    let row_cdf = cumulative_sum(&row_weights);
+
+    Ok(LightMap { weights, row_cdf, cell_cdfs })
+  }
+
+  // equirectangular coordinates of the picked point and the probability density per unit of the uv area
+  fn sample(&self, sample: (f32, f32)) -> Option<(f32, f32, f32)> {
+    let total = self.row_cdf[LIGHT_MAP_HEIGHT];
+
+    if total <= 0.0 {
+      return None;
+    }
+
+    let (row, row_fract) = sample_cdf(&self.row_cdf, sample.1);
+    let (column, column_fract) = sample_cdf(&self.cell_cdfs[row], sample.0);
+    let weight = self.weights[column + row * LIGHT_MAP_WIDTH];
+    let pdf = weight * (LIGHT_MAP_WIDTH * LIGHT_MAP_HEIGHT) as f32 / total;
+
+    let u = (column as f32 + column_fract) / LIGHT_MAP_WIDTH as f32;
+    let v = (row as f32 + row_fract) / LIGHT_MAP_HEIGHT as f32;
+
+    Some((u, v, pdf))
+  }
+}
+
+// the equirectangular image is averaged over the cell pixels, the cube skies are supersampled
+fn cell_luminance(skybox: &Skybox, column: usize, row: usize) -> Result<f32> {
+  if let Source::Equirectangular(image) = &skybox.source {
+    let x_range = column * image.width as usize / LIGHT_MAP_WIDTH..(column + 1) * image.width as usize / LIGHT_MAP_WIDTH;
+    let y_range = row * image.height as usize / LIGHT_MAP_HEIGHT..(row + 1) * image.height as usize / LIGHT_MAP_HEIGHT;
+
+    if !x_range.is_empty() && !y_range.is_empty() {
+      let count = x_range.len() * y_range.len();
+      let sum = y_range.flat_map(|y| x_range.clone().map(move |x| (x, y)))
+        .map(|(x, y)| image.pixels[x + y * image.width as usize].luminance())
+        .sum::<f32>();
+
+      return Ok(sum / count as f32);
+    }
+  }
+
+  let mut sum = 0.0;
+
+  for sub_row in 0..LIGHT_MAP_SUPERSAMPLES {
+    for sub_column in 0..LIGHT_MAP_SUPERSAMPLES {
+      let u = (column as f32 + (sub_column as f32 + 0.5) / LIGHT_MAP_SUPERSAMPLES as f32) / LIGHT_MAP_WIDTH as f32;
+      let v = (row as f32 + (sub_row as f32 + 0.5) / LIGHT_MAP_SUPERSAMPLES as f32) / LIGHT_MAP_HEIGHT as f32;
+      sum += skybox.trace_unrotated(&equirectangular_direction(u, v))?.luminance();
+    }
+  }
+
+  Ok(sum / (LIGHT_MAP_SUPERSAMPLES * LIGHT_MAP_SUPERSAMPLES) as f32)
+}
+
+fn cumulative_sum(values: &[f32]) -> Vec<f32> {
+  let mut cdf = Vec::with_capacity(values.len() + 1);
+  cdf.push(0.0);
+
+  for value in values {
+    cdf.push(cdf[cdf.len() - 1] + value);
+  }
+
+  cdf
+}
+
+// index of the picked interval and the position inside it
+fn sample_cdf(cdf: &[f32], value: f32) -> (usize, f32) {
+  let count = cdf.len() - 1;
+  let target = value * cdf[count];
+  let index = (cdf.partition_point(|&sum| sum <= target).max(1) - 1).min(count - 1);
+  let width = cdf[index + 1] - cdf[index];
+  let fract = if width > 0.0 { ((target - cdf[index]) / width).clamp(0.0, 1.0) } else { 0.5 };
+
+  (index, fract)
+}
+
+fn equirectangular_direction(u: f32, v: f32) -> Vector3 {
+  let longitude = (u - 0.5) * 2.0 * PI;
+  let latitude = (v - 0.5) * PI;
+
+  Vector3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin())
+}
+
+// cube face of the ray and the position on it in range [-1, 1]
+fn cube_face(ray: &Vector3) -> (usize, f32, f32) {
+  let ax = ray.x.abs() + VERY_SMALL_NUMBER;
+  let ay = ray.y.abs() + VERY_SMALL_NUMBER;
+  let az = ray.z.abs() + VERY_SMALL_NUMBER;
+
+  if az >= ax && az >= ay {
+    if ray.z > 0.0 {
+      (4, ray.x / az, ray.y / az)
+    } else {
+      (5, -ray.x / az, ray.y / az)
+    }
+  } else if ax >= ay && ax >= az {
+    if ray.x > 0.0 {
+      (0, -ray.z / ax, ray.y / ax)
+    } else {
+      (1, ray.z / ax, ray.y / ax)
+    }
+  } else if ray.y > 0.0 {
+    (2, ray.x / ay, -ray.z / ay)
+  } else {
+    (3, ray.x / ay, ray.z / ay)
   }
 }
 
+fn rotate_y(vector: &Vector3, angle: f32) -> Vector3 {
+  let (sin, cos) = angle.sin_cos();
+  Vector3::new(vector.x * cos + vector.z * sin, vector.y, -vector.x * sin + vector.z * cos)
+}
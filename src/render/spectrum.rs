@@ -0,0 +1,57 @@
+use super::Color;
+
+// visible range sampled by the spectral paths, in nanometers
+pub const MIN_WAVELENGTH: f32 = 380.0;
+pub const MAX_WAVELENGTH: f32 = 780.0;
+// the Fraunhofer d line, the IOR of the RGB paths is taken at it
+pub const MEAN_WAVELENGTH: f32 = 587.6;
+
+// makes the mean RGB of the uniformly sampled wavelengths white, the constant spectrum stays white
+const WHITE_SCALE: [f32; 3] = [3.1162, 3.9390, 4.1216];
+
+// uniform over the visible range
+pub fn sample_wavelength(sample: f32) -> f32 {
+  MIN_WAVELENGTH + sample * (MAX_WAVELENGTH - MIN_WAVELENGTH)
+}
+
+// CIE 1931 color matching functions by the multi-lobe fit of C. Wyman, P.-P. Sloan and P. Shirley,
+// "Simple Analytic Approximations to the CIE XYZ Color Matching Functions"
+pub fn wavelength_to_xyz(wavelength: f32) -> [f32; 3] {
+  let lobe = |mean: f32, left: f32, right: f32| {
+    let t = (wavelength - mean) / if wavelength < mean { left } else { right };
+    f32::exp(-0.5 * t * t)
+  };
+
+  [
+    1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7) - 0.065 * lobe(501.1, 20.4, 26.2),
+    0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+    1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+  ]
+}
+
+// linear sRGB of the unit spectral value at the wavelength, scaled for the uniform wavelength sampling,
+// the colors out of the gamut keep the negative components, they cancel out in the pixel mean
+pub fn wavelength_to_color(wavelength: f32) -> Color {
+  let [x, y, z] = wavelength_to_xyz(wavelength);
+
+  Color::new(
+    (3.2406 * x - 1.5372 * y - 0.4986 * z) * WHITE_SCALE[0],
+    (-0.9689 * x + 1.8758 * y + 0.0415 * z) * WHITE_SCALE[1],
+    (0.0557 * x - 0.2040 * y + 1.0570 * z) * WHITE_SCALE[2],
+  )
+}
+
+// smooth spectrum of the RGB color, the blue, green and red bands overlap smoothly and sum up to one,
+// so the gray colors get the flat spectra
+pub fn color_to_spectrum(color: &Color, wavelength: f32) -> f32 {
+  let red = smoothstep(570.0, 610.0, wavelength);
+  let blue = 1.0 - smoothstep(470.0, 510.0, wavelength);
+  let green = 1.0 - red - blue;
+
+  color.r * red + color.g * green + color.b * blue
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+  let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+  t * t * (3.0 - 2.0 * t)
+}
@@ -1,6 +1,8 @@
 use anyhow::Result;
 
-use super::{Material, Trace};
+use std::f32::consts::PI;
+
+use super::{MaterialId, TextureId, Trace};
 use super::math::constants::{DELTA, VERY_SMALL_NUMBER};
 use super::math::Vector3;
 
@@ -9,18 +11,25 @@ pub struct Sphere {
   pub center: Vector3,
   pub radius: f32,
   pub sq_radius: f32,
-  pub material: Material,
+  pub material: MaterialId,
+  pub texture: Option<TextureId>,
 }
 
 impl Sphere {
-  pub fn new(center: Vector3, radius: f32, material: Material) -> Sphere {
+  pub fn new(center: Vector3, radius: f32, material: MaterialId) -> Sphere {
     Sphere {
       center,
       radius,
       sq_radius: radius * radius,
       material,
+      texture: None,
     }
   }
+
+  // the texture is wrapped around the sphere by the longitude and latitude
+  pub fn set_texture(&mut self, texture: TextureId) {
+    self.texture = Some(texture);
+  }
 }
 
 impl Trace for Sphere {
@@ -32,7 +41,6 @@ impl Trace for Sphere {
     out_norm: Option<&mut Vector3>,
     out_reflected_ray: Option<&mut Vector3>,
     out_distance: Option<&mut f32>,
-    out_drop_material: Option<&mut Material>,
   ) -> Result<bool>
   {
     let vco = origin - &self.center;
@@ -84,10 +92,43 @@ impl Trace for Sphere {
       *out_reflected_ray = full_ray.reflected(&norm);
     }
 
-    if let Some(out_drop_material) = out_drop_material {
-      *out_drop_material = self.material.clone();
+    Ok(true)
+  }
+
+  fn trace_exit(&self, origin: &Vector3, direction: &Vector3) -> Option<(f32, Vector3)> {
+    let vco = origin - &self.center;
+    let b = direction * &vco;
+    let d = b * b - vco.sq_length() + self.sq_radius;
+
+    if d < 0.0 {
+      return None;
     }
 
-    Ok(true)
+    let distance = -b + d.sqrt();
+
+    if distance <= 0.0 {
+      return None;
+    }
+
+    let norm = (origin + direction * distance - &self.center).normalized();
+    Some((distance, norm))
+  }
+
+  fn get_type_name(&self) -> &'static str {
+    "sphere"
+  }
+
+  fn get_material_id(&self) -> MaterialId {
+    self.material
+  }
+
+  fn get_texture_id(&self) -> Option<TextureId> {
+    self.texture
+  }
+
+  // longitude and latitude of the point
+  fn get_uv(&self, drop: &Vector3) -> (f32, f32) {
+    let norm = (drop - &self.center).normalized();
+    (0.5 + f32::atan2(norm.z, norm.x) / (2.0 * PI), 0.5 + f32::asin(norm.y.clamp(-1.0, 1.0)) / PI)
   }
 }
\ No newline at end of file
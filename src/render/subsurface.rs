@@ -0,0 +1,99 @@
+use std::f32::consts::PI;
+
+use super::{Color, Material, Trace};
+use super::math::constants::DELTA;
+use super::math::{Rnd, Vector3};
+
+// the walk that doesn't leave the object after that many scatterings is absorbed
+const MAX_WALK_STEPS: u32 = 256;
+// the shortest mean free path, keeps the walk finite for the zero scattering distance
+const MIN_MEAN_FREE_PATH: f32 = 1.0e-4;
+
+// point where the light leaves the object, the weight is the share of the entered light per channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exit {
+  pub point: Vector3,
+  pub norm: Vector3,
+  pub weight: Color,
+}
+
+// single scattering albedo giving the multiple scattering albedo of the semi-infinite medium,
+// the fit of M. Chiang, P. Kutz and B. Burley, "Practical and Controllable Subsurface Scattering for Production Path
+// Tracing" (2016)
+pub fn single_scattering_albedo(albedo: f32) -> f32 {
+  let albedo = albedo.clamp(0.0, 1.0);
+  let a = 4.09712 + 4.20863 * albedo - f32::sqrt(9.59217 + 41.6808 * albedo + 17.7126 * albedo * albedo);
+
+  (1.0 - a * a).clamp(0.0, 1.0)
+}
+
+// random walk inside the object from the surface point with the normalized outward normal, the color is the share
+// of the light coming back out, the channels are walked together, every step picks the channel of its distance and
+// weights the others by the ratio of the probabilities, none if the object has no inside, the absorbed light
+// leaves nothing
+pub fn random_walk(object: &dyn Trace, drop: &Vector3, norm: &Vector3, color: &Color, material: &Material,
+                   rnd: &Rnd) -> Option<Exit>
+{
+  let mean_free_paths = [material.scattering_color.r, material.scattering_color.g, material.scattering_color.b]
+    .map(|scale| f32::max(scale * material.scattering_distance, MIN_MEAN_FREE_PATH));
+  let extinction = mean_free_paths.map(|path| 1.0 / path);
+  let albedo = [color.r, color.g, color.b].map(single_scattering_albedo);
+
+  let mut point = drop - norm * DELTA;
+  let mut direction = cosine_direction(&-norm, rnd);
+  let mut weight = [1.0; 3];
+  let absorbed = Exit { point: drop.clone(), norm: norm.clone(), weight: Color::default() };
+
+  // the open objects like the triangles can't be entered
+  object.trace_exit(&point, &direction)?;
+
+  for _ in 0..MAX_WALK_STEPS {
+    let channel = usize::min((rnd.next_f32() * 3.0) as usize, 2);
+    let distance = -f32::ln(1.0 - rnd.next_f32()) / extinction[channel];
+    let (exit_distance, exit_norm) = match object.trace_exit(&point, &direction) {
+      Some(exit) => exit,
+      None => return Some(absorbed),
+    };
+
+    if exit_distance <= distance {
+      // passing through to the surface, the probability is the mean transmittance of the channels
+      let transmittance = extinction.map(|sigma| f32::exp(-sigma * exit_distance));
+      let pdf = transmittance.iter().sum::<f32>() / 3.0;
+
+      for index in 0..3 {
+        weight[index] *= transmittance[index] / pdf;
+      }
+
+      let [r, g, b] = weight;
+      return Some(Exit { point: &point + &direction * exit_distance, norm: exit_norm, weight: Color::new(r, g, b) });
+    }
+
+    // scattering inside, the probability is the mean density of the collision at the distance
+    let collision = extinction.map(|sigma| sigma * f32::exp(-sigma * distance));
+    let pdf = collision.iter().sum::<f32>() / 3.0;
+
+    for index in 0..3 {
+      weight[index] *= albedo[index] * collision[index] / pdf;
+    }
+
+    point += &direction * distance;
+    direction = uniform_direction(rnd);
+  }
+
+  Some(absorbed)
+}
+
+fn uniform_direction(rnd: &Rnd) -> Vector3 {
+  let z = 1.0 - 2.0 * rnd.next_f32();
+  let r = f32::sqrt(f32::max(0.0, 1.0 - z * z));
+  let phi = 2.0 * PI * rnd.next_f32();
+
+  Vector3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// cosine weighted direction around the normalized axis, the light entering the diffuse surface
+fn cosine_direction(axis: &Vector3, rnd: &Rnd) -> Vector3 {
+  let direction = axis + uniform_direction(rnd);
+
+  if direction.length() > DELTA { direction.normalized() } else { axis.clone() }
+}
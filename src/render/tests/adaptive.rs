@@ -0,0 +1,53 @@
+use crate::render::adaptive::heatmap_color;
+use crate::render::pixel::Pixel;
+
+use super::{AdaptiveSampling, Color, FirstHit};
+
+#[test]
+fn pixel_statistics() {
+  let mut pixel = Pixel::default();
+  assert_eq!(pixel.get_error(), f32::INFINITY);
+
+  for &value in [0.2, 0.4, 0.6, 0.8].iter() {
+    pixel.add_sample(&Color::new(value, value, value), &FirstHit::default());
+    pixel.add_weighted(&Color::new(value, value, value), 0.5);
+  }
+
+  assert_eq!(pixel.samples, 4);
+  assert!((pixel.mean - 0.5).abs() < 1e-6);
+  assert!((pixel.get_variance() - 0.2 / 3.0).abs() < 1e-6);
+  assert!((pixel.get_color().g - 0.5).abs() < 1e-6);
+  // standard error of the mean is sqrt(variance / n)
+  assert!((pixel.get_error() - f32::sqrt(0.2 / 3.0 / 4.0) / 0.5).abs() < 1e-5);
+
+  pixel.reset();
+  assert_eq!(pixel, Pixel::default());
+}
+
+#[test]
+fn convergence() {
+  let adaptive = AdaptiveSampling { threshold: 0.01, min_samples: 4, max_samples: 16 };
+  let mut flat = Pixel::default();
+  let mut noisy = Pixel::default();
+
+  for i in 0..4 {
+    flat.add_sample(&Color::new(0.5, 0.5, 0.5), &FirstHit::default());
+    noisy.add_sample(&Color::new(i as f32 % 2.0, 0.0, 0.0), &FirstHit::default());
+  }
+
+  assert!(adaptive.is_converged(&flat));
+  assert!(!adaptive.is_converged(&noisy));
+
+  for i in 4..16 {
+    noisy.add_sample(&Color::new(i as f32 % 2.0, 0.0, 0.0), &FirstHit::default());
+  }
+
+  assert!(adaptive.is_converged(&noisy));
+}
+
+#[test]
+fn heatmap() {
+  assert_eq!(heatmap_color(0.0), Color::new(0.0, 0.0, 1.0));
+  assert_eq!(heatmap_color(1.0), Color::new(1.0, 0.0, 0.0));
+  assert_eq!(heatmap_color(2.0), Color::new(1.0, 0.0, 0.0));
+}
@@ -0,0 +1,51 @@
+use crate::render::aov::{depth_color, id_color, normal_color, position_color, AovPixel};
+
+use super::{AovKind, Color, FirstHit, SKY_DEPTH};
+use super::math::Vector3;
+
+#[test]
+fn names() {
+  assert_eq!(AovKind::from_name("object_id"), Some(AovKind::ObjectId));
+  assert_eq!(AovKind::from_name(AovKind::Indirect.name()), Some(AovKind::Indirect));
+  assert_eq!(AovKind::from_name("speed"), None);
+}
+
+#[test]
+fn pixel_accumulation() {
+  let mut aov = AovPixel::default();
+  let first_hit = FirstHit {
+    position: Vector3::new(1.0, 2.0, 3.0),
+    object_id: 3,
+    material_id: 2,
+    direct: Color::new(0.5, 0.5, 0.5),
+    shadow: 1.0,
+    ..FirstHit::default()
+  };
+
+  aov.add_sample(&Color::new(0.75, 0.5, 0.25), &first_hit);
+  aov.add_sample(&Color::new(0.75, 0.5, 0.25), &FirstHit { object_id: 4, shadow: 0.0, ..first_hit.clone() });
+
+  assert_eq!(aov.samples, 2);
+  assert_eq!((aov.object_id, aov.material_id), (3, 2));
+  assert_eq!(aov.get_position(), Vector3::new(1.0, 2.0, 3.0));
+  assert_eq!(aov.get_direct(), Color::new(0.5, 0.5, 0.5));
+  // indirect light can't be negative
+  assert_eq!(aov.get_indirect(), Color::new(0.25, 0.0, 0.0));
+  assert_eq!(aov.get_shadow(), 0.5);
+}
+
+#[test]
+fn visualization() {
+  assert_eq!(depth_color(SKY_DEPTH, 10.0), Color::new(1.0, 1.0, 1.0));
+  assert_eq!(depth_color(0.0, 10.0), Color::default());
+  assert_eq!(depth_color(5.0, 10.0), Color::new(0.5, 0.5, 0.5));
+  assert_eq!(normal_color(&Vector3::new(-1.0, 0.0, 1.0)), Color::new(0.0, 0.5, 1.0));
+
+  let min = Vector3::new(0.0, 0.0, 0.0);
+  let max = Vector3::new(2.0, 4.0, 8.0);
+  assert_eq!(position_color(&Vector3::new(1.0, 1.0, 8.0), &min, &max), Color::new(0.5, 0.25, 1.0));
+
+  assert_eq!(id_color(0), Color::default());
+  assert_eq!(id_color(7), id_color(7));
+  assert_ne!(id_color(7), id_color(8));
+}
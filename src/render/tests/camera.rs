@@ -0,0 +1,60 @@
+use crate::render::camera::{AnalogControl, DEFAULT_MAX_SHIFT_SPEED, DEFAULT_MAX_TURN_SPEED, SHIFT_FORWARD_MASK, TURN_LEFT_MASK};
+
+use std::f32::consts::{FRAC_PI_2, PI};
+use super::Camera;
+use super::math::Vector3;
+
+fn camera() -> Camera {
+  Camera::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), 1.0)
+}
+
+#[test]
+fn turn_limits() {
+  // the pitch stops short of the poles
+  let mut camera = camera();
+  camera.turn(0.0, 10.0);
+  assert_eq!(camera.pitch, 0.95 * FRAC_PI_2);
+  camera.turn(0.0, -20.0);
+  assert_eq!(camera.pitch, -0.95 * FRAC_PI_2);
+
+  // the yaw wraps around the full turn
+  let yaw = camera.yaw;
+  camera.turn(7.0, 0.0);
+  assert!((camera.yaw - (yaw + 7.0 - 2.0 * PI)).abs() < 1e-5, "{}", camera.yaw);
+  camera.turn(-4.0, 0.0);
+  camera.turn(-4.0, 0.0);
+  assert!((camera.yaw - (yaw - 1.0)).abs() < 1e-5, "{}", camera.yaw);
+}
+
+#[test]
+fn zoom_limits() {
+  let mut camera = camera();
+  camera.zoom(0.5);
+  assert_eq!(camera.fov, 0.5);
+  camera.zoom(100.0);
+  assert_eq!(camera.fov, 2.5);
+  camera.zoom(0.001);
+  assert_eq!(camera.fov, 0.1);
+}
+
+#[test]
+fn analog_control() {
+  // the digital control accelerates the camera
+  let mut camera = camera();
+  camera.proceed_control(TURN_LEFT_MASK, &AnalogControl::default(), 0.05);
+  assert!(camera.turn_rl_speed < 0.0 && camera.turn_rl_speed > -DEFAULT_MAX_TURN_SPEED, "{}", camera.turn_rl_speed);
+  assert!(camera.is_in_motion());
+
+  // the analog input sets the speed of its axis directly and overrides the digital control
+  let analog = AnalogControl { turn_rl: 0.5, shift_fb: 3.0, ..AnalogControl::default() };
+  camera.proceed_control(TURN_LEFT_MASK | SHIFT_FORWARD_MASK, &analog, 0.05);
+  assert_eq!(camera.turn_rl_speed, 0.5 * DEFAULT_MAX_TURN_SPEED);
+  assert_eq!(camera.shift_fb_speed, DEFAULT_MAX_SHIFT_SPEED);
+
+  // without any input the camera stops
+  for _ in 0..100 {
+    camera.proceed_control(0, &AnalogControl::default(), 0.1);
+  }
+
+  assert!(!camera.is_in_motion());
+}
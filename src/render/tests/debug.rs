@@ -0,0 +1,38 @@
+use crate::render::debug::{debug_depth_color, material_kind_color, DEBUG_MODES};
+
+use super::{Color, DebugMode, MaterialKind};
+
+#[test]
+fn names() {
+  for mode in &DEBUG_MODES {
+    assert_eq!(DebugMode::from_name(mode.name()), Some(*mode));
+  }
+
+  assert_eq!(DebugMode::from_name("wireframe"), None);
+}
+
+#[test]
+fn cycle() {
+  let mut mode = None;
+  let mut visited = Vec::new();
+
+  loop {
+    mode = DebugMode::next(mode);
+
+    match mode {
+      Some(mode) => visited.push(mode),
+      None => break,
+    }
+  }
+
+  assert_eq!(visited, DEBUG_MODES.to_vec());
+}
+
+#[test]
+fn colors() {
+  assert_eq!(debug_depth_color(0.0), Color::default());
+  assert!((debug_depth_color(10.0).r - 0.5).abs() < 1e-5);
+  assert!(debug_depth_color(100.0).r > debug_depth_color(20.0).r);
+
+  assert_ne!(material_kind_color(&MaterialKind::Metal), material_kind_color(&MaterialKind::Dielectric));
+}
@@ -0,0 +1,61 @@
+use super::{Color, Denoiser, Guide};
+use super::math::{Rnd, Vector3};
+
+const SIZE: u32 = 16;
+
+// left half is a dark object, right half is a bright one, both with the noise
+fn noisy_image(rnd: &Rnd) -> Vec<Color> {
+  (0..SIZE * SIZE)
+    .map(|i| {
+      let base = if i % SIZE < SIZE / 2 { 0.2 } else { 0.8 };
+      let noise = rnd.next_f32() * 0.2 - 0.1;
+      Color::new(base + noise, base + noise, base + noise)
+    })
+    .collect()
+}
+
+fn object_guide() -> (Vec<Color>, Vec<Vector3>, Vec<f32>) {
+  let albedo = (0..SIZE * SIZE)
+    .map(|i| if i % SIZE < SIZE / 2 { Color::new(0.2, 0.2, 0.2) } else { Color::new(0.8, 0.8, 0.8) })
+    .collect();
+  let normal = (0..SIZE * SIZE).map(|_| Vector3::new(0.0, 0.0, 1.0)).collect();
+  let depth = (0..SIZE * SIZE).map(|i| if i % SIZE < SIZE / 2 { 2.0 } else { 5.0 }).collect();
+
+  (albedo, normal, depth)
+}
+
+fn error(image: &[Color]) -> f32 {
+  image.iter().enumerate()
+    .map(|(i, c)| (c.r - if i as u32 % SIZE < SIZE / 2 { 0.2 } else { 0.8 }).abs())
+    .sum::<f32>() / image.len() as f32
+}
+
+#[test]
+fn removes_noise_and_keeps_edges() {
+  let image = noisy_image(&Rnd::new(1));
+  let (albedo, normal, depth) = object_guide();
+  let guide = Guide { albedo: &albedo, normal: &normal, depth: &depth };
+  let denoised = Denoiser::default().denoise(SIZE, SIZE, &image, &guide);
+
+  assert_eq!(denoised.len(), image.len());
+  assert!(error(&denoised) < error(&image) / 2.0, "{} {}", error(&denoised), error(&image));
+
+  // pixels next to the edge are not blurred with the other object
+  for y in 0..SIZE {
+    let left = &denoised[(SIZE / 2 - 1 + y * SIZE) as usize];
+    let right = &denoised[(SIZE / 2 + y * SIZE) as usize];
+    assert!(left.r < 0.35 && right.r > 0.65, "{} {}", left, right);
+  }
+}
+
+#[test]
+fn flat_image_unchanged() {
+  let image = vec![Color::new(0.5, 0.25, 1.0); (SIZE * SIZE) as usize];
+  let (albedo, normal, depth) = object_guide();
+  let guide = Guide { albedo: &albedo, normal: &normal, depth: &depth };
+  let denoised = Denoiser::default().denoise(SIZE, SIZE, &image, &guide);
+
+  for color in denoised {
+    assert!((color.r - 0.5).abs() < 1e-5 && (color.g - 0.25).abs() < 1e-5 && (color.b - 1.0).abs() < 1e-5);
+  }
+}
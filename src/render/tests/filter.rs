@@ -0,0 +1,29 @@
+use super::{Filter, FilterKind, FILTER_KINDS};
+
+#[test]
+fn names() {
+  for &kind in FILTER_KINDS.iter() {
+    assert_eq!(FilterKind::from_name(kind.name()), Some(kind));
+  }
+
+  assert_eq!(FilterKind::from_name("sinc"), None);
+}
+
+#[test]
+fn weights() {
+  for &kind in FILTER_KINDS.iter() {
+    let filter = Filter::new(kind, 2.0);
+    assert!(filter.weight(0.0, 0.0) > 0.0, "{:?}", kind);
+    assert_eq!(filter.weight(2.1, 0.0), 0.0, "{:?}", kind);
+    assert_eq!(filter.weight(0.5, -1.0), filter.weight(-0.5, 1.0), "{:?}", kind);
+    // the weight falls off from the center
+    assert!(filter.weight(0.0, 0.0) >= filter.weight(0.5, 0.0), "{:?}", kind);
+  }
+
+  assert_eq!(Filter::default().weight(0.3, 0.5), 1.0);
+  assert_eq!(Filter::new(FilterKind::Tent, 1.0).weight(0.5, 0.0), 0.5);
+  assert!(Filter::new(FilterKind::Gaussian, 2.0).weight(2.0, 0.0).abs() < 1e-6);
+  // Mitchell and Lanczos have negative lobes
+  assert!(Filter::new(FilterKind::Mitchell, 2.0).weight(1.5, 0.0) < 0.0);
+  assert!(Filter::new(FilterKind::Lanczos, 3.0).weight(1.5, 0.0) < 0.0);
+}
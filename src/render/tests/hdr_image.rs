@@ -0,0 +1,64 @@
+use std::io::Cursor;
+
+use super::{Color, HdrImage, Texture};
+
+fn header(width: u32, height: u32) -> Vec<u8> {
+  format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\nEXPOSURE=1.0\n\n-Y {} +X {}\n", height, width).into_bytes()
+}
+
+#[test]
+fn load_flat() {
+  let mut data = header(2, 2);
+  // top row: 1.0 red and 4.0 white, bottom row: black and 0.5 green
+  data.extend_from_slice(&[128, 0, 0, 129, 128, 128, 128, 131, 0, 0, 0, 0, 0, 128, 0, 128]);
+
+  let image = HdrImage::from_hdr(Cursor::new(data)).unwrap();
+  assert_eq!((image.width, image.height), (2, 2));
+  assert_eq!(image.get_pixel_color(0, 1).unwrap(), Color::new(1.0, 0.0, 0.0));
+  assert_eq!(image.get_pixel_color(1, 1).unwrap(), Color::new(4.0, 4.0, 4.0));
+  assert_eq!(image.get_pixel_color(0, 0).unwrap(), Color::default());
+  assert_eq!(image.get_pixel_color(1, 0).unwrap(), Color::new(0.0, 0.5, 0.0));
+}
+
+#[test]
+fn load_run_length_encoded() {
+  let width = 10;
+  let mut data = header(width, 1);
+  data.extend_from_slice(&[2, 2, 0, width as u8]);
+  // red: a run of 10 values, green: 10 literal values, blue: zeros, exponent: a run
+  data.extend_from_slice(&[128 + 10, 128]);
+  data.push(10);
+  data.extend((0..10).map(|x| x * 10));
+  data.extend_from_slice(&[128 + 10, 0]);
+  data.extend_from_slice(&[128 + 10, 129]);
+
+  let image = HdrImage::from_hdr(Cursor::new(data)).unwrap();
+  assert_eq!(image.pixels.len(), 10);
+  assert_eq!(image.get_pixel_color(3, 0).unwrap(), Color::new(1.0, 30.0 / 128.0, 0.0));
+}
+
+#[test]
+fn load_errors() {
+  assert!(HdrImage::from_hdr(Cursor::new(b"P6\n".to_vec())).is_err());
+  assert!(HdrImage::from_hdr(Cursor::new(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n".to_vec())).is_err());
+
+  // the pixels are missing
+  assert!(HdrImage::from_hdr(Cursor::new(header(4, 4))).is_err());
+
+  // the empty or the huge image is rejected before the pixels are read
+  let pixel = [128u8, 128, 128, 128];
+  assert!(HdrImage::from_hdr(Cursor::new([header(0, 1), pixel.to_vec()].concat())).is_err());
+  assert!(HdrImage::from_hdr(Cursor::new([header(1, 0), pixel.to_vec()].concat())).is_err());
+  assert!(HdrImage::from_hdr(Cursor::new([header(u32::MAX, u32::MAX), pixel.to_vec()].concat())).is_err());
+  assert!(HdrImage::from_hdr(Cursor::new([header(32768, 1), pixel.to_vec()].concat())).is_err());
+  assert!(HdrImage::from_hdr(Cursor::new([header(16384, 16384), pixel.to_vec()].concat())).is_err());
+}
+
+#[test]
+fn from_texture() {
+  let texture = Texture { width: 2, height: 1, color_buffer: vec![255, 0, 0, 0, 0, 255] };
+  let image = HdrImage::from(&texture);
+
+  assert_eq!(image.get_texel_color(0.25, 0.5).unwrap(), texture.get_texel_color(0.25, 0.5).unwrap());
+  assert_eq!(image.get_pixel_color(1, 0).unwrap(), Color::new(0.0, 0.0, 1.0));
+}
@@ -0,0 +1,40 @@
+use std::rc::Rc;
+
+use super::{Color, Library, Material, MaterialKind, Texture, TextureSource};
+use super::math::Vector3;
+
+#[test]
+fn shared_materials() {
+  let mut library = Library::default();
+  let metal = Material::new(MaterialKind::Metal, Color::new(1.0, 1.0, 1.0), 1.0, 0.0);
+  let plastic = Material::new(MaterialKind::Dielectric, Color::new(1.0, 0.0, 0.0), 0.5, 0.0);
+
+  assert_eq!(library.add_material(&metal), 0);
+  assert_eq!(library.add_material(&plastic), 1);
+  assert_eq!(library.add_material(&metal.clone()), 0);
+  assert_eq!(library.add_named_material("red", &plastic), 1);
+  assert_eq!(library.get_material_count(), 2);
+
+  assert_eq!(library.find_material("red"), Some(1));
+  assert_eq!(library.find_material("blue"), None);
+  assert_eq!(library.get_material(1), &plastic);
+  assert_eq!(library.get_material_name(1), Some("red"));
+  assert_eq!(library.get_material_name(0), None);
+}
+
+#[test]
+fn shared_textures() {
+  let mut library = Library::default();
+  let image = Texture { width: 1, height: 1, color_buffer: vec![255, 0, 0] };
+  let texture: Rc<dyn TextureSource> = Rc::new(image.clone());
+  // equal texture loaded separately is a different texture
+  let copy: Rc<dyn TextureSource> = Rc::new(image);
+
+  assert_eq!(library.add_named_texture("floor.tga", &texture), 0);
+  assert_eq!(library.add_texture(&Rc::clone(&texture)), 0);
+  assert_eq!(library.add_texture(&copy), 1);
+  assert_eq!(library.get_texture_count(), 2);
+  assert_eq!(library.find_texture("floor.tga"), Some(0));
+  assert_eq!(library.get_texture(0).sample((0.5, 0.5), &Vector3::default()).unwrap(), Color::new(1.0, 0.0, 0.0));
+  assert_eq!(Rc::strong_count(&texture), 2);
+}
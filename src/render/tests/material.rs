@@ -0,0 +1,53 @@
+use crate::render::material::fresnel_dielectric;
+
+use super::{Coat, Color, Ior};
+use super::math::ApproxEq;
+
+#[test]
+fn fresnel() {
+  assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-4);
+  assert!((fresnel_dielectric(0.0, 1.5) - 1.0).abs() < 1e-4);
+  assert!(fresnel_dielectric(0.5, 1.5) > fresnel_dielectric(0.9, 1.5));
+  assert_eq!(fresnel_dielectric(0.7, 1.0), 0.0);
+}
+
+#[test]
+fn coat_reflectance() {
+  let coat = Coat::new(1.5, 0.0);
+  assert!(coat.get_reflectance(1.0).approx_eq(&Color::new(0.04, 0.04, 0.04), 1e-4));
+
+  // the film of no thickness changes nothing, the film of the coat IOR neither
+  let bare = coat.get_reflectance(0.6);
+  assert!(coat.clone().with_thin_film(0.0, 1.33).get_reflectance(0.6).approx_eq(&bare, 1e-4));
+  assert!(coat.clone().with_thin_film(500.0, 1.5).get_reflectance(0.6).approx_eq(&bare, 1e-4));
+
+  // the soap film reflects the colors differently, and they change with the angle
+  let soap = Coat::new(1.0, 0.0).with_thin_film(380.0, 1.33);
+  let straight = soap.get_reflectance(1.0);
+  let oblique = soap.get_reflectance(0.5);
+  assert!((straight.r - straight.b).abs() > 0.02, "{}", straight);
+  assert!(!straight.approx_eq(&oblique, 0.02), "{} {}", straight, oblique);
+  assert!([straight.r, straight.g, straight.b].iter().all(|value| (0.0..=1.0).contains(value)));
+}
+
+#[test]
+fn dispersive_ior() {
+  // BK7 at the d line, the blue is bent more than the red
+  assert!((Ior::GLASS.get_mean() - 1.5168).abs() < 1e-3);
+  assert!((Ior::DIAMOND.get_mean() - 2.4175).abs() < 1e-3);
+  assert!(Ior::GLASS.get(450.0) > Ior::GLASS.get(650.0));
+  assert_eq!(Ior::Constant(1.33).get(450.0), 1.33);
+
+  let cauchy = Ior::parse("cauchy 1.5046, 0.0042").unwrap();
+  assert!((cauchy.get(500.0) - (1.5046 + 0.0042 / 0.25)).abs() < 1e-5);
+  assert_eq!(Ior::parse(&Ior::GLASS.to_string()), Some(Ior::GLASS));
+  assert_eq!(Ior::parse(" 2.4 "), Some(Ior::Constant(2.4)));
+  assert_eq!(Ior::parse("cauchy 1.5"), None);
+  assert_eq!(Ior::parse("abbe 1.5, 60"), None);
+
+  // the pole near 550 nm and the blue above 4 are not seen at the d line
+  assert!(Ior::GLASS.is_valid() && Ior::DIAMOND.is_valid() && cauchy.is_valid());
+  assert!(!Ior::parse("sellmeier 1, 0, 0, 0.3, 0, 0").unwrap().is_valid());
+  assert!(!Ior::parse("cauchy 3.5, 0.1").unwrap().is_valid());
+  assert!(!Ior::Constant(0.5).is_valid());
+}
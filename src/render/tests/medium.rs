@@ -0,0 +1,78 @@
+use crate::render::medium::Media;
+
+use super::{Color, Fog, Volume};
+use super::math::Vector3;
+
+fn fog(density: f32, height_falloff: f32) -> Fog {
+  Fog { color: Color::new(0.5, 0.5, 0.5), density, height_falloff, base_height: 0.0 }
+}
+
+#[test]
+fn homogeneous_fog() {
+  let media = Media { fog: Some(fog(0.5, 0.0)), volumes: Vec::new() };
+  let direction = Vector3::new(0.0, 1.0, 0.0);
+
+  assert!((media.get_transmittance(&Vector3::default(), &direction, 2.0) - f32::exp(-1.0)).abs() < 1e-5);
+  assert_eq!(media.get_transmittance(&Vector3::default(), &direction, 1.0e6), 0.0);
+  assert_eq!(media.get_albedo(&Vector3::new(3.0, 4.0, 5.0)), Color::new(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn height_fog() {
+  let media = Media { fog: Some(fog(1.0, 2.0)), volumes: Vec::new() };
+  let origin = Vector3::new(0.0, -1.0, 0.0);
+  let direction = Vector3::new(0.6, 0.8, 0.0);
+
+  // the analytic optical depth matches the sum of the densities along the ray
+  let steps = 10000;
+  let step = 3.0 / steps as f32;
+  let sum = (0..steps)
+    .map(|index| media.fog.as_ref().unwrap().get_density(&(&origin + &direction * ((index as f32 + 0.5) * step))) * step)
+    .sum::<f32>();
+
+  let depth = media.get_optical_depth(&origin, &direction, 3.0);
+  assert!((depth - sum).abs() < 1e-3 * sum, "{} {}", depth, sum);
+
+  // the fog thins out upwards, so the sky above is still seen, and it's dense below the base height
+  assert!(media.get_transmittance(&Vector3::default(), &Vector3::new(0.0, 1.0, 0.0), 1.0e6) > 0.5);
+  assert_eq!(media.get_transmittance(&Vector3::default(), &Vector3::new(0.0, -1.0, 0.0), 1.0e6), 0.0);
+  assert!(media.get_transmittance(&Vector3::new(0.0, 100.0, 0.0), &Vector3::new(0.0, 1.0, 0.0), 1.0e6) > 0.999);
+}
+
+#[test]
+fn volume() {
+  let smoke = Volume { center: Vector3::new(0.0, 0.0, 5.0), radius: 1.0, color: Color::new(1.0, 0.0, 0.0), density: 2.0 };
+  let media = Media { fog: None, volumes: vec![smoke] };
+  let origin = Vector3::default();
+  let forward = Vector3::new(0.0, 0.0, 1.0);
+
+  // the ray crosses the whole diameter, the short segment stops in the middle, the sideways ray misses
+  assert!((media.get_optical_depth(&origin, &forward, 10.0) - 4.0).abs() < 1e-4);
+  assert!((media.get_optical_depth(&origin, &forward, 5.0) - 2.0).abs() < 1e-4);
+  assert_eq!(media.get_optical_depth(&origin, &Vector3::new(1.0, 0.0, 0.0), 10.0), 0.0);
+  assert_eq!(media.get_optical_depth(&Vector3::new(0.0, 0.0, 10.0), &forward, 10.0), 0.0);
+
+  assert_eq!(media.get_albedo(&Vector3::new(0.0, 0.0, 5.0)), Color::new(1.0, 0.0, 0.0));
+  assert_eq!(media.get_albedo(&origin), Color::default());
+}
+
+#[test]
+fn sample_distance() {
+  let smoke = Volume { center: Vector3::new(0.0, 0.0, 5.0), radius: 1.0, color: Color::new(1.0, 1.0, 1.0), density: 2.0 };
+  let media = Media { fog: None, volumes: vec![smoke] };
+  let origin = Vector3::default();
+  let forward = Vector3::new(0.0, 0.0, 1.0);
+
+  // the scattering points are inside the volume only, the first samples are closer to the entry
+  for sample in [0.0, 0.25, 0.5, 0.75, 0.999] {
+    let (distance, scattered) = media.sample_distance(&origin, &forward, 10.0, sample).unwrap();
+    assert!((4.0 - 1e-3..=6.0 + 1e-3).contains(&distance), "{}", distance);
+    assert!((scattered - (1.0 - f32::exp(-4.0))).abs() < 1e-5);
+  }
+
+  let near = media.sample_distance(&origin, &forward, 10.0, 0.5).unwrap().0;
+  assert!(near < 5.0, "{}", near);
+
+  assert!(media.sample_distance(&origin, &Vector3::new(1.0, 0.0, 0.0), 10.0, 0.5).is_none());
+  assert!(Media::default().sample_distance(&origin, &forward, 10.0, 0.5).is_none());
+}
@@ -2,10 +2,29 @@ use crate::math;
 
 use super::*;
 
+mod camera;
 mod color;
+mod material;
 mod sphere;
 mod texture;
+mod hdr_image;
 mod skybox;
+mod physical_sky;
+mod medium;
+mod subsurface;
+mod spectrum;
+mod refraction;
 mod triangle;
 mod scene;
+mod render;
 
+mod sampler;
+mod adaptive;
+mod filter;
+mod denoiser;
+mod aov;
+mod debug;
+mod library;
+mod scene_builder;
+mod scene_desc;
+mod procedural;
@@ -0,0 +1,57 @@
+use super::{Color, PhysicalSky, SkyMapping, Skybox};
+use super::math::Vector3;
+
+fn sky(elevation: f32) -> PhysicalSky {
+  PhysicalSky::new(&PhysicalSky::sun_direction(elevation.to_radians(), 0.0), 3.0, 0.3)
+}
+
+#[test]
+fn sun_direction() {
+  let direction = PhysicalSky::sun_direction(0.0, 90f32.to_radians());
+  assert!((&direction - Vector3::new(1.0, 0.0, 0.0)).length() < 1e-5, "{}", direction);
+  assert!((PhysicalSky::sun_direction(90f32.to_radians(), 0.0).y - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn sky_colors() {
+  let up = Vector3::new(0.0, 1.0, 0.0);
+  let noon = sky(80.0);
+  let zenith = noon.get_sky_color(&up);
+
+  // the clear sky is blue, and the sky is brighter around the sun than opposite to it
+  assert!(zenith.b > zenith.r && zenith.b > zenith.g, "{}", zenith);
+  assert!(zenith.luminance() > sky(10.0).get_sky_color(&up).luminance());
+
+  let evening = sky(10.0);
+  let towards_sun = evening.get_sky_color(&Vector3::new(0.0, 0.1, 1.0));
+  let away_from_sun = evening.get_sky_color(&Vector3::new(0.0, 0.1, -1.0));
+  assert!(towards_sun.luminance() > away_from_sun.luminance());
+
+  // the ground reflects the horizon color
+  let ground = noon.get_sky_color(&Vector3::new(0.0, -1.0, 0.0));
+  assert!(ground.luminance() > 0.0 && ground.luminance() < zenith.luminance());
+}
+
+#[test]
+fn sun_colors() {
+  let noon = sky(80.0).get_sun_color();
+  let evening = sky(5.0).get_sun_color();
+
+  // the low sun is dimmer and redder
+  assert!(noon.r <= 1.0 && noon.luminance() > evening.luminance());
+  assert!(evening.r / evening.b > noon.r / noon.b);
+  assert_eq!(sky(-10.0).get_sun_color(), Color::default());
+}
+
+#[test]
+fn physical_skybox() {
+  let mut skybox = Skybox::physical(sky(45.0));
+  skybox.set_rotation(90f32.to_radians());
+  assert_eq!(skybox.get_mapping(), SkyMapping::Physical);
+
+  // the sun turns with the sky
+  let (direction, color) = skybox.get_sun().unwrap();
+  assert!((direction.x - 45f32.to_radians().cos()).abs() < 1e-5, "{}", direction);
+  assert!(color.luminance() > 0.0);
+  assert!(Skybox::from_color(&Color::default()).get_sun().is_none());
+}
@@ -0,0 +1,70 @@
+use crate::render::procedural::{fbm, perlin, turbulence};
+
+use super::{Checker, Color, Gradient, ProceduralKind, ProceduralSpace, TextureSource, UvGrid, PROCEDURAL_KINDS};
+use super::math::Vector3;
+
+fn black_and_white() -> [Color; 2] {
+  [Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)]
+}
+
+#[test]
+fn checker() {
+  let uv_checker = Checker { space: ProceduralSpace::Uv, scale: 2.0, colors: black_and_white() };
+  let origin = Vector3::default();
+
+  assert_eq!(uv_checker.sample((0.1, 0.1), &origin).unwrap(), Color::new(0.0, 0.0, 0.0));
+  assert_eq!(uv_checker.sample((0.6, 0.1), &origin).unwrap(), Color::new(1.0, 1.0, 1.0));
+  assert_eq!(uv_checker.sample((0.6, 0.6), &origin).unwrap(), Color::new(0.0, 0.0, 0.0));
+
+  // the solid checker ignores the texture coordinates
+  let world_checker = Checker { space: ProceduralSpace::World, scale: 1.0, colors: black_and_white() };
+  assert_eq!(world_checker.sample((0.6, 0.1), &Vector3::new(0.5, 0.5, 0.5)).unwrap(), Color::new(0.0, 0.0, 0.0));
+  assert_eq!(world_checker.sample((0.6, 0.1), &Vector3::new(0.5, 0.5, -0.5)).unwrap(), Color::new(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn noise_range() {
+  for index in 0..1000 {
+    let point = Vector3::new(index as f32 * 0.173, index as f32 * -0.291, index as f32 * 0.057);
+    let value = perlin(&point);
+
+    assert!((-1.0..=1.0).contains(&value));
+    assert!((-1.0..=1.0).contains(&fbm(&point, 4)));
+    assert!((0.0..=1.0).contains(&turbulence(&point, 4)));
+  }
+
+  // the noise is zero at the lattice points and it's the same for the same point
+  assert_eq!(perlin(&Vector3::new(3.0, -2.0, 7.0)), 0.0);
+  assert_eq!(fbm(&Vector3::new(0.3, 0.4, 0.5), 3), fbm(&Vector3::new(0.3, 0.4, 0.5), 3));
+
+  // the noise at the negative coordinates doesn't repeat along the other axes
+  assert_ne!(perlin(&Vector3::new(-1.5, 0.5, 0.5)), perlin(&Vector3::new(-1.5, 1.5, 0.5)));
+  assert_ne!(perlin(&Vector3::new(0.5, -1.5, 0.5)), perlin(&Vector3::new(0.5, -1.5, 1.5)));
+}
+
+#[test]
+fn gradient_and_grid() {
+  let gradient = Gradient { space: ProceduralSpace::Uv, direction: Vector3::new(1.0, 0.0, 0.0), colors: black_and_white() };
+  assert_eq!(gradient.sample((0.25, 0.9), &Vector3::default()).unwrap(), Color::new(0.25, 0.25, 0.25));
+  assert_eq!(gradient.sample((1.0, 0.9), &Vector3::default()).unwrap(), Color::new(1.0, 1.0, 1.0));
+
+  let grid = UvGrid { cells: 4, line_width: 0.1, line_color: Color::new(1.0, 1.0, 1.0) };
+  assert_eq!(grid.sample((0.5, 0.3), &Vector3::default()).unwrap(), Color::new(1.0, 1.0, 1.0));
+  assert_eq!(grid.sample((0.625, 0.125), &Vector3::default()).unwrap(), Color::new(0.625, 0.125, 0.5));
+}
+
+#[test]
+fn kinds() {
+  for kind in PROCEDURAL_KINDS.iter() {
+    assert_eq!(ProceduralKind::from_name(kind.name()), Some(*kind));
+
+    let color = kind.create(3.0).sample((0.3, 0.7), &Vector3::new(0.2, -1.4, 2.5)).unwrap();
+    assert!([color.r, color.g, color.b].iter().all(|value| (0.0..=1.0).contains(value)));
+  }
+
+  assert_eq!(ProceduralKind::parse("marble"), Some((ProceduralKind::Marble, 1.0)));
+  assert_eq!(ProceduralKind::parse("wood 2.5"), Some((ProceduralKind::Wood, 2.5)));
+  assert_eq!(ProceduralKind::parse("wood -1"), None);
+  assert_eq!(ProceduralKind::parse("wood 1 2"), None);
+  assert_eq!(ProceduralKind::parse("textures/wood.tga"), None);
+}
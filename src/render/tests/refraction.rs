@@ -0,0 +1,61 @@
+use crate::render::refraction::{refract, trace_through};
+
+use super::{Ior, Sphere, Triangle};
+use super::math::{ApproxEq, Rnd, Vector3};
+
+#[test]
+fn snell_law() {
+  let up = Vector3::new(0.0, 1.0, 0.0);
+  let down = Vector3::new(0.0, -1.0, 0.0);
+  assert!(refract(&down, &up, 1.0 / 1.5).unwrap().approx_eq(&down, 1e-6));
+
+  // the sine shrinks by the ratio of the IORs, the refracted direction stays normalized
+  let oblique = Vector3::new(0.6, -0.8, 0.0);
+  let refracted = refract(&oblique, &up, 1.0 / 1.5).unwrap();
+  assert!((refracted.x - 0.4).abs() < 1e-5 && refracted.y < 0.0, "{}", refracted);
+  assert!((refracted.length() - 1.0).abs() < 1e-5);
+
+  // the light going out of the glass at the grazing angle is reflected back
+  assert!(refract(&Vector3::new(0.8, -0.6, 0.0), &up, 1.5).is_none());
+}
+
+#[test]
+fn through_sphere() {
+  let sphere = Sphere::new(Vector3::default(), 1.0, 0);
+  let top = Vector3::new(0.0, 1.0, 0.0);
+  let down = Vector3::new(0.0, -1.0, 0.0);
+  let rnd = Rnd::new(1);
+
+  // the light through the center isn't bent, almost all of it leaves at the bottom
+  let passage = trace_through(&sphere, &top, &top, &down, 1.5, &rnd).unwrap();
+  assert!(passage.direction.approx_eq(&down, 1e-5), "{}", passage.direction);
+  assert!((passage.point.y + 1.0).abs() < 1e-3, "{}", passage.point);
+
+  // the off-center light is bent towards the axis, the blue more than the red and the diamond even more
+  let drop = Vector3::new(0.6, 0.8, 0.0);
+  let exit_direction = |ior: f32| {
+    (0..200).filter_map(|_| trace_through(&sphere, &drop, &drop, &down, ior, &rnd))
+      .find(|passage| passage.point.y < 0.0)
+      .unwrap()
+      .direction
+  };
+
+  let red = exit_direction(Ior::GLASS.get(650.0));
+  let blue = exit_direction(Ior::GLASS.get(450.0));
+  let diamond = exit_direction(Ior::DIAMOND.get_mean());
+  assert!(red.x < 0.0 && blue.x < red.x && diamond.x < blue.x, "{} {} {}", red, blue, diamond);
+}
+
+#[test]
+fn through_open_object() {
+  let triangle = Triangle::new([
+    &Vector3::new(0.0, 0.0, 0.0),
+    &Vector3::new(1.0, 0.0, 0.0),
+    &Vector3::new(0.0, 0.0, 1.0),
+  ], 0);
+  let drop = Vector3::new(0.2, 0.0, 0.2);
+  let direction = Vector3::new(0.6, -0.8, 0.0);
+
+  let passage = trace_through(&triangle, &drop, &Vector3::new(0.0, 1.0, 0.0), &direction, 1.5, &Rnd::new(1)).unwrap();
+  assert_eq!((passage.point, passage.direction), (drop, direction));
+}
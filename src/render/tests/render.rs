@@ -0,0 +1,232 @@
+use std::io::Cursor;
+
+use super::{
+  AdaptiveSampling, AovKind, Camera, Color, Denoiser, Filter, FilterKind, Material, MaterialKind, Render, Scene, Skybox, Texture,
+};
+use super::math::Vector3;
+
+const SKYBOX_24_BPP: &[u8] = include_bytes!("res/skybox_32x24_24_bpp.tga");
+
+// render of the textured skybox seen by the camera, the tests add their objects and settings
+fn skybox_render(eye: Vector3, look_at: Vector3, fov: f32, width: u32, height: u32) -> Render {
+  let mut render = Render::new();
+  let skybox = Skybox::new(Texture::from_tga(Cursor::new(SKYBOX_24_BPP)).unwrap());
+  render.scene = Scene::new(skybox, Color::new(1.0, 1.0, 1.0), 1.0);
+  render.camera = Camera::new(eye, look_at, fov);
+  render.resize_image(width, height);
+
+  render
+}
+
+#[test]
+fn save_and_load_state() {
+  let mut render = skybox_render(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 0.0, 0.0), 1.2, 4, 3);
+  render.set_aov_enabled(true);
+  render.begin_render(7, 2, false);
+  render.render(5).unwrap();
+  render.set_denoiser(Some(Denoiser::default()));
+  render.spectral = true;
+
+  let mut stream = Cursor::new(Vec::new());
+  render.save_state(&mut stream).unwrap();
+  stream.set_position(0);
+
+  let mut loaded = Render::new();
+  loaded.load_state(&mut stream).unwrap();
+
+  assert_eq!((loaded.image_width, loaded.image_height), (4, 3));
+  assert_eq!(loaded.get_samples(), 2);
+  assert_eq!(loaded.get_max_reflections(), 7);
+  assert_eq!(loaded.get_progress(), render.get_progress());
+  assert_eq!(loaded.camera.eye, Vector3::new(1.0, 2.0, 3.0));
+  assert_eq!(loaded.camera.fov, 1.2);
+  assert_eq!(loaded.get_denoiser(), Some(Denoiser::default()));
+  assert!(loaded.is_aov_enabled());
+  assert!(loaded.spectral);
+  assert_eq!(loaded.get_aov_texture(AovKind::Direct).unwrap().color_buffer,
+             render.get_aov_texture(AovKind::Direct).unwrap().color_buffer);
+
+  for y in 0..3 {
+    for x in 0..4 {
+      assert_eq!(loaded.get_pixel(x, y), render.get_pixel(x, y));
+    }
+  }
+}
+
+#[test]
+fn load_invalid_state() {
+  let mut render = Render::new();
+  assert!(render.load_state(Cursor::new(vec![0u8; 64])).is_err());
+
+  render.resize_image(2, 2);
+  render.begin_render(1, 1, false);
+  let mut stream = Vec::new();
+  render.save_state(&mut stream).unwrap();
+  let corrupted = |offset: usize, value: &[u8]| {
+    let mut stream = stream.clone();
+    stream[offset..offset + value.len()].copy_from_slice(value);
+    Render::new().load_state(Cursor::new(stream)).unwrap_err().to_string()
+  };
+
+  // the garbage size is rejected before the image is allocated
+  assert_eq!(corrupted(8, &u32::MAX.to_le_bytes()), "Render state is corrupted");
+
+  // the incomplete render can't resume past the last row, the samples count can't overflow
+  assert_eq!(corrupted(20, &2u32.to_le_bytes()), "Render state is corrupted");
+  assert_eq!(corrupted(28, &i32::MAX.to_le_bytes()), "Render state is corrupted");
+  assert_eq!(corrupted(28, &i32::MIN.to_le_bytes()), "Render state is corrupted");
+  assert!(Render::new().load_state(Cursor::new(stream)).is_ok());
+}
+
+fn render_image(seed: u64, chunk_size: u32) -> Vec<Color> {
+  let mut render = skybox_render(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 0.0, 0.0), 1.2, 5, 4);
+  render.seed = seed;
+  render.begin_render(5, 2, true);
+
+  while !render.is_complete {
+    render.render(chunk_size).unwrap();
+  }
+
+  (0..4).flat_map(|y| (0..5).map(move |x| (x, y))).map(|(x, y)| render.get_pixel(x, y)).collect()
+}
+
+#[test]
+fn reproducible_render() {
+  // the image does not depend on the way the render is split into chunks, the same seed gives the same image
+  let image = render_image(3, 20);
+  assert_eq!(render_image(3, 1), image);
+  assert_eq!(render_image(3, 7), image);
+
+  // the other seed jitters the samples differently
+  assert_ne!(render_image(4, 20), image);
+}
+
+#[test]
+fn adaptive_sampling() {
+  // flat skybox converges after the minimal samples count
+  let mut render = skybox_render(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 0.0, 0.0), 0.01, 2, 2);
+  render.adaptive = Some(AdaptiveSampling { threshold: 0.01, min_samples: 4, max_samples: 64 });
+
+  for _ in 0..10 {
+    render.begin_render(5, 1, true);
+
+    while !render.is_complete {
+      render.render(4).unwrap();
+    }
+  }
+
+  assert_eq!(render.get_pixel_samples(0, 0), 4);
+  assert_eq!(render.get_sample_heatmap(0, 0), Color::new(1.0, 0.0, 0.0));
+
+  // stop-at-noise-level mode takes at least one batch of samples in a single frame
+  render.begin_render(5, 3, false);
+  render.render(4).unwrap();
+  assert_eq!(render.get_pixel_samples(1, 1), 9);
+}
+
+#[test]
+fn filter_splatting() {
+  // wide filter blends the neighbour pixels, progressive frames keep the weights normalized
+  let mut render = skybox_render(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 0.0, 0.0), 0.001, 3, 3);
+  render.filter = Filter::new(FilterKind::Gaussian, 1.5);
+
+  for _ in 0..3 {
+    render.begin_render(5, 2, true);
+
+    while !render.is_complete {
+      render.render(2).unwrap();
+    }
+  }
+
+  // the tiny field of view sees a single skybox texel, so every pixel gets the same color
+  let center = render.get_pixel(1, 1);
+
+  for y in 0..3 {
+    for x in 0..3 {
+      assert!((render.get_pixel(x, y).r - center.r).abs() < 1e-4);
+    }
+  }
+
+  assert_eq!(render.get_pixel_samples(1, 1), 12);
+}
+
+#[test]
+fn aov_textures() {
+  let mut render = skybox_render(Vector3::new(0.0, 0.0, 3.0), Vector3::new(0.0, 0.0, 0.0), 1.2, 9, 9);
+  let material = render.scene.get_library_mut().add_material(&Material::new(MaterialKind::Metal, Color::new(1.0, 0.0, 0.0), 1.0, 0.0));
+  render.scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+  render.begin_render(2, 1, false);
+  render.render(81).unwrap();
+
+  // depth, normal and albedo are kept for the denoiser anyway
+  assert!(render.get_aov_texture(AovKind::Depth).is_some());
+  assert!(render.get_aov_texture(AovKind::ObjectId).is_none());
+
+  render.set_aov_enabled(true);
+  render.begin_render(2, 1, false);
+  render.render(81).unwrap();
+
+  for kind in &[AovKind::Depth, AovKind::Normal, AovKind::Position, AovKind::Albedo, AovKind::ObjectId,
+    AovKind::MaterialId, AovKind::Direct, AovKind::Indirect, AovKind::Shadow]
+  {
+    let texture = render.get_aov_texture(*kind).unwrap();
+    assert_eq!((texture.width, texture.height, texture.color_buffer.len()), (9, 9, 243));
+  }
+
+  // the sphere is in the middle of the image, the sky in the corner
+  let albedo = render.get_aov_texture(AovKind::Albedo).unwrap();
+  assert_eq!(albedo.get_pixel_color(4, 4).unwrap(), Color::new(1.0, 0.0, 0.0));
+  let depth = render.get_aov_texture(AovKind::Depth).unwrap();
+  assert_eq!(depth.get_pixel_color(0, 0).unwrap(), Color::new(1.0, 1.0, 1.0));
+  let center_depth = depth.get_pixel_color(4, 4).unwrap();
+  assert!(center_depth.r > 0.0 && center_depth.r < 1.0, "{}", center_depth);
+}
+
+#[test]
+fn depth_along_view() {
+  // the wall facing the camera has the same depth in every pixel
+  let mut render = skybox_render(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), 1.2, 9, 9);
+  let material = render.scene.get_library_mut().add_material(&Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.0, 0.0));
+  render.scene.add_triangle([
+    &Vector3::new(-100.0, -100.0, 5.0),
+    &Vector3::new(-100.0, 300.0, 5.0),
+    &Vector3::new(300.0, -100.0, 5.0),
+  ], material, None);
+  render.begin_render(1, 1, false);
+  render.render(81).unwrap();
+
+  let depth = render.get_aov_texture(AovKind::Depth).unwrap();
+  let center_depth = depth.get_pixel_color(4, 4).unwrap();
+
+  for (x, y) in [(0, 0), (4, 0), (8, 8), (0, 4)] {
+    assert_eq!(depth.get_pixel_color(x, y).unwrap(), center_depth);
+  }
+}
+
+#[test]
+fn pick_pixel() {
+  let mut render = skybox_render(Vector3::new(0.0, 0.0, 3.0), Vector3::new(0.0, 0.0, 0.0), 1.2, 9, 9);
+  let material = render.scene.get_library_mut().add_material(&Material::new(MaterialKind::Metal, Color::new(1.0, 0.0, 0.0), 1.0, 0.0));
+  render.scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+
+  let picked = render.pick(4, 4).unwrap().unwrap();
+  assert_eq!(picked.object_index, 0);
+  assert!((picked.distance - 2.0).abs() < 1e-3, "{}", picked.distance);
+  assert_eq!(render.pick(0, 0).unwrap(), None);
+}
+
+#[test]
+fn render_whole_image() {
+  let sky_color = Color::new(0.2, 0.4, 0.6);
+  let mut render = Render::new();
+  render.scene = Scene::new(Skybox::from_color(&sky_color), Color::new(1.0, 1.0, 1.0), 1.0);
+  render.camera = Camera::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), 1.0);
+
+  let texture = render.render_image(5, 4, 2, 1).unwrap();
+  assert_eq!((texture.width, texture.height), (5, 4));
+  assert!(render.is_complete);
+  assert_eq!(texture.color_buffer, sky_color.rgb().repeat(5 * 4));
+
+  assert!(render.render_image(0, 4, 2, 1).is_err());
+  assert!(render.render_image(5, 0, 2, 1).is_err());
+}
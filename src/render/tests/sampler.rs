@@ -0,0 +1,123 @@
+use super::{SampleId, SamplerKind, SampleStream, SAMPLER_KINDS};
+
+fn sample_id(x: u32, y: u32, frame: u32, index: u32, count: u32) -> SampleId {
+  SampleId { seed: 5, frame, x, y, index, count }
+}
+
+#[test]
+fn names() {
+  for &kind in SAMPLER_KINDS.iter() {
+    assert_eq!(SamplerKind::from_name(kind.name()), Some(kind));
+  }
+
+  assert_eq!(SamplerKind::from_name("random"), None);
+}
+
+#[test]
+fn ranges_and_determinism() {
+  for &kind in SAMPLER_KINDS.iter() {
+    let sampler = kind.create();
+
+    for index in 0..64 {
+      for dimension in 0..100 {
+        let id = sample_id(3, 7, 1, index, 64);
+        let value = sampler.get(&id, dimension);
+        assert!((0.0..1.0).contains(&value), "{:?} gives {}", kind, value);
+        assert_eq!(sampler.get(&id, dimension), value);
+      }
+    }
+  }
+}
+
+#[test]
+fn stream_dimensions() {
+  let sampler = SamplerKind::Sobol.create();
+  let id = sample_id(1, 2, 0, 3, 4);
+  let mut stream = SampleStream::new(sampler.as_ref(), id);
+  let (a, b) = stream.next_2d();
+  stream.set_dimension(1);
+
+  assert_eq!(a, sampler.get(&id, 0));
+  assert_eq!(b, stream.next_1d());
+  assert_eq!(stream.next_3d(), [sampler.get(&id, 2), sampler.get(&id, 3), sampler.get(&id, 4)]);
+
+  // the generator takes one dimension, it's the same for the same sample and differs for the others
+  let value = stream.next_rnd().next_u32();
+  assert_eq!(stream.next_1d(), sampler.get(&id, 6));
+  stream.set_dimension(5);
+  assert_eq!(stream.next_rnd().next_u32(), value);
+
+  let mut other = SampleStream::new(sampler.as_ref(), sample_id(1, 2, 0, 4, 4));
+  other.set_dimension(5);
+  assert_ne!(other.next_rnd().next_u32(), value);
+}
+
+// every one of n equal intervals holds exactly one of n samples
+fn is_stratified(values: &[f32]) -> bool {
+  let n = values.len();
+  let mut hits = vec![0; n];
+  values.iter().for_each(|&value| hits[(value * n as f32) as usize] += 1);
+  hits.iter().all(|&h| h == 1)
+}
+
+#[test]
+fn stratification() {
+  for &kind in [SamplerKind::Stratified, SamplerKind::Sobol].iter() {
+    let sampler = kind.create();
+
+    for dimension in 0..12 {
+      let values: Vec<f32> = (0..16).map(|index| sampler.get(&sample_id(9, 4, 0, index, 16), dimension)).collect();
+      assert!(is_stratified(&values), "{:?} dimension {}", kind, dimension);
+    }
+  }
+
+  // the first two Sobol dimensions form a (0, 2)-sequence, every cell of 4x4 grid gets one sample
+  let sampler = SamplerKind::Sobol.create();
+  let mut cells = [0; 16];
+
+  for index in 0..16 {
+    let id = sample_id(2, 2, 0, index, 16);
+    let (x, y) = (sampler.get(&id, 0), sampler.get(&id, 1));
+    cells[(x * 4.0) as usize + (y * 4.0) as usize * 4] += 1;
+  }
+
+  assert!(cells.iter().all(|&c| c == 1));
+}
+
+#[test]
+fn halton_progressive() {
+  // consecutive frames continue the sequence, so 2 frames of 4 samples are stratified as 8 samples
+  let sampler = SamplerKind::Halton.create();
+  let values: Vec<f32> = (0..2)
+    .flat_map(|frame| (0..4).map(move |index| (frame, index)))
+    .map(|(frame, index)| sampler.get(&sample_id(0, 0, frame, index, 4), 0))
+    .collect();
+
+  let mut shifted: Vec<f32> = values.iter().map(|v| v - values[0]).map(|v| v - v.floor()).collect();
+  shifted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+  for (i, value) in shifted.iter().enumerate() {
+    assert!((value - i as f32 / 8.0).abs() < 1e-5, "{:?}", shifted);
+  }
+}
+
+#[test]
+fn blue_noise_mask() {
+  // the dither mask holds every threshold once
+  let sampler = SamplerKind::BlueNoise.create();
+  let mut values: Vec<f32> = (0..64 * 64).map(|i| sampler.get(&sample_id(i % 64, i / 64, 0, 0, 1), 0)).collect();
+  values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+  for (i, value) in values.iter().enumerate() {
+    assert!((value - (i as f32 + 0.5) / 4096.0).abs() < 1e-6);
+  }
+
+  // neighbour pixels differ more than in white noise, the mean difference of the white noise is 1/3
+  let diff: f32 = (0..63).map(|x| {
+    let a = sampler.get(&sample_id(x, 10, 0, 0, 1), 3);
+    let b = sampler.get(&sample_id(x + 1, 10, 0, 0, 1), 3);
+    (a - b).abs()
+  }).sum::<f32>() / 63.0;
+
+  assert!(diff > 0.4, "mean neighbour difference {}", diff);
+}
@@ -1,11 +1,23 @@
 use std::io::Cursor;
 
+use anyhow::Result;
+
 use super::{
+  Coat,
   Color,
+  DebugMode,
+  FirstHit,
+  Fog,
+  Ior,
   Material,
   MaterialKind,
+  SampleId,
+  SamplerKind,
+  SampleStream,
   Scene,
   Skybox,
+  SKY_DEPTH,
+  Volume,
 };
 use super::math::ApproxEq;
 use super::math::constants::DELTA;
@@ -14,6 +26,24 @@ use super::Texture;
 
 const SKYBOX_24_BPP: &[u8] = include_bytes!("res/skybox_32x24_24_bpp.tga");
 
+// the sample of the sampler kind, the pixel mean is taken from "count" samples
+fn with_samples<T>(kind: SamplerKind, index: u32, count: u32, trace: impl FnOnce(&mut SampleStream) -> Result<T>) -> T {
+  let sampler = kind.create();
+  let id = SampleId { seed: 0, frame: 0, x: 0, y: 0, index, count };
+  trace(&mut SampleStream::new(sampler.as_ref(), id)).unwrap()
+}
+
+fn trace_sample(scene: &Scene, origin: Vector3, ray: Vector3, depth: u32, index: u32, count: u32,
+                first_hit: Option<&mut FirstHit>) -> Color
+{
+  with_samples(SamplerKind::Independent, index, count, |samples| scene.trace(&origin, &ray, depth, samples, first_hit))
+}
+
+// the stratified samples spread the wavelengths of the spectral paths evenly
+fn trace_spectral_sample(scene: &Scene, origin: Vector3, ray: Vector3, depth: u32, index: u32, count: u32) -> Color {
+  with_samples(SamplerKind::Stratified, index, count, |samples| scene.trace_spectral(&origin, &ray, depth, samples, None))
+}
+
 #[test]
 fn trace_plastic_triangle() {
   let skybox_texture_stream = Cursor::new(SKYBOX_24_BPP);
@@ -22,6 +52,7 @@ fn trace_plastic_triangle() {
   let mut scene = Scene::new(skybox, Color::new(1.0, 1.0, 1.0), 1.0);
   scene.add_spot_light(Vector3::new(100.0, 100.0, 100.0), 10.0, Color::new(1.0, 1.0, 1.0), 1.0);
   let material = Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 1.0, 0.0);
+  let material = scene.get_library_mut().add_material(&material);
 
   scene.add_triangle([
     &Vector3::new(10.0, 0.0, 0.0),
@@ -31,7 +62,7 @@ fn trace_plastic_triangle() {
 
   let trace_origin = Vector3::new(30.0, 30.0, 30.0);
   let trace_ray = Vector3::new(-1.0, -1.0, -1.0);
-  let color = scene.trace(&trace_origin, &trace_ray, 10).unwrap();
+  let color = trace_sample(&scene, trace_origin, trace_ray, 10, 0, 1, None);
   assert_eq!(color, Color::new(1.0, 1.0, 1.0), "Hit color of triangle");
 }
 
@@ -43,11 +74,302 @@ fn trace_metal_ball() {
   let mut scene = Scene::new(skybox, Color::new(1.0, 1.0, 1.0), 1.0);
   //scene.add_spot_light(Vector3::new(100.0, 100.0, 100.0), 10.0, Color::new(1.0, 1.0, 1.0), 1.0);
   let material = Material::new(MaterialKind::Metal, Color::new(1.0, 1.0, 1.0), 1.0, 0.0);
-  scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material);
+  let material = scene.get_library_mut().add_material(&material);
+  scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
 
   let trace_origin = Vector3::new(10.0, 0.0, 0.0);
   let trace_ray = Vector3::new(-1.0, 0.0, 0.0);
-  let color = scene.trace(&trace_origin, &trace_ray, 10).unwrap();
+  let color = trace_sample(&scene, trace_origin, trace_ray, 10, 0, 1, None);
   let expected = Color::new(0.2, 1.0, 1.0);
   assert!(color.approx_eq(&expected, DELTA), "Hit color of skybox\n left: {}\n right: {}", color, expected);
 }
+
+#[test]
+fn trace_first_hit() {
+  let skybox_texture = Texture::from_tga(Cursor::new(SKYBOX_24_BPP)).unwrap();
+  let mut scene = Scene::new(Skybox::new(skybox_texture), Color::new(1.0, 1.0, 1.0), 1.0);
+  let material = Material::new(MaterialKind::Metal, Color::new(0.5, 0.6, 0.7), 1.0, 0.0);
+  let other_material = Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 1.0, 0.0);
+  let other_material = scene.get_library_mut().add_material(&other_material);
+  let material = scene.get_library_mut().add_material(&material);
+  scene.add_sphere(Vector3::new(0.0, 5.0, 0.0), 1.0, other_material, None);
+  scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+  // the light is behind the first sphere from the second one
+  scene.add_spot_light(Vector3::new(0.0, 100.0, 0.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0);
+
+  let mut first_hit = FirstHit::default();
+  trace_sample(&scene, Vector3::new(10.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0), 10, 0, 1, Some(&mut first_hit));
+
+  assert_eq!(first_hit.albedo, Color::new(0.5, 0.6, 0.7));
+  assert!(first_hit.normal.approx_eq(&Vector3::new(1.0, 0.0, 0.0), DELTA), "{}", first_hit.normal);
+  assert!((first_hit.depth - 9.0).abs() < DELTA);
+  assert!(first_hit.position.approx_eq(&Vector3::new(1.0, 0.0, 0.0), DELTA), "{}", first_hit.position);
+  assert_eq!((first_hit.object_id, first_hit.material_id), (2, 2));
+  assert_eq!(first_hit.shadow, 0.0);
+
+  // top of the second sphere is shadowed by the first one
+  let mut first_hit = FirstHit::default();
+  trace_sample(&scene, Vector3::new(0.0, 1.5, 3.0), Vector3::new(0.0, -0.5, -3.0), 10, 0, 1, Some(&mut first_hit));
+
+  assert_eq!(first_hit.object_id, 2);
+  assert_eq!(first_hit.shadow, 1.0);
+
+  // missed ray sees the sky
+  let mut first_hit = FirstHit::default();
+  trace_sample(&scene, Vector3::new(10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), 10, 0, 1, Some(&mut first_hit));
+
+  assert_eq!(first_hit.depth, SKY_DEPTH);
+  assert_eq!(first_hit.normal, Vector3::default());
+  assert_eq!(first_hit.object_id, 0);
+}
+
+#[test]
+fn trace_debug_modes() {
+  let skybox_texture = Texture::from_tga(Cursor::new(SKYBOX_24_BPP)).unwrap();
+  let mut scene = Scene::new(Skybox::new(skybox_texture), Color::new(1.0, 1.0, 1.0), 1.0);
+  let material = Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 1.0, 0.0);
+  let material = scene.get_library_mut().add_material(&material);
+  scene.add_triangle([
+    &Vector3::new(0.0, 0.0, 0.0),
+    &Vector3::new(1.0, 0.0, 0.0),
+    &Vector3::new(0.0, 1.0, 0.0),
+  ], material, None);
+  scene.add_sphere(Vector3::new(0.0, 0.0, 5.0), 1.0, material, None);
+  scene.add_spot_light(Vector3::new(0.0, 0.0, 100.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0);
+
+  let trace = |origin: Vector3, ray: Vector3, mode: DebugMode| {
+    with_samples(SamplerKind::Independent, 0, 1, |samples| scene.trace_debug(&origin, &ray, 4, samples, mode))
+  };
+
+  // triangle point at (0.25, 0.25) is weighted by all three vertices
+  let weights = trace(Vector3::new(0.25, 0.25, 1.0), Vector3::new(0.0, 0.0, -1.0), DebugMode::Barycentric);
+  assert!((weights.r - 0.5).abs() < DELTA && (weights.g - 0.25).abs() < DELTA && (weights.b - 0.25).abs() < DELTA, "{}", weights);
+
+  let normal = trace(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, -1.0), DebugMode::Normal);
+  assert!((normal.b - 1.0).abs() < DELTA && (normal.r - 0.5).abs() < DELTA, "{}", normal);
+
+  assert_eq!(trace(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, 1.0), DebugMode::Depth), Color::default());
+  assert_eq!(trace(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, -1.0), DebugMode::MaterialKind), Color::new(0.3, 0.6, 1.0));
+
+  // the light is visible from the sphere, but blocked by the sphere for the triangle
+  assert_eq!(trace(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, -1.0), DebugMode::Occlusion), Color::new(1.0, 1.0, 1.0));
+  assert_eq!(trace(Vector3::new(0.1, 0.1, 1.0), Vector3::new(0.0, 0.0, -1.0), DebugMode::Occlusion), Color::default());
+
+  // a ray to the sky stops after the first bounce
+  assert_ne!(trace(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, 1.0), DebugMode::Bounces),
+             trace(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, -1.0), DebugMode::Bounces));
+}
+
+#[test]
+fn pick_object() {
+  let skybox_texture = Texture::from_tga(Cursor::new(SKYBOX_24_BPP)).unwrap();
+  let mut scene = Scene::new(Skybox::new(skybox_texture), Color::new(1.0, 1.0, 1.0), 1.0);
+  let material = Material::new(MaterialKind::Metal, Color::new(0.5, 0.6, 0.7), 0.25, 0.0);
+  let material_id = scene.get_library_mut().add_named_material("blue_metal", &material);
+  scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material_id, None);
+  scene.add_sphere(Vector3::new(0.0, 0.0, -5.0), 1.0, material_id, None);
+
+  let picked = scene.pick(&Vector3::new(0.0, 0.0, 10.0), &Vector3::new(0.0, 0.0, -2.0)).unwrap().unwrap();
+  assert_eq!((picked.object_index, picked.object_type, picked.material_id), (0, "sphere", 1));
+  assert!(picked.position.approx_eq(&Vector3::new(0.0, 0.0, 1.0), DELTA), "{}", picked.position);
+  assert!(picked.normal.approx_eq(&Vector3::new(0.0, 0.0, 1.0), DELTA), "{}", picked.normal);
+  assert!((picked.distance - 9.0).abs() < DELTA);
+  assert_eq!(picked.material, material);
+  assert_eq!(picked.material_name.as_deref(), Some("blue_metal"));
+
+  assert_eq!(scene.pick(&Vector3::new(0.0, 0.0, 10.0), &Vector3::new(0.0, 0.0, 1.0)).unwrap(), None);
+}
+
+#[test]
+fn lighting_sky() {
+  let mut skybox = Skybox::from_color(&Color::new(1.0, 1.0, 1.0));
+  skybox.set_lighting(true).unwrap();
+  // no diffuse light and no light sources, the sky is the only light
+  let mut scene = Scene::new(skybox, Color::default(), 0.0);
+  let material = Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.0, 0.0);
+  let material = scene.get_library_mut().add_material(&material);
+  scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+
+  let count = 1024;
+  let mut direct = 0.0;
+
+  // the top of the sphere sees the whole upper hemisphere, the dielectric reflects a fifth of the light at normal incidence
+  for index in 0..count {
+    let mut first_hit = FirstHit::default();
+    trace_sample(&scene, Vector3::new(0.0, 10.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 1, index, count, Some(&mut first_hit));
+    direct += first_hit.direct.r / count as f32;
+  }
+
+  assert!((direct - 0.8).abs() < 0.05, "{}", direct);
+
+  // the lighting sky isn't scaled by the scene lights
+  let sky = trace_sample(&scene, Vector3::new(0.0, 10.0, 0.0), Vector3::new(0.0, 1.0, 0.0), 1, 0, 1, None);
+  assert_eq!(sky, Color::new(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn fog_hides_sky() {
+  let mut scene = Scene::new(Skybox::from_color(&Color::new(1.0, 1.0, 1.0)), Color::new(1.0, 1.0, 1.0), 1.0);
+  scene.set_fog(Some(Fog { color: Color::new(0.5, 0.5, 0.5), density: 0.1, height_falloff: 0.0, base_height: 0.0 }));
+
+  // the homogeneous fog swallows the whole sky and scatters the diffuse light instead
+  let color = trace_sample(&scene, Vector3::default(), Vector3::new(0.0, 1.0, 0.0), 1, 0, 1, None);
+  assert!(color.approx_eq(&Color::new(0.5, 0.5, 0.5), DELTA), "{}", color);
+}
+
+#[test]
+fn fog_keeps_sun() {
+  // the sun is far beyond the sky, the thin global fog dims it only within the scene
+  let mut scene = Scene::new(Skybox::from_color(&Color::default()), Color::default(), 0.0);
+  scene.add_spot_light(Vector3::new(0.0, 1.0e10, 0.0), 3.0e8, Color::new(1.0, 1.0, 1.0), 1.0);
+  let material = Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.0, 0.0);
+  let material = scene.get_library_mut().add_material(&material);
+  scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+
+  let trace = |scene: &Scene| {
+    let mut first_hit = FirstHit::default();
+    trace_sample(scene, Vector3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 1, 0, 1, Some(&mut first_hit));
+    first_hit.direct
+  };
+
+  let clear = trace(&scene);
+  scene.set_fog(Some(Fog { color: Color::new(1.0, 1.0, 1.0), density: 1.0e-7, height_falloff: 0.0, base_height: 0.0 }));
+  let foggy = trace(&scene);
+  assert!(clear.r > 0.5 && foggy.r > 0.8 * clear.r, "{} {}", foggy, clear);
+}
+
+#[test]
+fn volume_shadows_and_shafts() {
+  let smoke = |center| Volume { center, radius: 1.0, color: Color::new(1.0, 1.0, 1.0), density: 1.0 };
+  let mut scene = Scene::new(Skybox::from_color(&Color::default()), Color::default(), 0.0);
+  scene.add_spot_light(Vector3::new(0.0, 10.0, 0.0), 0.0, Color::new(1.0, 1.0, 1.0), 1.0);
+  let material = Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.0, 0.0);
+  let material = scene.get_library_mut().add_material(&material);
+  scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+
+  let trace = |scene: &Scene, origin: Vector3, ray: Vector3| {
+    let mut first_hit = FirstHit::default();
+    trace_sample(scene, origin, ray, 1, 0, 1, Some(&mut first_hit));
+    first_hit.direct
+  };
+
+  let lit = trace(&scene, Vector3::new(3.0, 4.0, 0.0), Vector3::new(-1.0, -1.0, 0.0));
+  scene.add_volume(smoke(Vector3::new(0.0, 5.0, 0.0)));
+  scene.add_volume(smoke(Vector3::new(0.0, -3.0, 0.0)));
+
+  // the smoke between the light and the sphere dims the light passing twice its radius
+  let shadowed = trace(&scene, Vector3::new(3.0, 4.0, 0.0), Vector3::new(-1.0, -1.0, 0.0));
+  assert!((shadowed.r - lit.r * f32::exp(-2.0)).abs() < 1e-4, "{} {}", shadowed, lit);
+
+  // the lit smoke scatters the light towards the camera, the smoke in the shadow of the sphere stays dark
+  assert!(trace(&scene, Vector3::new(-5.0, 5.0, 0.0), Vector3::new(1.0, 0.0, 0.0)).r > 0.05);
+  assert_eq!(trace(&scene, Vector3::new(-5.0, -3.0, 0.0), Vector3::new(1.0, 0.0, 0.0)), Color::default());
+}
+
+#[test]
+fn subsurface_translucency() {
+  // the light is under the sphere, so its top is lit only by the light coming through the sphere
+  let top_light = |material: Material| {
+    let mut scene = Scene::new(Skybox::from_color(&Color::default()), Color::default(), 0.0);
+    scene.add_spot_light(Vector3::new(0.0, -10.0, 0.0), 0.0, Color::new(1.0, 1.0, 1.0), 1.0);
+    let material = scene.get_library_mut().add_material(&material);
+    scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+
+    let count = 1024;
+
+    (0..count).map(|index| {
+      let mut first_hit = FirstHit::default();
+      trace_sample(&scene, Vector3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 1, index, count, Some(&mut first_hit));
+      first_hit.direct.r / count as f32
+    }).sum::<f32>()
+  };
+
+  let white = Color::new(1.0, 1.0, 1.0);
+  assert_eq!(top_light(Material::new(MaterialKind::Dielectric, white.clone(), 0.0, 0.0)), 0.0);
+  assert!(top_light(Material::subsurface(white.clone(), white.clone(), 2.0, 0.0)) > 0.1);
+  assert!(top_light(Material::subsurface(white.clone(), white, 0.01, 0.0)) < 0.01);
+}
+
+#[test]
+fn coat_highlight() {
+  // the black base has no highlight, the clear coat reflects the light above by the Fresnel share
+  let highlight = |material: Material| {
+    let mut scene = Scene::new(Skybox::from_color(&Color::default()), Color::default(), 0.0);
+    scene.add_spot_light(Vector3::new(0.0, 10.0, 0.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0);
+    let material = scene.get_library_mut().add_material(&material);
+    scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+
+    let mut first_hit = FirstHit::default();
+    trace_sample(&scene, Vector3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 1, 0, 1, Some(&mut first_hit));
+    first_hit.direct
+  };
+
+  let black = Material::new(MaterialKind::Dielectric, Color::default(), 0.0, 0.0);
+  assert_eq!(highlight(black.clone()), Color::default());
+
+  let lacquer = highlight(black.clone().with_coat(Coat::new(1.5, 0.0)));
+  assert!((lacquer.r - 0.04).abs() < 0.005 && lacquer.r == lacquer.b, "{}", lacquer);
+
+  let soap = highlight(black.with_coat(Coat::new(1.0, 0.0).with_thin_film(380.0, 1.33)));
+  assert!((soap.r - soap.b).abs() > 0.02, "{}", soap);
+}
+
+#[test]
+fn glass_shows_sky() {
+  // the white sky seen through the clear sphere is dimmed only by the reflections of its surfaces,
+  // the opaque black dielectric shows only the reflected sky
+  let through = |material: Material| {
+    let mut scene = Scene::new(Skybox::from_color(&Color::new(1.0, 1.0, 1.0)), Color::new(1.0, 1.0, 1.0), 1.0);
+    let material = scene.get_library_mut().add_material(&material);
+    scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+
+    let count = 64;
+
+    (0..count).map(|index| {
+      let color = trace_sample(&scene, Vector3::new(0.0, 0.0, 5.0), Vector3::new(0.1, 0.0, -1.0), 4, index, count, None);
+      color.g / count as f32
+    }).sum::<f32>()
+  };
+
+  let glass = through(Material::transparent(Color::new(1.0, 1.0, 1.0), Ior::GLASS));
+  assert!(glass > 0.9, "{}", glass);
+  assert!(through(Material::new(MaterialKind::Dielectric, Color::default(), 1.0, 0.0)) < 0.5);
+}
+
+#[test]
+fn spectral_gray_sky() {
+  // the spectral paths of the gray sky give the RGB color in the pixel mean
+  let scene = Scene::new(Skybox::from_color(&Color::new(0.5, 0.5, 0.5)), Color::new(1.0, 1.0, 1.0), 1.0);
+  let count = 256;
+  let mut sum = Color::default();
+
+  for index in 0..count {
+    sum += trace_spectral_sample(&scene, Vector3::default(), Vector3::new(0.0, 1.0, 0.0), 1, index, count) / count as f32;
+  }
+
+  let rgb = trace_sample(&scene, Vector3::default(), Vector3::new(0.0, 1.0, 0.0), 1, 0, 1, None);
+  assert!(rgb.approx_eq(&Color::new(0.5, 0.5, 0.5), 0.01), "{}", rgb);
+  assert!(sum.approx_eq(&rgb, 0.02), "{} {}", sum, rgb);
+}
+
+#[test]
+fn spectral_colored_light() {
+  // the yellow light on the cyan surface leaves the green in both modes, the colors are multiplied as spectra
+  let mut scene = Scene::new(Skybox::from_color(&Color::default()), Color::default(), 0.0);
+  scene.add_spot_light(Vector3::new(0.0, 10.0, 0.0), 0.0, Color::new(1.0, 1.0, 0.0), 1.0);
+  let material = Material::new(MaterialKind::Dielectric, Color::new(0.0, 1.0, 1.0), 0.0, 0.0);
+  let material = scene.get_library_mut().add_material(&material);
+  scene.add_sphere(Vector3::new(0.0, 0.0, 0.0), 1.0, material, None);
+
+  let count = 256;
+  let mut sum = Color::default();
+
+  for index in 0..count {
+    let color = trace_spectral_sample(&scene, Vector3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 1, index, count);
+    sum += color / count as f32;
+  }
+
+  let rgb = trace_sample(&scene, Vector3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 1, 0, 1, None);
+  assert!(rgb.g > 0.5 && rgb.r == 0.0 && rgb.b == 0.0, "{}", rgb);
+  assert!(sum.approx_eq(&rgb, 0.1), "{} {}", sum, rgb);
+}
@@ -0,0 +1,155 @@
+use std::rc::Rc;
+
+use super::{
+  Coat, Color, DebugMode, HdrImage, Ior, Material, MaterialKind, MaterialPreset, PhysicalSky, SampleId, SamplerKind, SampleStream, SceneBuilder,
+  SkyMapping, Texture, TextureHandle, MATERIAL_PRESETS,
+};
+use super::math::Vector3;
+
+fn down() -> Vector3 {
+  Vector3::new(0.0, -1.0, 0.0)
+}
+
+fn floor() -> [Vector3; 4] {
+  [
+    Vector3::new(-5.0, 0.0, -5.0),
+    Vector3::new(-5.0, 0.0, 5.0),
+    Vector3::new(5.0, 0.0, 5.0),
+    Vector3::new(5.0, 0.0, -5.0),
+  ]
+}
+
+fn with_camera() -> SceneBuilder {
+  SceneBuilder::new().camera(Vector3::new(0.0, 1.0, -5.0), Vector3::new(0.0, 0.0, 0.0), 1.0)
+}
+
+#[test]
+fn presets() {
+  for preset in &MATERIAL_PRESETS {
+    assert_eq!(MaterialPreset::from_name(preset.name()), Some(*preset));
+  }
+
+  assert_eq!(MaterialPreset::from_name("velvet"), None);
+  assert_eq!(MaterialPreset::Gold.material().kind, MaterialKind::Metal);
+
+  // every preset is a valid material
+  for preset in MATERIAL_PRESETS {
+    assert!(with_camera().sphere(Vector3::default(), 1.0, preset).build().is_ok(), "{}", preset.name());
+  }
+}
+
+#[test]
+fn build_scene() {
+  let texture: TextureHandle = Rc::new(Texture { width: 1, height: 1, color_buffer: vec![255, 0, 0] });
+  let shared = Rc::new(Material::new(MaterialKind::Dielectric, Color::new(0.5, 0.5, 0.5), 0.2, 0.0));
+
+  let (scene, camera) = with_camera()
+    .sky_color(Color::new(0.1, 0.2, 0.3))
+    .sun(Vector3::new(1.0, 1.0, 0.0), Color::new(1.0, 1.0, 1.0), 0.8)
+    .sphere(Vector3::new(0.0, 1.0, 0.0), 1.0, MaterialPreset::Chrome)
+    .sphere(Vector3::new(3.0, 1.0, 0.0), 0.5, Rc::clone(&shared))
+    .textured_quad(floor(), MaterialPreset::Matte, &texture)
+    .material("shared", Rc::clone(&shared))
+    .texture("floor", &texture)
+    .build()
+    .unwrap();
+
+  assert_eq!(camera.fov, 1.0);
+
+  // the quad triangles share the texture and the material
+  let library = scene.get_library();
+  assert_eq!((library.get_material_count(), library.get_texture_count()), (3, 1));
+  assert_eq!(library.find_texture("floor"), Some(0));
+  assert_eq!(library.get_material(library.find_material("shared").unwrap()), &*shared);
+
+  let sphere = scene.pick(&Vector3::new(0.0, 5.0, 0.0), &down()).unwrap().unwrap();
+  assert_eq!((sphere.object_index, sphere.object_type), (0, "sphere"));
+  assert_eq!(sphere.material, MaterialPreset::Chrome.material());
+
+  let small_sphere = scene.pick(&Vector3::new(3.0, 5.0, 0.0), &down()).unwrap().unwrap();
+  assert_eq!(small_sphere.material, *shared);
+
+  // both halves of the quad are hit
+  let near_half = scene.pick(&Vector3::new(-4.0, 5.0, -4.0), &down()).unwrap().unwrap();
+  let far_half = scene.pick(&Vector3::new(4.0, 5.0, 4.0), &down()).unwrap().unwrap();
+  assert_eq!((near_half.object_index, far_half.object_index), (2, 3));
+  assert!((near_half.uv.0 - 0.1).abs() < 1e-4 && (near_half.uv.1 - 0.1).abs() < 1e-4);
+}
+
+#[test]
+fn build_mesh() {
+  let vertices = floor();
+  let (scene, _) = with_camera()
+    .mesh(&vertices, &[[0, 1, 3], [1, 2, 3]], MaterialPreset::GlossyPlastic)
+    .build()
+    .unwrap();
+
+  let hit = scene.pick(&Vector3::new(4.0, 5.0, 4.0), &down()).unwrap().unwrap();
+  assert_eq!((hit.object_index, hit.object_type), (1, "triangle"));
+}
+
+#[test]
+fn validation_errors() {
+  let material = || MaterialPreset::Mirror;
+  let error = |builder: SceneBuilder| builder.build().err().map(|err| err.to_string());
+
+  assert_eq!(error(SceneBuilder::new()), Some(String::from("Camera is not set")));
+  assert!(error(with_camera().sphere(Vector3::default(), 0.0, material())).is_some());
+  assert!(error(with_camera().sphere(Vector3::default(), f32::NAN, material())).is_some());
+  assert!(error(with_camera().triangle([Vector3::default(), Vector3::default(), Vector3::new(1.0, 0.0, 0.0)], material())).is_some());
+  assert!(error(with_camera().mesh(&floor(), &[[0, 1, 4]], material())).is_some());
+  assert!(error(with_camera().light(Vector3::default(), -1.0, Color::new(1.0, 1.0, 1.0), 1.0)).is_some());
+  assert!(error(with_camera().sun(Vector3::default(), Color::new(1.0, 1.0, 1.0), 1.0)).is_some());
+  assert!(error(with_camera().sky_color(Color::new(-1.0, 0.0, 0.0))).is_some());
+  assert!(error(SceneBuilder::new().camera(Vector3::default(), Vector3::default(), 1.0)).is_some());
+  assert!(error(with_camera().sky(&Texture::default())).is_some());
+  assert!(error(with_camera().sky_image(&Rc::new(HdrImage::default()), SkyMapping::Equirectangular)).is_some());
+  assert!(error(with_camera().sky_intensity(-1.0)).is_some());
+  assert!(error(with_camera().physical_sky(PhysicalSky::new(&down(), 3.0, 0.3), -1.0)).is_some());
+  assert!(error(with_camera().fog(Color::new(1.5, 1.0, 1.0), 0.1)).is_some());
+  assert!(error(with_camera().height_fog(Color::new(1.0, 1.0, 1.0), 0.1, 0.0, -1.0)).is_some());
+  assert!(error(with_camera().volume(Vector3::default(), 0.0, Color::new(1.0, 1.0, 1.0), 1.0)).is_some());
+  assert!(error(with_camera().volume(Vector3::default(), 1.0, Color::new(1.0, 1.0, 1.0), -1.0)).is_some());
+
+  let rough = Material::new(MaterialKind::Metal, Color::new(1.0, 1.0, 1.0), 1.5, 0.0);
+  assert!(error(with_camera().sphere(Vector3::default(), 1.0, rough)).is_some());
+
+  let pole = Material { ior: Ior::Sellmeier { b: [1.0, 0.0, 0.0], c: [0.3, 0.0, 0.0] }, ..Material::default() };
+  assert!(error(with_camera().sphere(Vector3::default(), 1.0, pole)).is_some());
+
+  let flat = Material::subsurface(Color::new(1.0, 1.0, 1.0), Color::new(1.0, 1.0, 1.0), 0.0, 0.5);
+  assert!(error(with_camera().sphere(Vector3::default(), 1.0, flat)).is_some());
+
+  let coated = || Material::new(MaterialKind::Dielectric, Color::new(1.0, 1.0, 1.0), 0.5, 0.0);
+  assert!(error(with_camera().sphere(Vector3::default(), 1.0, coated().with_coat(Coat::new(0.5, 0.0)))).is_some());
+  assert!(error(with_camera().sphere(Vector3::default(), 1.0, coated().with_coat(Coat::new(1.5, 2.0)))).is_some());
+  let film = Coat::new(1.5, 0.0).with_thin_film(-1.0, 1.33);
+  assert!(error(with_camera().sphere(Vector3::default(), 1.0, coated().with_coat(film))).is_some());
+  let vacuum = Material::transparent(Color::new(1.0, 1.0, 1.0), Ior::Constant(0.5));
+  assert!(error(with_camera().sphere(Vector3::default(), 1.0, vacuum)).is_some());
+
+  // the first error is reported even if the later calls are valid
+  let result = error(with_camera().sphere(Vector3::default(), -1.0, material()).quad(floor(), material()));
+  assert_eq!(result, Some(String::from("Sphere radius should be positive")));
+}
+
+#[test]
+fn physical_sky_sun() {
+  // the top of the sphere is lit by the sun above the horizon only
+  let occlusion = |elevation: f32| {
+    let sky = PhysicalSky::new(&PhysicalSky::sun_direction(elevation.to_radians(), 0.0), 3.0, 0.3);
+    let (scene, _) = with_camera()
+      .physical_sky(sky, 1.0)
+      .sphere(Vector3::default(), 1.0, MaterialPreset::Matte)
+      .build()
+      .unwrap();
+
+    let sampler = SamplerKind::Independent.create();
+    let id = SampleId { seed: 0, frame: 0, x: 0, y: 0, index: 0, count: 1 };
+    let mut samples = SampleStream::new(sampler.as_ref(), id);
+    scene.trace_debug(&Vector3::new(0.0, 5.0, 0.0), &down(), 1, &mut samples, DebugMode::Occlusion).unwrap()
+  };
+
+  assert_eq!(occlusion(60.0), Color::new(1.0, 1.0, 1.0));
+  assert_eq!(occlusion(-10.0), Color::new(0.0, 0.0, 0.25));
+}
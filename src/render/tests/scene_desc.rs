@@ -0,0 +1,315 @@
+use crate::render::scene_desc::Shape;
+
+use std::path::Path;
+use std::rc::Rc;
+
+use super::{Camera, Color, HdrImage, Ior, MaterialKind, SceneDesc, SkyMapping, TextureCache};
+use super::math::Vector3;
+
+// the materials are validated by the builder, the sky is taken from the cache
+fn builds(text: &str) -> bool {
+  let mut textures = TextureCache::new();
+  textures.insert(String::from("sky.tga"), Rc::new(HdrImage { width: 8, height: 6, pixels: vec![Color::default(); 8 * 6] }));
+  SceneDesc::parse(text).and_then(|scene| scene.build(Path::new(""), &mut textures)).is_ok()
+}
+
+#[test]
+fn parse_example_file() {
+  let scene = SceneDesc::parse(include_str!("../../../assets/scene.ini")).unwrap();
+  assert_eq!(scene, SceneDesc::default());
+}
+
+#[test]
+fn save_and_parse() {
+  let scene = SceneDesc::default();
+  assert_eq!(SceneDesc::parse(&scene.to_text()).unwrap(), scene);
+}
+
+#[test]
+fn parse_objects() {
+  let text = "
+    [sky]
+    texture = sky.tga
+
+    [sphere]
+    center = 1, 2, 3
+    material = metal
+    color = 0.5, 0.25, 1
+
+    [triangle]
+    v1 = 1, 0, 0 # comment
+    v2 = 0, 1, 0
+    texture = floor.tga
+    uv2 = 0.5, 1
+  ";
+
+  let scene = SceneDesc::parse(text).unwrap();
+  assert!(scene.lights.is_empty());
+  assert_eq!(scene.objects.len(), 2);
+
+  let sphere = &scene.objects[0];
+  assert_eq!(sphere.shape, Shape::Sphere { center: Vector3::new(1.0, 2.0, 3.0), radius: 1.0, texture: None });
+  assert_eq!(sphere.material.kind, MaterialKind::Metal);
+  assert_eq!(sphere.material.color, Color::new(0.5, 0.25, 1.0));
+
+  match &scene.objects[1].shape {
+    Shape::Triangle { vertices, texture, uvs } => {
+      assert_eq!(vertices[1], Vector3::new(1.0, 0.0, 0.0));
+      assert_eq!(texture.as_deref(), Some("floor.tga"));
+      assert_eq!(uvs[2], (0.5, 1.0));
+    }
+    shape => panic!("Unexpected shape {:?}", shape),
+  }
+}
+
+#[test]
+fn parse_errors() {
+  let with_sky = |text: &str| format!("[sky]\ntexture = sky.tga\n{}", text);
+
+  assert!(SceneDesc::parse("[sphere]").is_err());
+  assert!(SceneDesc::parse(&with_sky("[cube]")).is_err());
+  assert!(SceneDesc::parse(&with_sky("[sphere]\nsize = 1")).is_err());
+  assert!(SceneDesc::parse(&with_sky("[sphere]\ncenter = 1, 2")).is_err());
+  assert!(SceneDesc::parse(&with_sky("[sphere]\nradius = 0")).is_err());
+  assert!(SceneDesc::parse(&with_sky("[sphere]\nmaterial = glass")).is_err());
+  assert!(SceneDesc::parse(&with_sky("[sphere]\nv0 = 1, 2, 3")).is_err());
+  assert!(SceneDesc::parse(&with_sky("[camera]\nfov = 4")).is_err());
+  assert!(SceneDesc::parse(&with_sky("[camera]\neye = 1, 1, 1\nlook_at = 1, 1, 1")).is_err());
+
+  let err = SceneDesc::parse(&with_sky("[light]\npower = strong")).unwrap_err();
+  assert!(format!("{:#}", err).contains("Line 4"));
+}
+
+#[test]
+fn edit_object() {
+  let mut object = SceneDesc::default().objects[8].clone();
+  let center = object.get_center();
+
+  object.translate(&Vector3::new(0.0, 1.0, 0.0));
+  assert_eq!(object.get_center(), &center + Vector3::new(0.0, 1.0, 0.0));
+
+  object.scale(2.0);
+  assert!((&object.get_center() - &center - Vector3::new(0.0, 1.0, 0.0)).length() < 1e-4);
+}
+
+#[test]
+fn set_camera() {
+  let mut scene = SceneDesc::default();
+  let camera = Camera::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(1.0, 2.0, 5.0), 0.5);
+  scene.set_camera(&camera);
+
+  assert_eq!(scene.camera.eye, Vector3::new(1.0, 2.0, 3.0));
+  assert!((&scene.camera.look_at - Vector3::new(1.0, 2.0, 4.0)).length() < 1e-5);
+  assert_eq!(scene.camera.fov, 0.5);
+}
+
+#[test]
+fn texture_names() {
+  let scene = SceneDesc::default();
+  assert_eq!(scene.get_texture_names(), vec!["textures/skybox.tga", "textures/periodic.tga"]);
+}
+
+#[test]
+fn procedural_textures() {
+  let text = "
+    [sky]
+    texture = sky.tga
+
+    [camera]
+    look_at = 0, 0, 1
+
+    [sphere]
+    texture = marble 2
+
+    [sphere]
+    center = 3, 0, 0
+    texture = marble 2
+  ";
+
+  let scene = SceneDesc::parse(text).unwrap();
+  assert_eq!(scene.objects[0].get_texture(), Some("marble 2"));
+  assert_eq!(scene.get_texture_names(), vec!["sky.tga"]);
+  assert_eq!(SceneDesc::parse(&scene.to_text()).unwrap(), scene);
+
+  // the sky is taken from the cache, so nothing is loaded from the disk
+  let mut textures = TextureCache::new();
+  textures.insert(String::from("sky.tga"), Rc::new(HdrImage { width: 8, height: 6, pixels: vec![Color::default(); 8 * 6] }));
+  let built = scene.build(Path::new(""), &mut textures).unwrap();
+
+  // the sky and the marble shared by both spheres
+  let library = built.get_library();
+  assert_eq!(library.get_texture_count(), 2);
+  assert_eq!(library.find_texture("marble 2"), Some(1));
+}
+
+#[test]
+fn sky_settings() {
+  let text = "
+    [sky]
+    texture = px.hdr, nx.hdr, py.hdr, ny.hdr, pz.hdr, nz.hdr
+    mapping = faces
+    rotation = 90
+    intensity = 2.5
+    lighting = true
+  ";
+
+  let scene = SceneDesc::parse(text).unwrap();
+  assert_eq!(scene.sky_mapping, SkyMapping::Faces);
+  assert_eq!((scene.sky_rotation, scene.sky_intensity, scene.sky_lighting), (90.0, 2.5, true));
+  assert_eq!(scene.get_texture_names(), vec!["px.hdr", "nx.hdr", "py.hdr", "ny.hdr", "pz.hdr", "nz.hdr"]);
+  assert_eq!(SceneDesc::parse(&scene.to_text()).unwrap(), scene);
+
+  let with_sky = |text: &str| format!("[sky]\ntexture = sky.hdr\n{}", text);
+  assert!(SceneDesc::parse(&with_sky("mapping = sphere")).is_err());
+  assert!(SceneDesc::parse(&with_sky("mapping = faces")).is_err());
+  assert!(SceneDesc::parse(&with_sky("intensity = -1")).is_err());
+  assert!(SceneDesc::parse(&with_sky("mapping = equirectangular\nlighting = true")).is_ok());
+}
+
+#[test]
+fn physical_sky() {
+  let text = "
+    [camera]
+    look_at = 0, 0, 1
+
+    [sky]
+    mapping = physical
+    sun_elevation = 15
+    sun_azimuth = 120
+    turbidity = 4
+    lighting = true
+  ";
+
+  let scene = SceneDesc::parse(text).unwrap();
+  assert_eq!((scene.sun.elevation, scene.sun.azimuth, scene.sun.turbidity), (15.0, 120.0, 4.0));
+  assert!(scene.get_texture_names().is_empty());
+  assert_eq!(SceneDesc::parse(&scene.to_text()).unwrap(), scene);
+
+  // nothing is loaded
+  let mut textures = TextureCache::new();
+  scene.build(Path::new(""), &mut textures).unwrap();
+  assert!(textures.is_empty());
+
+  assert!(SceneDesc::parse("[sky]\nmapping = physical\nturbidity = 0.5").is_err());
+  assert!(SceneDesc::parse("[sky]\nmapping = physical\nsun_elevation = 100").is_err());
+  assert!(SceneDesc::parse("[sky]\nmapping = equirectangular").is_err());
+}
+
+#[test]
+fn fog_and_volumes() {
+  let text = "
+    [camera]
+    look_at = 0, 0, 1
+
+    [sky]
+    texture = sky.tga
+
+    [fog]
+    color = 0.8, 0.8, 0.9
+    density = 0.2
+    height_falloff = 0.5
+
+    [volume]
+    center = 1, 2, 3
+    radius = 2
+
+    [volume]
+    density = 3
+  ";
+
+  let scene = SceneDesc::parse(text).unwrap();
+  let fog = scene.fog.as_ref().unwrap();
+  assert_eq!((fog.density, fog.height_falloff, fog.base_height), (0.2, 0.5, 0.0));
+  assert_eq!(scene.volumes.len(), 2);
+  assert_eq!((scene.volumes[0].center.clone(), scene.volumes[0].radius), (Vector3::new(1.0, 2.0, 3.0), 2.0));
+  assert_eq!((scene.volumes[1].radius, scene.volumes[1].density), (1.0, 3.0));
+  assert_eq!(SceneDesc::parse(&scene.to_text()).unwrap(), scene);
+
+  assert!(SceneDesc::parse("[sky]\ntexture = sky.tga\n[fog]\ndensity = -1").is_err());
+  assert!(SceneDesc::parse("[sky]\ntexture = sky.tga\n[fog]\ncolor = 2, 0, 0").is_err());
+  assert!(SceneDesc::parse("[sky]\ntexture = sky.tga\n[volume]\nradius = 0").is_err());
+}
+
+#[test]
+fn subsurface_material() {
+  let text = "
+    [camera]
+    look_at = 0, 0, 1
+
+    [sky]
+    texture = sky.tga
+
+    [sphere]
+    material = subsurface
+    color = 0.9, 0.8, 0.7
+    scattering_distance = 0.2
+    scattering_color = 1, 0.5, 0.25
+  ";
+
+  let scene = SceneDesc::parse(text).unwrap();
+  let material = &scene.objects[0].material;
+  assert_eq!(material.kind, MaterialKind::Subsurface);
+  assert_eq!((material.scattering_distance, material.scattering_color.clone()), (0.2, Color::new(1.0, 0.5, 0.25)));
+  assert_eq!(SceneDesc::parse(&scene.to_text()).unwrap(), scene);
+
+  assert!(builds(text));
+  assert!(!builds("[sky]\ntexture = sky.tga\n[sphere]\nmaterial = subsurface"));
+}
+
+#[test]
+fn coated_material() {
+  let text = "
+    [camera]
+    look_at = 0, 0, 1
+
+    [sky]
+    texture = sky.tga
+
+    [sphere]
+    material = metal
+    coat_roughness = 0.1
+
+    [sphere]
+    film_thickness = 300
+  ";
+
+  let scene = SceneDesc::parse(text).unwrap();
+  let coat = scene.objects[0].material.coat.as_ref().unwrap();
+  assert_eq!((coat.ior, coat.roughness, coat.thin_film.is_none()), (1.5, 0.1, true));
+  let film = scene.objects[1].material.coat.as_ref().unwrap().thin_film.as_ref().unwrap();
+  assert_eq!((film.thickness, film.ior), (300.0, 1.33));
+  assert_eq!(SceneDesc::parse(&scene.to_text()).unwrap(), scene);
+
+  assert!(builds(text));
+  assert!(!builds("[sky]\ntexture = sky.tga\n[sphere]\ncoat_ior = 0.5"));
+  assert!(!builds("[sky]\ntexture = sky.tga\n[sphere]\nfilm_thickness = -5"));
+  assert!(!builds("[sky]\ntexture = sky.tga\n[sphere]\nfilm_thickness = inf"));
+}
+
+#[test]
+fn dispersive_ior() {
+  let text = "
+    [camera]
+    look_at = 0, 0, 1
+
+    [sky]
+    texture = sky.tga
+
+    [sphere]
+    transparency = 1
+    ior = cauchy 1.5046, 0.0042
+
+    [sphere]
+    ior = 2.4
+  ";
+
+  let scene = SceneDesc::parse(text).unwrap();
+  assert_eq!(scene.objects[0].material.ior, Ior::Cauchy { a: 1.5046, b: 0.0042 });
+  assert_eq!(scene.objects[1].material.ior, Ior::Constant(2.4));
+  assert_eq!(SceneDesc::parse(&scene.to_text()).unwrap(), scene);
+
+  assert!(builds(text));
+  assert!(!builds("[sky]\ntexture = sky.tga\n[sphere]\nior = 0.5"));
+  assert!(!builds("[sky]\ntexture = sky.tga\n[sphere]\nior = sellmeier 1, 0, 0, 0.3, 0, 0"));
+  assert!(SceneDesc::parse("[sky]\ntexture = sky.tga\n[sphere]\nior = sellmeier 1, 2").is_err());
+}
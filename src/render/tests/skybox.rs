@@ -1,7 +1,9 @@
+use std::f32::consts::PI;
 use std::io::Cursor;
+use std::rc::Rc;
 
-use super::{Color, Skybox, Texture};
-use super::math::Vector3;
+use super::{Color, HdrImage, SkyMapping, Skybox, Texture};
+use super::math::{ApproxEq, Vector3};
 
 const SKYBOX_24_BPP: &[u8] = include_bytes!("res/skybox_32x24_24_bpp.tga");
 
@@ -69,3 +71,97 @@ fn trace_front_left_interpolated() {
   // let front_left_color = skybox.trace(&Vector3::new(-1.0, 0.0, 0.8192)).unwrap();
   // assert_eq!(front_left_color, Color::new(0.0, 0.0, 0.0));
 }
+
+#[test]
+fn trace_single_color() {
+  let color = Color::new(0.0, 1.0, 1.0);
+  let skybox = Skybox::from_color(&color);
+
+  for ray in &[Vector3::new(1.0, 0.2, 0.1), Vector3::new(-0.3, -1.0, 0.5), Vector3::new(0.0, 0.7, -0.7)] {
+    assert_eq!(skybox.trace(ray).unwrap(), color);
+  }
+}
+
+fn image(width: u32, height: u32, color: impl Fn(u32, u32) -> Color) -> Rc<HdrImage> {
+  let pixels = (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)| color(x, y)).collect();
+  Rc::new(HdrImage { width, height, pixels })
+}
+
+#[test]
+fn trace_equirectangular() {
+  // the back half of the longitudes is red, the front half is blue
+  let halves = image(8, 4, |x, _| if x < 4 { Color::new(1.0, 0.0, 0.0) } else { Color::new(0.0, 0.0, 1.0) });
+  let mut skybox = Skybox::equirectangular(halves);
+  assert_eq!(skybox.get_mapping(), SkyMapping::Equirectangular);
+  assert_eq!(skybox.trace(&Vector3::new(0.0, 0.0, 1.0)).unwrap(), Color::new(0.0, 0.0, 1.0));
+  assert_eq!(skybox.trace(&Vector3::new(0.0, 0.0, -1.0)).unwrap(), Color::new(1.0, 0.0, 0.0));
+
+  // the HDR colors aren't clamped
+  skybox.set_rotation(PI);
+  skybox.set_intensity(2.0);
+  let color = skybox.trace(&Vector3::new(0.0, 0.0, 1.0)).unwrap();
+  assert!(color.approx_eq(&Color::new(2.0, 0.0, 0.0), 1e-5), "{}", color);
+}
+
+#[test]
+fn trace_faces() {
+  let colors = [
+    Color::new(1.0, 0.0, 0.0),
+    Color::new(0.0, 1.0, 0.0),
+    Color::new(0.0, 0.0, 1.0),
+    Color::new(1.0, 1.0, 0.0),
+    Color::new(0.0, 1.0, 1.0),
+    Color::new(4.0, 4.0, 4.0),
+  ];
+
+  let faces = [0, 1, 2, 3, 4, 5].map(|face| image(2, 2, |_, _| colors[face].clone()));
+  let skybox = Skybox::from_faces(faces);
+  let axes = [
+    Vector3::new(1.0, 0.0, 0.0),
+    Vector3::new(-1.0, 0.0, 0.0),
+    Vector3::new(0.0, 1.0, 0.0),
+    Vector3::new(0.0, -1.0, 0.0),
+    Vector3::new(0.0, 0.0, 1.0),
+    Vector3::new(0.0, 0.0, -1.0),
+  ];
+
+  for (axis, color) in axes.iter().zip(&colors) {
+    assert_eq!(&skybox.trace(axis).unwrap(), color);
+  }
+}
+
+#[test]
+fn sample_light() {
+  let mut skybox = Skybox::from_color(&Color::new(1.0, 1.0, 1.0));
+  assert_eq!(skybox.sample_light((0.5, 0.5)).unwrap(), None);
+
+  // the integral of the unit radiance over the sphere
+  skybox.set_lighting(true).unwrap();
+  let count = 64;
+  let mut sum = 0.0;
+
+  for i in 0..count {
+    for j in 0..count {
+      let sample = ((i as f32 + 0.5) / count as f32, (j as f32 + 0.5) / count as f32);
+
+      if let Some(sky) = skybox.sample_light(sample).unwrap() {
+        assert!((sky.direction.length() - 1.0).abs() < 1e-4);
+        sum += sky.color.r / sky.pdf;
+      }
+    }
+  }
+
+  let integral = sum / (count * count) as f32;
+  assert!((integral - 4.0 * PI).abs() < 0.05 * 4.0 * PI, "{}", integral);
+
+  // the bright upper half of the sky gets almost all the samples, the lower half is blended with it near the horizon
+  let mut skybox = Skybox::equirectangular(image(32, 16, |_, y| Color::new(1.0, 1.0, 1.0) * if y >= 8 { 100.0 } else { 0.1 }));
+  skybox.set_lighting(true).unwrap();
+
+  let upper = (0..100)
+    .filter_map(|i| skybox.sample_light((0.37, (i as f32 + 0.5) / 100.0)).unwrap())
+    .filter(|sky| sky.direction.y > -0.1 && sky.color.r > 1.0)
+    .count();
+
+  assert!(upper > 95, "{}", upper);
+}
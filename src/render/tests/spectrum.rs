@@ -0,0 +1,51 @@
+use crate::render::spectrum::{color_to_spectrum, sample_wavelength, wavelength_to_color, MAX_WAVELENGTH, MIN_WAVELENGTH};
+
+use super::Color;
+use super::math::ApproxEq;
+
+// mean RGB of the evenly spread wavelengths, like the pixel mean of many spectral paths
+fn spectral_mean(color: &Color) -> Color {
+  let count = 4000;
+
+  (0..count).map(|index| {
+    let wavelength = sample_wavelength((index as f32 + 0.5) / count as f32);
+    wavelength_to_color(wavelength) * color_to_spectrum(color, wavelength)
+  }).fold(Color::default(), |sum, color| sum + color / count as f32)
+}
+
+#[test]
+fn wavelength_range() {
+  assert_eq!(sample_wavelength(0.0), MIN_WAVELENGTH);
+  assert_eq!(sample_wavelength(1.0), MAX_WAVELENGTH);
+}
+
+#[test]
+fn white_round_trip() {
+  let white = Color::new(1.0, 1.0, 1.0);
+  assert!(spectral_mean(&white).approx_eq(&white, 0.01), "{}", spectral_mean(&white));
+
+  let gray = Color::new(0.3, 0.3, 0.3);
+  assert!(spectral_mean(&gray).approx_eq(&gray, 0.01), "{}", spectral_mean(&gray));
+
+  // the primaries come back close, their hue is kept
+  let red = spectral_mean(&Color::new(1.0, 0.0, 0.0));
+  assert!(red.r > 0.8 && red.g.abs() < 0.15 && red.b.abs() < 0.15, "{}", red);
+  let blue = spectral_mean(&Color::new(0.0, 0.0, 1.0));
+  assert!(blue.b > 0.8 && blue.r.abs() < 0.15 && blue.g.abs() < 0.15, "{}", blue);
+}
+
+#[test]
+fn wavelength_hue() {
+  let blue = wavelength_to_color(450.0);
+  assert!(blue.b > blue.g && blue.b > blue.r, "{}", blue);
+  let green = wavelength_to_color(530.0);
+  assert!(green.g > green.r && green.g > green.b, "{}", green);
+  let red = wavelength_to_color(650.0);
+  assert!(red.r > red.g && red.r > red.b, "{}", red);
+
+  // the bands of the upsampled spectrum
+  let color = Color::new(0.9, 0.5, 0.1);
+  assert_eq!(color_to_spectrum(&color, 420.0), 0.1);
+  assert_eq!(color_to_spectrum(&color, 540.0), 0.5);
+  assert_eq!(color_to_spectrum(&color, 700.0), 0.9);
+}
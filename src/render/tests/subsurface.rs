@@ -0,0 +1,63 @@
+use crate::render::subsurface::{random_walk, single_scattering_albedo};
+
+use super::{Color, Material, Sphere, Triangle};
+use super::math::{Rnd, Vector3};
+
+fn walk_sphere(color: Color, scattering_distance: f32) -> (Color, f32) {
+  let sphere = Sphere::new(Vector3::default(), 1.0, 0);
+  let material = Material::subsurface(color.clone(), Color::new(1.0, 1.0, 1.0), scattering_distance, 0.0);
+  let top = Vector3::new(0.0, 1.0, 0.0);
+  let rnd = Rnd::new(1);
+  let count = 2000;
+  let mut weight = Color::default();
+  let mut exit_height = 0.0;
+
+  for _ in 0..count {
+    let exit = random_walk(&sphere, &top, &top, &color, &material, &rnd).unwrap();
+
+    if exit.weight != Color::default() {
+      assert!((exit.point.length() - 1.0).abs() < 1e-3, "{}", exit.point);
+      assert!((&exit.norm - &exit.point).length() < 1e-3, "{}", exit.norm);
+    }
+
+    weight += exit.weight / count as f32;
+    exit_height += exit.point.y / count as f32;
+  }
+
+  (weight, exit_height)
+}
+
+#[test]
+fn albedo_inversion() {
+  assert!(single_scattering_albedo(0.0).abs() < 1e-5);
+  assert!((single_scattering_albedo(1.0) - 1.0).abs() < 1e-5);
+  assert!(single_scattering_albedo(0.5) > 0.5 && single_scattering_albedo(0.5) < single_scattering_albedo(0.8));
+}
+
+#[test]
+fn walk_inside_sphere() {
+  // nothing is absorbed by the white medium, the dark one keeps about its color
+  let (white, _) = walk_sphere(Color::new(1.0, 1.0, 1.0), 0.1);
+  assert!((white.r - 1.0).abs() < 0.05, "{}", white);
+
+  let (dark, near_height) = walk_sphere(Color::new(0.5, 0.5, 0.5), 0.1);
+  assert!(dark.r < 0.7 && dark.r > 0.3, "{}", dark);
+
+  // the light walks out near the entry in the dense medium and goes through the thin one
+  let (_, far_height) = walk_sphere(Color::new(0.5, 0.5, 0.5), 10.0);
+  assert!(near_height > 0.5 && far_height < 0.25, "{} {}", near_height, far_height);
+}
+
+#[test]
+fn open_objects() {
+  let triangle = Triangle::new([
+    &Vector3::new(0.0, 0.0, 0.0),
+    &Vector3::new(1.0, 0.0, 0.0),
+    &Vector3::new(0.0, 0.0, 1.0),
+  ], 0);
+  let color = Color::new(1.0, 1.0, 1.0);
+  let material = Material::subsurface(color.clone(), color.clone(), 0.1, 0.0);
+  let up = Vector3::new(0.0, 1.0, 0.0);
+
+  assert!(random_walk(&triangle, &Vector3::new(0.2, 0.0, 0.2), &up, &color, &material, &Rnd::new(1)).is_none());
+}
@@ -70,4 +70,13 @@ fn get_filtered_texel_color() {
   let y = 0.5 - 0.5 / texture.height as f32;
   let interpolated_texel_color = texture.get_texel_color(x, y).unwrap();
   assert_eq!(interpolated_texel_color, Color::new(0.25, 0.25, 0.25));
-}
\ No newline at end of file
+}
+
+#[test]
+fn to_tga() {
+  let texture = Texture::from_tga(Cursor::new(TEX_24_BPP)).unwrap();
+  let mut stream = Cursor::new(Vec::new());
+  texture.to_tga(&mut stream).unwrap();
+  stream.set_position(0);
+  validate_texture(Texture::from_tga(stream).unwrap());
+}
@@ -94,8 +94,11 @@ impl Texture {
 
         self.to_bmp(stream)
       }
-      ".tga" => {
-        Result::Err(Error::msg("Not implemented"))
+      "tga" => {
+        let file = File::create(path)?;
+        let stream = BufWriter::new(file);
+
+        self.to_tga(stream)
       }
       _ => {
         Result::Err(Error::msg("File not supported"))
@@ -184,6 +187,33 @@ impl Texture {
     })
   }
 
+  pub(in super) fn to_tga(&self, mut stream: impl Write) -> Result<()> {
+    if self.width > i16::MAX as u32 || self.height > i16::MAX as u32 {
+      return Result::Err(Error::msg("Image is too large for TGA format"));
+    }
+
+    stream.write_i8(0)?; // ident_size
+    stream.write_i8(0)?; // color_map_type
+    stream.write_i8(2)?; // image_type: uncompressed RGB
+    stream.write_i16::<LittleEndian>(0)?; // color_map_origin
+    stream.write_i16::<LittleEndian>(0)?; // color_map_length
+    stream.write_i8(0)?; // color_map_bits_per_entry
+    stream.write_i16::<LittleEndian>(0)?; // x_offset
+    stream.write_i16::<LittleEndian>(0)?; // y_offset
+    stream.write_i16::<LittleEndian>(self.width as i16)?;
+    stream.write_i16::<LittleEndian>(self.height as i16)?;
+    stream.write_i8(24)?; // bits_per_pixel
+    stream.write_i8(0)?; // image_descriptor: bottom left origin
+
+    for rgb in self.color_buffer.chunks(3) {
+      stream.write_all(&[rgb[2], rgb[1], rgb[0]])?;
+    }
+
+    stream.flush()?;
+
+    Ok(())
+  }
+
   pub(in super) fn to_bmp(&self, mut stream: impl Write + Seek) -> Result<()> {
     const FILE_HEADER_SIZE: u32 = size_of::<BMPFileHeader>() as u32;
     const INFO_HEADER_SIZE: u32 = size_of::<BMPInfoHeader>() as u32;
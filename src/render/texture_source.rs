@@ -0,0 +1,18 @@
+use std::fmt::Debug;
+
+use anyhow::Result;
+
+use super::{Color, Texture};
+use super::math::Vector3;
+
+// anything that gives the surface color: an image or a procedural pattern,
+// the 2D sources use the texture coordinates and the solid ones use the world position of the surface point
+pub trait TextureSource: Debug {
+  fn sample(&self, uv: (f32, f32), position: &Vector3) -> Result<Color>;
+}
+
+impl TextureSource for Texture {
+  fn sample(&self, uv: (f32, f32), _position: &Vector3) -> Result<Color> {
+    self.get_texel_color(uv.0, uv.1)
+  }
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 
-use super::Material;
+use super::{MaterialId, TextureId};
 use super::math::Vector3;
 
 pub trait Trace {
@@ -12,6 +12,28 @@ pub trait Trace {
     out_norm: Option<&mut Vector3>,
     out_reflected_ray: Option<&mut Vector3>,
     out_distance: Option<&mut f32>,
-    out_drop_material: Option<&mut Material>,
   ) -> Result<bool>;
+
+  // distance from a point inside the object to its surface along the normalized direction and the outward normal there,
+  // the open objects have no inside
+  fn trace_exit(&self, _origin: &Vector3, _direction: &Vector3) -> Option<(f32, Vector3)> {
+    None
+  }
+
+  fn get_material_id(&self) -> MaterialId;
+
+  // the texture replaces the material color, it's sampled at the texture coordinates
+  fn get_texture_id(&self) -> Option<TextureId> {
+    None
+  }
+
+  fn get_type_name(&self) -> &'static str;
+
+  // texture coordinates of the surface point
+  fn get_uv(&self, drop: &Vector3) -> (f32, f32);
+
+  // weights of the vertices at the surface point, only the objects made of vertices have them
+  fn get_barycentric(&self, _drop: &Vector3) -> Option<Vector3> {
+    None
+  }
 }
\ No newline at end of file
@@ -1,8 +1,6 @@
-use std::rc::Rc;
-
 use anyhow::Result;
 
-use super::{Material, Texture, Trace};
+use super::{MaterialId, TextureId, Trace};
 use super::math::{Matrix33, Vector3};
 use super::math::constants::{DELTA, VERY_SMALL_NUMBER};
 
@@ -14,15 +12,15 @@ pub struct Triangle {
   tu: [f32; 3],
   // vertexes texture v-coords
   tv: [f32; 3],
-  material: Material,
+  material: MaterialId,
   norm: Vector3,
-  texture: Option<Rc<Texture>>,
+  texture: Option<TextureId>,
   ax_transform: Matrix33,
   tuv_transform: Matrix33,
 }
 
 impl Triangle {
-  pub fn new(vertices: [&Vector3; 3], material: Material) -> Triangle
+  pub fn new(vertices: [&Vector3; 3], material: MaterialId) -> Triangle
   {
     let ax = vertices[2] - vertices[0];
     let ay = vertices[1] - vertices[0];
@@ -48,11 +46,11 @@ impl Triangle {
 
   pub fn set_texture(
     &mut self,
-    texture: Rc<Texture>,
+    texture: TextureId,
     texture_u_points: [f32; 3],
     texture_v_points: [f32; 3])
   {
-    self.texture = Some(Rc::clone(&texture));
+    self.texture = Some(texture);
 
     self.tu = texture_u_points;
     self.tv = texture_v_points;
@@ -61,6 +59,12 @@ impl Triangle {
     let v3 = Vector3::new(self.tu[2], self.tv[2], 0.0);
     self.tuv_transform = Matrix33::from_cols(&v3 - &v1, &v2 - &v1, Vector3::new(0.0, 0.0, -1.0));
   }
+
+  // coordinates of the point along the (v2 - v0) and (v1 - v0) edges
+  fn get_local_uv(&self, drop: &Vector3) -> (f32, f32) {
+    let local = &self.ax_transform * (drop - &self.v[0]);
+    (local.x, local.y)
+  }
 }
 
 impl Trace for Triangle {
@@ -72,7 +76,6 @@ impl Trace for Triangle {
     out_norm: Option<&mut Vector3>,
     out_reflected_ray: Option<&mut Vector3>,
     out_distance: Option<&mut f32>,
-    out_drop_material: Option<&mut Material>,
   ) -> Result<bool>
   {
     let transformed_origin = &self.ax_transform * (origin - &self.v[0]);
@@ -118,18 +121,35 @@ impl Trace for Triangle {
       *out_reflected_ray = full_ray.reflected(&self.norm);
     }
 
-    if let Some(out_drop_material) = out_drop_material {
-      if let Some(texture) = &self.texture {
-        let texture_vector = &self.tuv_transform * Vector3::new(u, v, 0.0);
-        (*out_drop_material).color = texture.get_texel_color(self.tu[0] + texture_vector.x, self.tv[0] + texture_vector.y)?;
-        (*out_drop_material).kind = self.material.kind.clone();
-        (*out_drop_material).reflectivity = self.material.reflectivity;
-        (*out_drop_material).transparency = self.material.transparency;
-      } else {
-        *out_drop_material = self.material.clone();
-      }
+    Ok(true)
+  }
+
+  fn get_type_name(&self) -> &'static str {
+    "triangle"
+  }
+
+  fn get_material_id(&self) -> MaterialId {
+    self.material
+  }
+
+  fn get_texture_id(&self) -> Option<TextureId> {
+    self.texture
+  }
+
+  // the triangle without a texture is mapped by its edges
+  fn get_uv(&self, drop: &Vector3) -> (f32, f32) {
+    let (u, v) = self.get_local_uv(drop);
+
+    if self.texture.is_some() {
+      let texture_vector = &self.tuv_transform * Vector3::new(u, v, 0.0);
+      (self.tu[0] + texture_vector.x, self.tv[0] + texture_vector.y)
+    } else {
+      (u, v)
     }
+  }
 
-    Ok(true)
+  fn get_barycentric(&self, drop: &Vector3) -> Option<Vector3> {
+    let (u, v) = self.get_local_uv(drop);
+    Some(Vector3::new(1.0 - u - v, v, u))
   }
 }
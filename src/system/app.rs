@@ -1,13 +1,18 @@
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
+use anyhow::{Context, Error, Result};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::math::constants::VERY_SMALL_NUMBER;
+use crate::math::Vector3;
 
 use super::default as Config;
-use super::KeyCode;
+use super::{GamepadAxis, KeyCode};
 use super::render::camera::{
+  AnalogControl,
   SHIFT_BACK_MASK,
   SHIFT_DOWN_MASK,
   SHIFT_FORWARD_MASK,
@@ -19,64 +24,65 @@ use super::render::camera::{
   TURN_RIGHT_MASK,
   TURN_UP_MASK,
 };
-use super::render::Render;
+use super::render::{AdaptiveSampling, Color, DebugMode, Denoiser, Filter, PickInfo, Render, Scene, SceneDesc, Skybox, TextureCache};
+use super::{ObjectEditor, Preview, ScreenshotForm, Settings};
+
+// the longer screenshot file name means the checkpoint is corrupted
+const MAX_CHECKPOINT_NAME_LENGTH: usize = 4096;
+// keys picking the resolutions and supersample rates of the screenshot lists
+const SELECT_KEYS: [KeyCode; 9] = [
+  KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8,
+  KeyCode::Key9,
+];
 
 #[derive(PartialEq)]
 enum State
 {
   New,
   CameraControl,
+  ObjectEdit,
   ScreenshotResolutionSelection,
   ScreenshotSamplingSelection,
+  ScreenshotCustomEntry,
   ScreenshotRenderBegin,
   ScreenshotRenderProceed,
   ScreenshotRenderEnd,
   ScreenshotRenderSave,
   ScreenshotRenderCancelRequested,
+  ScreenshotRenderPaused,
+  ScreenshotResumeQuery,
 }
 
-struct Resolution<'a>
-{
-  w: u32,
-  h: u32,
-  tip: &'a str,
-}
-
-const RESOLUTIONS: [Resolution; 9] = [
-  Resolution { w: 800, h: 600, tip: "(4:3)" },
-  Resolution { w: 1024, h: 768, tip: "(4:3)" },
-  Resolution { w: 1280, h: 960, tip: "(4:3)" },
-  Resolution { w: 1280, h: 800, tip: "(16:10)" },
-  Resolution { w: 1680, h: 1050, tip: "(16:10)" },
-  Resolution { w: 1920, h: 1200, tip: "(16:10)" },
-  Resolution { w: 1280, h: 720, tip: "(HD)" },
-  Resolution { w: 1920, h: 1080, tip: "(Full HD)" },
-  Resolution { w: 7680, h: 4320, tip: "(Super Hi Vision 16:9)" },
-];
-
-struct SsRate<'a>
-{
-  rate: i32,
-  tip: &'a str,
-}
-
-const SUPERSAMPLE_RATES: [SsRate; 9] = [
-  SsRate { rate: 1, tip: "(fast but rough)" },
-  SsRate { rate: 2, tip: "" },
-  SsRate { rate: 4, tip: "" },
-  SsRate { rate: 8, tip: "" },
-  SsRate { rate: 16, tip: "" },
-  SsRate { rate: 32, tip: "" },
-  SsRate { rate: 64, tip: "" },
-  SsRate { rate: 128, tip: "" },
-  SsRate { rate: 256, tip: "(slow but smooth)" },
-];
-
 pub struct App {
   render: Render,
   state: State,
+  settings: Settings,
+  settings_path: PathBuf,
+  settings_modified: Option<SystemTime>,
+  settings_check_time: Instant,
+  settings_error: Option<String>,
+  settings_version: u32,
+  root_path: PathBuf,
+  scene_desc: SceneDesc,
+  scene_path: PathBuf,
+  // scene file and the textures it uses with their modification times
+  scene_files: Vec<(PathBuf, Option<SystemTime>)>,
+  scene_check_time: Instant,
+  textures: TextureCache,
+  scene_error: Option<String>,
+  object_editor: Option<ObjectEditor>,
   last_pulse_time: Instant,
   control_flags: u32,
+  analog_control: AnalogControl,
+  gamepad_triggers: (f32, f32),
+  mouse_look: bool,
+  show_sample_heatmap: bool,
+  is_denoised: bool,
+  debug_mode: Option<DebugMode>,
+  // object inspected with the click or the crosshair in the image center
+  picked: Option<PickInfo>,
+  mouse_turn: (f32, f32),
+  is_view_changed: bool,
   frame_time_accumulator: f32,
   frame_time: f32,
   render_chunk_in_pixels: u32,
@@ -84,8 +90,19 @@ pub struct App {
   screenshot_width: u32,
   screenshot_height: u32,
   screenshot_samples: i32,
+  screenshot_reflections: u32,
+  screenshot_format: &'static str,
+  screenshot_custom_name: String,
+  screenshot_form: ScreenshotForm,
   screenshot_progress: f32,
   screenshot_start_ticks: Option<Instant>,
+  screenshot_start_progress: f32,
+  checkpoint_path: PathBuf,
+  checkpoint_time: Instant,
+  checkpoint_error: Option<String>,
+  preview: Preview,
+  preview_time: Instant,
+  is_preview_changed: bool,
   motion_dyn_samples: i32,
   prev_samples: i32,
   prev_in_motion: bool,
@@ -93,13 +110,42 @@ pub struct App {
   window_height: u32,
 }
 
+impl Default for App {
+  fn default() -> App {
+    App::new()
+  }
+}
+
 impl App {
   pub fn new() -> App {
     App {
       render: Render::new(),
       state: State::New,
+      settings: Settings::default(),
+      settings_path: PathBuf::default(),
+      settings_modified: None,
+      settings_check_time: Instant::now(),
+      settings_error: None,
+      settings_version: 0,
+      root_path: PathBuf::default(),
+      scene_desc: SceneDesc::default(),
+      scene_path: PathBuf::default(),
+      scene_files: Vec::new(),
+      scene_check_time: Instant::now(),
+      textures: TextureCache::new(),
+      scene_error: None,
+      object_editor: None,
       last_pulse_time: Instant::now(),
       control_flags: 0,
+      analog_control: AnalogControl::default(),
+      gamepad_triggers: (0.0, 0.0),
+      mouse_look: false,
+      show_sample_heatmap: false,
+      is_denoised: false,
+      debug_mode: None,
+      picked: None,
+      mouse_turn: (0.0, 0.0),
+      is_view_changed: false,
       frame_time_accumulator: 0.0,
       frame_time: 0.0,
       render_chunk_in_pixels: 0,
@@ -107,8 +153,19 @@ impl App {
       screenshot_width: 0,
       screenshot_height: 0,
       screenshot_samples: 0,
+      screenshot_reflections: 0,
+      screenshot_format: "bmp",
+      screenshot_custom_name: String::new(),
+      screenshot_form: new_screenshot_form(&Settings::default()),
       screenshot_progress: 0.0,
       screenshot_start_ticks: None,
+      screenshot_start_progress: 0.0,
+      checkpoint_path: PathBuf::default(),
+      checkpoint_time: Instant::now(),
+      checkpoint_error: None,
+      preview: Preview::default(),
+      preview_time: Instant::now(),
+      is_preview_changed: false,
       motion_dyn_samples: 0,
       prev_samples: 0,
       prev_in_motion: false,
@@ -120,12 +177,174 @@ impl App {
   pub fn init(&mut self) -> Result<()> {
     let exe_file_path = std::env::current_exe()?;
     let root_path = exe_file_path.parent().unwrap();
-    self.render.init_scene(root_path)?;
-    self.set_state(State::CameraControl);
+    self.root_path = root_path.to_path_buf();
+    self.scene_path = root_path.join(Config::SCENE_FILE_NAME);
+
+    // missing scene file is not an error, the built-in scene is used
+    let load_result = if self.scene_path.exists() {
+      SceneDesc::load_from_file(&self.scene_path).map(|scene_desc| self.scene_desc = scene_desc)
+    } else {
+      Ok(())
+    };
+
+    // broken scene is reported in the overlay, the viewer shows an empty scene until the files are fixed
+    self.render.scene = Scene::new(Skybox::from_color(&Color::default()), Color::default(), 0.0);
+    self.render.camera = self.scene_desc.get_camera();
+    self.rebuild_scene();
+    self.watch_scene_files();
+
+    if let Err(err) = load_result {
+      println!("Scene load failed: {:#}", err);
+      self.scene_error = Some(format!("{:#}", err));
+    }
+
+    self.settings_path = root_path.join(Config::SETTINGS_FILE_NAME);
+
+    // missing settings file is not an error, defaults are used
+    if self.settings_path.exists() {
+      self.settings = Settings::load_from_file(&self.settings_path)?;
+      self.settings_modified = std::fs::metadata(&self.settings_path)?.modified().ok();
+    }
+
+    self.screenshot_form = new_screenshot_form(&self.settings);
+    self.apply_settings();
+    self.checkpoint_path = root_path.join(Config::CHECKPOINT_FILE_NAME);
+
+    if self.checkpoint_path.exists() {
+      self.set_state(State::ScreenshotResumeQuery);
+    } else {
+      self.set_state(State::CameraControl);
+    }
 
     Ok(())
   }
 
+  pub fn get_settings(&self) -> &Settings {
+    &self.settings
+  }
+
+  // incremented on every successful reload, lets the caller rebuild data derived from settings
+  pub fn get_settings_version(&self) -> u32 {
+    self.settings_version
+  }
+
+  fn apply_settings(&mut self) {
+    self.render.camera.max_turn_speed = self.settings.turn_speed;
+    self.render.camera.max_shift_speed = self.settings.shift_speed;
+    self.render.seed = self.settings.seed;
+    self.render.set_sampler(self.settings.sampler);
+    self.render.filter = Filter::new(self.settings.filter, self.settings.filter_radius);
+    self.is_denoised = self.settings.denoise;
+
+    if self.debug_mode != self.settings.debug_mode {
+      self.debug_mode = self.settings.debug_mode;
+      self.is_view_changed = true;
+    }
+
+    // the screenshot being rendered keeps its own denoiser, the full lighting and the spectral mode
+    if !self.is_preview() {
+      if self.render.spectral != self.settings.spectral {
+        self.render.spectral = self.settings.spectral;
+        self.is_view_changed = true;
+      }
+
+      self.render.debug_mode = self.debug_mode;
+      self.render.set_denoiser(self.get_viewer_denoiser());
+    }
+    self.motion_dyn_samples = self.settings.motion_min_samples;
+    self.settings_version += 1;
+  }
+
+  // debug shading is never denoised, it has no noise and no first hit to guide the denoiser
+  fn get_viewer_denoiser(&self) -> Option<Denoiser> {
+    if self.is_denoised && self.debug_mode.is_none() { Some(Denoiser::default()) } else { None }
+  }
+
+  fn check_settings_reload(&mut self) {
+    if self.settings_check_time.elapsed().as_millis() < Config::SETTINGS_RELOAD_INTERVAL as u128 {
+      return;
+    }
+
+    self.settings_check_time = Instant::now();
+    let modified = get_modified_time(&self.settings_path);
+
+    if modified.is_none() || modified == self.settings_modified {
+      return;
+    }
+
+    self.settings_modified = modified;
+
+    match Settings::load_from_file(&self.settings_path) {
+      Ok(settings) => {
+        println!("Settings reloaded");
+        self.settings = settings;
+        self.settings_error = None;
+        self.apply_settings();
+      }
+      Err(err) => {
+        println!("Settings reload failed: {:#}", err);
+        self.settings_error = Some(format!("{:#}", err));
+      }
+    }
+  }
+
+  fn watch_scene_files(&mut self) {
+    let mut paths = vec![self.scene_path.clone()];
+    paths.extend(self.scene_desc.get_texture_names().iter().map(|name| self.root_path.join(name)));
+
+    self.scene_files = paths.into_iter().map(|path| {
+      let modified = get_modified_time(&path);
+      (path, modified)
+    }).collect();
+  }
+
+  // the changed textures are dropped from the cache and the scene is rebuilt, the camera pose is kept
+  fn check_scene_reload(&mut self) {
+    if self.scene_check_time.elapsed().as_millis() < Config::SCENE_RELOAD_INTERVAL as u128 {
+      return;
+    }
+
+    self.scene_check_time = Instant::now();
+    let changed_paths: Vec<PathBuf> = self.scene_files.iter()
+      .filter(|(path, modified)| get_modified_time(path) != *modified)
+      .map(|(path, _)| path.clone())
+      .collect();
+
+    if changed_paths.is_empty() {
+      return;
+    }
+
+    let root_path = &self.root_path;
+    self.textures.retain(|name, _| !changed_paths.contains(&root_path.join(name)));
+    // the picked object index may point past the objects of the reloaded scene
+    self.picked = None;
+
+    if changed_paths.contains(&self.scene_path) {
+      // the new description replaces the old one only together with its scene, so they never disagree
+      let result = SceneDesc::load_from_file(&self.scene_path)
+        .and_then(|scene_desc| scene_desc.build(&self.root_path, &mut self.textures).map(|scene| (scene_desc, scene)));
+
+      match result {
+        Ok((scene_desc, scene)) => {
+          self.scene_desc = scene_desc;
+          self.set_scene(scene);
+        }
+        Err(err) => {
+          println!("Scene reload failed: {:#}", err);
+          self.scene_error = Some(format!("{:#}", err));
+          // the broken file is loaded again only after the next change
+          self.watch_scene_files();
+          return;
+        }
+      }
+    } else {
+      self.rebuild_scene();
+    }
+
+    println!("Scene reloaded");
+    self.watch_scene_files();
+  }
+
   pub fn pulse(&mut self) -> Result<bool> {
     assert_ne!(self.render.image_width, 0);
     assert_ne!(self.render.image_height, 0);
@@ -135,10 +354,24 @@ impl App {
     match self.state {
       State::New |
       State::ScreenshotResolutionSelection |
-      State::ScreenshotSamplingSelection => {
+      State::ScreenshotSamplingSelection |
+      State::ScreenshotCustomEntry |
+      State::ScreenshotResumeQuery => {
         std::thread::sleep(Duration::from_millis(10));
       }
-      State::CameraControl => {
+      State::ScreenshotRenderPaused => {
+        std::thread::sleep(Duration::from_millis(10));
+        return Ok(self.take_preview_update());
+      }
+      State::CameraControl |
+      State::ObjectEdit => {
+        self.check_settings_reload();
+
+        // reloaded scene would lose the object being edited
+        if self.state == State::CameraControl {
+          self.check_scene_reload();
+        }
+
         self.proceed_control(elapsed);
         return self.render_image();
       }
@@ -152,12 +385,17 @@ impl App {
 
         if is_complete {
           self.set_state(State::ScreenshotRenderSave);
+        } else if self.settings.checkpoint_interval > 0 &&
+          self.checkpoint_time.elapsed().as_secs() >= self.settings.checkpoint_interval as u64
+        {
+          self.save_checkpoint();
         }
 
-        return Ok(is_complete);
+        return Ok(is_complete || self.take_preview_update());
       }
       State::ScreenshotRenderSave => {
         self.screenshot_render_save()?;
+        self.remove_checkpoint();
         self.set_state(State::ScreenshotRenderEnd);
       }
       State::ScreenshotRenderEnd => {
@@ -172,23 +410,39 @@ impl App {
   pub fn render_image(&mut self) -> Result<bool> {
     let is_complete;
 
-    let in_motion = self.control_flags != 0 || self.render.camera.is_in_motion();
+    let in_motion = self.control_flags != 0 ||
+      self.is_view_changed ||
+      self.analog_control.is_active() ||
+      self.render.camera.is_in_motion();
 
     if self.render.is_complete || (in_motion && self.motion_dyn_samples != self.prev_samples) {
       if in_motion {
-        if self.frame_time > Config::MAX_MOTION_FRAME_TIME {
-          self.motion_dyn_samples = i32::max(self.motion_dyn_samples - 1, Config::MOTION_MAX_SAMPLES);
-        } else if self.frame_time < Config::MIN_MOTION_FRAME_TIME {
-          self.motion_dyn_samples = i32::min(self.motion_dyn_samples + 1, Config::MOTION_MIN_SAMPLES);
+        if self.frame_time > self.settings.max_motion_frame_time {
+          self.motion_dyn_samples = i32::max(self.motion_dyn_samples - 1, self.settings.motion_max_samples);
+        } else if self.frame_time < self.settings.min_motion_frame_time {
+          self.motion_dyn_samples = i32::min(self.motion_dyn_samples + 1, self.settings.motion_min_samples);
         }
       }
 
-      let reflections = if in_motion || self.prev_in_motion { Config::MOTION_REFLECTIONS } else { Config::STATIC_REFLECTIONS };
-      let samples = if in_motion || self.prev_in_motion { self.motion_dyn_samples } else { Config::STATIC_SAMPLES };
-      self.render.begin_render(reflections, samples, !(in_motion || self.prev_in_motion));
+      let reflections = if in_motion || self.prev_in_motion { self.settings.motion_reflections } else { self.settings.static_reflections };
+      let samples = if in_motion || self.prev_in_motion { self.motion_dyn_samples } else { self.settings.static_samples };
+      let is_additive = !(in_motion || self.prev_in_motion);
+
+      self.render.adaptive = if is_additive && self.settings.adaptive_threshold > 0.0 {
+        Some(AdaptiveSampling {
+          threshold: self.settings.adaptive_threshold,
+          min_samples: self.settings.adaptive_min_samples,
+          max_samples: self.settings.adaptive_max_samples,
+        })
+      } else {
+        None
+      };
+
+      self.render.begin_render(reflections, samples, is_additive);
       self.render_chunk_in_pixels = 1;
       self.prev_samples = samples;
       self.prev_in_motion = in_motion;
+      self.is_view_changed = false;
     }
 
     let counter = Instant::now();
@@ -198,9 +452,9 @@ impl App {
     self.frame_time_accumulator += render_time.as_nanos() as f32 / 1_000_000_000.0;
 
     if !is_complete {
-      if render_time_ms < Config::MIN_CHUNK_RENDER_TIME {
+      if render_time_ms < self.settings.min_chunk_render_time {
         self.render_chunk_in_pixels = u32::min(self.render_chunk_in_pixels * 2, self.render.image_height * self.render.image_width);
-      } else if render_time_ms > Config::MAX_CHUNK_RENDER_TIME {
+      } else if render_time_ms > self.settings.max_chunk_render_time {
         self.render_chunk_in_pixels = u32::max(self.render_chunk_in_pixels / 2, 1);
       }
     } else {
@@ -213,11 +467,132 @@ impl App {
 
   fn proceed_control(&mut self, elapsed: Duration) {
     let time_passed_sec = elapsed.as_nanos() as f32 / 1_000_000_000.0;
-    self.render.camera.proceed_control(self.control_flags, time_passed_sec);
+    self.render.camera.proceed_control(self.control_flags, &self.analog_control, time_passed_sec);
+
+    let (yaw_delta, pitch_delta) = self.mouse_turn;
+
+    if yaw_delta != 0.0 || pitch_delta != 0.0 {
+      self.render.camera.turn(yaw_delta, pitch_delta);
+      self.mouse_turn = (0.0, 0.0);
+      self.is_view_changed = true;
+    }
+  }
+
+  pub fn is_mouse_look(&self) -> bool {
+    self.mouse_look && self.state == State::CameraControl
+  }
+
+  pub fn handle_mouse_motion(&mut self, x_rel: i32, y_rel: i32) {
+    if self.is_mouse_look() {
+      self.mouse_turn.0 += x_rel as f32 * self.settings.mouse_look_sensitivity;
+      self.mouse_turn.1 -= y_rel as f32 * self.settings.mouse_look_sensitivity;
+    }
+  }
+
+  // the cursor is hidden in the mouse look mode, so the crosshair in the image center is picked
+  pub fn handle_mouse_click(&mut self, x: i32, y: i32) {
+    if self.state != State::CameraControl || self.window_width == 0 || self.window_height == 0 {
+      return;
+    }
+
+    let (image_width, image_height) = self.get_render_image_size();
+
+    let (image_x, image_y) = if self.mouse_look {
+      (image_width / 2, image_height / 2)
+    } else {
+      let image_x = (x.max(0) as u64 * image_width as u64 / self.window_width as u64) as u32;
+      let image_y = (y.max(0) as u64 * image_height as u64 / self.window_height as u64) as u32;
+      // the image rows go from the bottom of the window
+      (u32::min(image_x, image_width - 1), image_height - 1 - u32::min(image_y, image_height - 1))
+    };
+
+    self.pick(image_x, image_y);
+  }
+
+  fn pick(&mut self, x: u32, y: u32) {
+    self.picked = match self.render.pick(x, y) {
+      Ok(picked) => picked,
+      Err(err) => {
+        println!("Failed to pick object: {:#}", err);
+        None
+      }
+    };
+  }
+
+  pub fn handle_mouse_wheel(&mut self, y: i32) {
+    if self.state == State::CameraControl && y != 0 {
+      self.render.camera.zoom(f32::powi(self.settings.mouse_wheel_zoom_factor, y));
+      self.is_view_changed = true;
+    }
+  }
+
+  // gamepad axis value is expected in range [-1.0, 1.0] for sticks and [0.0, 1.0] for triggers
+  pub fn handle_gamepad_axis(&mut self, axis: GamepadAxis, value: f32) {
+    let value = if value.abs() < self.settings.gamepad_dead_zone {
+      0.0
+    } else {
+      (value - value.signum() * self.settings.gamepad_dead_zone) / (1.0 - self.settings.gamepad_dead_zone)
+    };
+
+    match axis {
+      GamepadAxis::LeftX => self.analog_control.shift_rl = value,
+      GamepadAxis::LeftY => self.analog_control.shift_fb = -value,
+      GamepadAxis::RightX => self.analog_control.turn_rl = value,
+      GamepadAxis::RightY => self.analog_control.turn_ud = -value,
+      GamepadAxis::TriggerLeft => self.gamepad_triggers.0 = value,
+      GamepadAxis::TriggerRight => self.gamepad_triggers.1 = value,
+    }
+
+    self.analog_control.shift_ud = self.gamepad_triggers.1 - self.gamepad_triggers.0;
   }
 
   pub fn get_render_image_pixel(&self, x: u32, y: u32) -> [u8; 3] {
-    self.render.get_pixel(x, y).rgb()
+    if self.show_sample_heatmap {
+      self.render.get_sample_heatmap(x, y).rgb()
+    } else {
+      self.render.get_pixel(x, y).rgb()
+    }
+  }
+
+  pub fn is_preview(&self) -> bool {
+    matches!(
+      self.state,
+      State::ScreenshotRenderProceed |
+      State::ScreenshotRenderCancelRequested |
+      State::ScreenshotRenderPaused
+    )
+  }
+
+  pub fn get_preview_pixel(&self, x: u32, y: u32, view_width: u32, view_height: u32) -> [u8; 3] {
+    self.preview.get_pixel(&self.render, x, y, view_width, view_height)
+  }
+
+  // preview of the screenshot render is refreshed periodically or right after the view changes
+  fn take_preview_update(&mut self) -> bool {
+    if self.is_preview_changed || self.preview_time.elapsed().as_millis() >= Config::PREVIEW_UPDATE_INTERVAL as u128 {
+      self.is_preview_changed = false;
+      self.preview_time = Instant::now();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn handle_preview_key(&mut self, key: KeyCode) {
+    let (view_width, view_height) = (self.window_width, self.window_height);
+    const STEP: f32 = Config::PREVIEW_PAN_STEP;
+
+    match key {
+      KeyCode::KeyZ => self.preview.toggle_zoom(&self.render, view_width, view_height),
+      KeyCode::KeyH => self.preview.is_heatmap = !self.preview.is_heatmap,
+      KeyCode::KeyLeft => self.preview.pan(&self.render, -STEP, 0.0, view_width, view_height),
+      KeyCode::KeyRight => self.preview.pan(&self.render, STEP, 0.0, view_width, view_height),
+      KeyCode::KeyUp => self.preview.pan(&self.render, 0.0, -STEP, view_width, view_height),
+      KeyCode::KeyDown => self.preview.pan(&self.render, 0.0, STEP, view_width, view_height),
+      _ => return,
+    }
+
+    self.is_preview_changed = true;
   }
 
   pub fn get_render_image_size(&self) -> (u32, u32) {
@@ -234,10 +609,85 @@ impl App {
         self.set_state(State::CameraControl);
         self.render.resize_image(width, height);
       }
+      State::ObjectEdit |
+      State::ScreenshotResumeQuery => {
+        self.render.resize_image(width, height);
+      }
       _ => {}
     }
   }
 
+  // the inspected object is edited, the center of the image is inspected if there is none
+  fn begin_object_edit(&mut self) {
+    if self.picked.is_none() {
+      let (width, height) = self.get_render_image_size();
+      self.pick(width / 2, height / 2);
+    }
+
+    let object = self.picked.as_ref()
+      .and_then(|picked| self.scene_desc.objects.get(picked.object_index).map(|object| (picked.object_index, object)));
+
+    if let Some((index, object)) = object {
+      self.object_editor = Some(ObjectEditor::new(index, object.clone()));
+      // the cursor keys select the values now, so the camera must not keep turning
+      self.control_flags = 0;
+      self.set_state(State::ObjectEdit);
+    }
+  }
+
+  fn change_edited_object(&mut self, is_increase: bool) {
+    let editor = self.object_editor.as_ref().unwrap();
+    editor.change(&mut self.scene_desc.objects[editor.index], is_increase);
+    self.rebuild_scene();
+  }
+
+  fn end_object_edit(&mut self, is_applied: bool) {
+    let editor = self.object_editor.take().unwrap();
+
+    if !is_applied {
+      self.scene_desc.objects[editor.index] = editor.original;
+      self.rebuild_scene();
+    }
+
+    // inspected values are outdated after the edit
+    self.picked = None;
+    self.set_state(State::CameraControl);
+  }
+
+  // the object indices of the rebuilt scene are the same as in the scene description
+  fn rebuild_scene(&mut self) {
+    match self.scene_desc.build(&self.root_path, &mut self.textures) {
+      Ok(scene) => self.set_scene(scene),
+      Err(err) => {
+        println!("Scene update failed: {:#}", err);
+        self.scene_error = Some(format!("{:#}", err));
+      }
+    }
+  }
+
+  fn set_scene(&mut self, scene: Scene) {
+    self.render.scene = scene;
+    self.scene_error = None;
+    self.is_view_changed = true;
+  }
+
+  fn save_scene(&mut self) {
+    self.scene_desc.set_camera(&self.render.camera);
+
+    match self.scene_desc.save_to_file(&self.scene_path) {
+      Ok(()) => {
+        println!("Scene saved to {}", self.scene_path.display());
+        self.scene_error = None;
+        // own changes need no reload
+        self.watch_scene_files();
+      }
+      Err(err) => {
+        println!("Scene save failed: {:#}", err);
+        self.scene_error = Some(format!("{:#}", err));
+      }
+    }
+  }
+
   fn set_state(&mut self, state: State) {
     self.state = state;
   }
@@ -257,6 +707,29 @@ impl App {
         KeyCode::KeyD => mask = SHIFT_RIGHT_MASK,
         KeyCode::KeySpace => mask = SHIFT_UP_MASK,
         KeyCode::KeyControl => mask = SHIFT_DOWN_MASK,
+        KeyCode::KeyM if is_pressed => self.mouse_look = !self.mouse_look,
+        KeyCode::KeyH if is_pressed => self.show_sample_heatmap = !self.show_sample_heatmap,
+        KeyCode::KeyF3 if is_pressed => {
+          self.is_denoised = !self.is_denoised;
+          self.render.set_denoiser(self.get_viewer_denoiser());
+        }
+        KeyCode::KeyF4 if is_pressed => {
+          self.debug_mode = DebugMode::next(self.debug_mode);
+          self.render.debug_mode = self.debug_mode;
+          self.render.set_denoiser(self.get_viewer_denoiser());
+          self.is_view_changed = true;
+        }
+        KeyCode::KeyI if is_pressed => {
+          if self.picked.is_some() {
+            self.picked = None;
+          } else {
+            let (width, height) = self.get_render_image_size();
+            self.pick(width / 2, height / 2);
+          }
+        }
+        KeyCode::KeyE if is_pressed => self.begin_object_edit(),
+        KeyCode::KeyF5 if is_pressed => self.save_scene(),
+        KeyCode::KeyEscape if is_pressed => self.mouse_look = false,
         KeyCode::KeyF2 => {
           if is_pressed {
             self.set_state(State::ScreenshotResolutionSelection);
@@ -266,6 +739,17 @@ impl App {
       }
 
       self.control_flags = if is_pressed { self.control_flags | mask } else { self.control_flags & !mask };
+    } else if self.state == State::ObjectEdit && is_pressed {
+      match key {
+        KeyCode::KeyUp => self.object_editor.as_mut().unwrap().prev_field(),
+        KeyCode::KeyDown | KeyCode::KeyTab => self.object_editor.as_mut().unwrap().next_field(),
+        KeyCode::KeyLeft => self.change_edited_object(false),
+        KeyCode::KeyRight => self.change_edited_object(true),
+        KeyCode::KeyEnter => self.end_object_edit(true),
+        KeyCode::KeyEscape => self.end_object_edit(false),
+        KeyCode::KeyF5 => self.save_scene(),
+        _ => {}
+      }
     } else if self.state == State::ScreenshotResolutionSelection && is_pressed {
       let mut index: i32 = -1;
 
@@ -279,13 +763,21 @@ impl App {
         KeyCode::Key7 => index = 6,
         KeyCode::Key8 => index = 7,
         KeyCode::Key9 => index = 8,
+        KeyCode::KeyC => {
+          // previously entered values are kept to simplify the repeated screenshots
+          self.screenshot_form.error = None;
+          self.set_state(State::ScreenshotCustomEntry);
+        }
         KeyCode::KeyEscape => self.set_state(State::CameraControl),
         _ => {}
       }
 
-      if index >= 0 {
-        self.screenshot_width = RESOLUTIONS[index as usize].w;
-        self.screenshot_height = RESOLUTIONS[index as usize].h;
+      if let Some(resolution) = self.settings.resolutions.get(index as usize) {
+        self.screenshot_width = resolution.w;
+        self.screenshot_height = resolution.h;
+        self.screenshot_reflections = self.settings.screenshot_reflections;
+        self.screenshot_format = "bmp";
+        self.screenshot_custom_name.clear();
         self.set_state(State::ScreenshotSamplingSelection);
       }
     } else if self.state == State::ScreenshotSamplingSelection && is_pressed {
@@ -305,25 +797,101 @@ impl App {
         _ => {}
       }
 
-      if index >= 0 {
-        self.screenshot_samples = SUPERSAMPLE_RATES[index as usize].rate;
+      if let Some(ss_rate) = self.settings.supersample_rates.get(index as usize) {
+        self.screenshot_samples = ss_rate.rate;
         self.set_state(State::ScreenshotRenderBegin);
       }
+    } else if self.state == State::ScreenshotCustomEntry && is_pressed {
+      match key {
+        KeyCode::KeyTab => self.screenshot_form.next_field(),
+        KeyCode::KeySpace => self.screenshot_form.toggle(),
+        KeyCode::KeyBackspace => self.screenshot_form.erase(),
+        KeyCode::KeyEnter => {
+          match self.screenshot_form.validate(self.window_width, self.window_height) {
+            Ok(params) => {
+              self.screenshot_width = params.width;
+              self.screenshot_height = params.height;
+              self.screenshot_samples = params.samples;
+              self.screenshot_reflections = params.reflections;
+              self.screenshot_format = params.format;
+              self.screenshot_custom_name = params.file_name;
+              self.set_state(State::ScreenshotRenderBegin);
+            }
+            Err(err) => self.screenshot_form.error = Some(err.to_string()),
+          }
+        }
+        KeyCode::KeyEscape => self.set_state(State::CameraControl),
+        _ => {}
+      }
     } else if self.state == State::ScreenshotRenderProceed && is_pressed {
-      if key == KeyCode::KeyEscape {
-        self.set_state(State::ScreenshotRenderCancelRequested);
+      self.handle_preview_key(key);
+
+      match key {
+        KeyCode::KeyEscape => self.set_state(State::ScreenshotRenderCancelRequested),
+        KeyCode::KeyP => {
+          self.save_checkpoint();
+          self.set_state(State::ScreenshotRenderPaused);
+        }
+        _ => {}
+      }
+    } else if self.state == State::ScreenshotRenderPaused && is_pressed {
+      self.handle_preview_key(key);
+
+      match key {
+        KeyCode::KeyEscape => self.set_state(State::ScreenshotRenderCancelRequested),
+        KeyCode::KeyP => {
+          self.restart_screenshot_timer();
+          self.set_state(State::ScreenshotRenderProceed);
+        }
+        _ => {}
       }
     } else if self.state == State::ScreenshotRenderCancelRequested && is_pressed {
       match key {
-        KeyCode::KeyY => self.set_state(State::CameraControl),
-        KeyCode::KeyN => self.set_state(State::ScreenshotRenderProceed),
+        KeyCode::KeyY => {
+          self.remove_checkpoint();
+          self.set_state(State::ScreenshotRenderEnd);
+        }
+        KeyCode::KeyN => {
+          self.restart_screenshot_timer();
+          self.set_state(State::ScreenshotRenderProceed);
+        }
+        _ => {}
+      }
+    } else if self.state == State::ScreenshotResumeQuery && is_pressed {
+      match key {
+        KeyCode::KeyY => {
+          if let Err(err) = self.load_checkpoint() {
+            println!("Failed to resume screenshot render: {:#}", err);
+            self.checkpoint_error = Some(format!("{:#}", err));
+            self.render.resize_image(self.window_width, self.window_height);
+            self.set_state(State::CameraControl);
+          } else {
+            self.set_state(State::ScreenshotRenderProceed);
+          }
+        }
+        KeyCode::KeyN => {
+          self.remove_checkpoint();
+          self.set_state(State::CameraControl);
+        }
         _ => {}
       }
     }
   }
 
+  pub fn is_text_input(&self) -> bool {
+    self.state == State::ScreenshotCustomEntry && self.screenshot_form.is_text_field()
+  }
+
+  pub fn handle_text_input(&mut self, text: &str) {
+    if self.is_text_input() {
+      self.screenshot_form.input_text(text);
+    }
+  }
+
   pub fn get_current_screen_text(&self) -> Vec<String> {
     let mut screen_text = Vec::new();
+    // the help lines show the keys bound in the settings
+    let keys = |key: KeyCode| self.settings.get_key_names(key);
 
     match &self.state {
       State::CameraControl => {
@@ -337,52 +905,135 @@ impl App {
         }
 
         screen_text.push(format!("Blended frames : {}", self.render.additive_counter));
+        screen_text.push(format!("Sampler : {}", self.render.get_sampler().name()));
+        screen_text.push(format!("Denoiser : {}", if self.render.get_denoiser().is_some() { "on" } else { "off" }));
+
+        if let Some(debug_mode) = self.debug_mode {
+          screen_text.push(format!("Debug shading : {}", debug_mode.name()));
+        }
+
+        if let Some(picked) = &self.picked {
+          screen_text.push(String::from(" "));
+          screen_text.extend(get_pick_text(picked));
+        }
+
+        screen_text.push(String::from(" "));
+        screen_text.push(format!("{}, {}, {}, {} : move", keys(KeyCode::KeyW), keys(KeyCode::KeyS), keys(KeyCode::KeyA),
+                                 keys(KeyCode::KeyD)));
+        screen_text.push(format!("{}, {}, {}, {} : turn", keys(KeyCode::KeyLeft), keys(KeyCode::KeyRight), keys(KeyCode::KeyUp),
+                                 keys(KeyCode::KeyDown)));
+        screen_text.push(format!("{} : ascent", keys(KeyCode::KeySpace)));
+        screen_text.push(format!("{} : descent", keys(KeyCode::KeyControl)));
+        screen_text.push(format!("{} : toggle mouse look", keys(KeyCode::KeyM)));
+        screen_text.push(String::from("Mouse wheel : zoom"));
+        screen_text.push(format!("{} : sample count heatmap", keys(KeyCode::KeyH)));
+        screen_text.push(format!("{} : toggle denoiser", keys(KeyCode::KeyF3)));
+        screen_text.push(format!("{} : cycle debug shading", keys(KeyCode::KeyF4)));
+        screen_text.push(format!("{} / click : inspect object", keys(KeyCode::KeyI)));
+        screen_text.push(format!("{} : edit inspected object", keys(KeyCode::KeyE)));
+        screen_text.push(format!("{} : save scene", keys(KeyCode::KeyF5)));
+        screen_text.push(String::from(" "));
+        screen_text.push(format!("{} : save screenshot", keys(KeyCode::KeyF2)));
+
+        if let Some(checkpoint_error) = &self.checkpoint_error {
+          screen_text.push(String::from(" "));
+          screen_text.push(format!("Screenshot resume failed: {}", checkpoint_error));
+        }
+
+        if let Some(settings_error) = &self.settings_error {
+          screen_text.push(String::from(" "));
+          screen_text.push(String::from("Settings reload failed:"));
+          screen_text.push(settings_error.clone());
+        }
+
+        if let Some(scene_error) = &self.scene_error {
+          screen_text.push(String::from(" "));
+          screen_text.push(format!("Scene update failed: {}", scene_error));
+        }
+      }
+      State::ObjectEdit => {
+        let editor = self.object_editor.as_ref().unwrap();
+        let object = &self.scene_desc.objects[editor.index];
+        screen_text.push(format!("Editing object : #{} {}", editor.index, object.get_type_name()));
         screen_text.push(String::from(" "));
-        screen_text.push(String::from("WSAD : move"));
-        screen_text.push(String::from("Cursor keys: turn"));
-        screen_text.push(String::from("Space : ascent"));
-        screen_text.push(String::from("Ctrl : descent"));
+        screen_text.extend(editor.get_lines(object));
         screen_text.push(String::from(" "));
-        screen_text.push(String::from("F2 : save screenshot"));
+        screen_text.push(format!("{}, {}, {} : select value", keys(KeyCode::KeyUp), keys(KeyCode::KeyDown),
+                                 keys(KeyCode::KeyTab)));
+        screen_text.push(format!("{}, {} : change value", keys(KeyCode::KeyLeft), keys(KeyCode::KeyRight)));
+        screen_text.push(format!("{} : apply", keys(KeyCode::KeyEnter)));
+        screen_text.push(format!("{} : revert", keys(KeyCode::KeyEscape)));
+        screen_text.push(format!("{} : save scene", keys(KeyCode::KeyF5)));
+
+        if let Some(scene_error) = &self.scene_error {
+          screen_text.push(String::from(" "));
+          screen_text.push(format!("Scene update failed: {}", scene_error));
+        }
       }
       State::ScreenshotResolutionSelection => {
-        screen_text.push(String::from("Select screenshot resolution (keys 1-9)"));
+        screen_text.push(String::from("Select screenshot resolution"));
         screen_text.push(String::from(" "));
 
-        RESOLUTIONS.iter().enumerate().for_each(|(i, res)| {
-          screen_text.push(format!("{} : {}x{} {}", i + 1, res.w, res.h, res.tip));
+        self.settings.resolutions.iter().zip(SELECT_KEYS.iter()).for_each(|(res, &key)| {
+          screen_text.push(format!("{} : {}x{} {}", keys(key), res.w, res.h, res.tip));
         });
 
+        screen_text.push(format!("{} : custom resolution and sampling", keys(KeyCode::KeyC)));
+        screen_text.push(String::from(" "));
+        screen_text.push(format!("{} : cancel", keys(KeyCode::KeyEscape)));
+      }
+      State::ScreenshotCustomEntry => {
+        screen_text.push(String::from("Enter screenshot parameters"));
         screen_text.push(String::from(" "));
-        screen_text.push(String::from("ESC : cancel"));
+        screen_text.extend(self.screenshot_form.get_lines(self.window_width, self.window_height));
+        screen_text.push(String::from(" "));
+
+        if let Some(error) = &self.screenshot_form.error {
+          screen_text.push(format!("Invalid input: {}", error));
+          screen_text.push(String::from(" "));
+        }
+
+        screen_text.push(format!("{} : next field", keys(KeyCode::KeyTab)));
+        screen_text.push(format!("{} : toggle option", keys(KeyCode::KeySpace)));
+        screen_text.push(format!("{} : start rendering", keys(KeyCode::KeyEnter)));
+        screen_text.push(format!("{} : cancel", keys(KeyCode::KeyEscape)));
       }
       State::ScreenshotSamplingSelection => {
-        screen_text.push(String::from("Select supersampling rate (keys 1-9)"));
+        screen_text.push(String::from("Select supersampling rate"));
         screen_text.push(String::from(" "));
 
-        SUPERSAMPLE_RATES.iter().enumerate().for_each(|(i, ss)| {
-          screen_text.push(format!("{} : {}x{} {}", i + 1, ss.rate, ss.rate, ss.tip));
+        self.settings.supersample_rates.iter().zip(SELECT_KEYS.iter()).for_each(|(ss, &key)| {
+          screen_text.push(format!("{} : {}x{} {}", keys(key), ss.rate, ss.rate, ss.tip));
         });
 
         screen_text.push(String::from(" "));
-        screen_text.push(String::from("ESC : cancel"));
+        screen_text.push(format!("{} : cancel", keys(KeyCode::KeyEscape)));
       }
       State::ScreenshotRenderBegin |
       State::ScreenshotRenderProceed |
       State::ScreenshotRenderSave |
       State::ScreenshotRenderEnd |
-      State::ScreenshotRenderCancelRequested => {
+      State::ScreenshotRenderCancelRequested |
+      State::ScreenshotRenderPaused => {
         screen_text.push(String::from("Saving screenshot:"));
         screen_text.push(self.screenshot_file_name.file_name().unwrap().to_str().unwrap().to_owned());
         screen_text.push(format!("Resolution: {}x{}", self.screenshot_width, self.screenshot_height));
         screen_text.push(format!("SSAA: {}x{}", self.screenshot_samples, self.screenshot_samples));
+        screen_text.push(format!("Reflections: {}", self.screenshot_reflections));
+
+        if self.render.is_aov_enabled() {
+          let names: Vec<&str> = self.settings.screenshot_aovs.iter().map(|aov| aov.name()).collect();
+          screen_text.push(format!("AOVs: {}", names.join(", ")));
+        }
+
         screen_text.push(String::from(""));
         screen_text.push(format!("Progress: {:.2} %", self.screenshot_progress));
 
         if self.screenshot_progress > VERY_SMALL_NUMBER {
           if let Some(screenshot_start_ticks) = self.screenshot_start_ticks {
             let time_passed = Instant::now().duration_since(screenshot_start_ticks);
-            let time_left = (time_passed.as_secs_f32() * (100.0 / self.screenshot_progress - 1.0)) as u32;
+            let progress_passed = f32::max(self.screenshot_progress - self.screenshot_start_progress, VERY_SMALL_NUMBER);
+            let time_left = (time_passed.as_secs_f32() * (100.0 - self.screenshot_progress) / progress_passed) as u32;
 
             let hr = time_left / 3600;
             let time_left = time_left % 3600;
@@ -395,12 +1046,39 @@ impl App {
           }
         }
 
+        if let Some(checkpoint_error) = &self.checkpoint_error {
+          screen_text.push(format!("Checkpoint failed: {}", checkpoint_error));
+          screen_text.push(String::from(""));
+        }
+
         if self.state == State::ScreenshotRenderCancelRequested {
-          screen_text.push(String::from("Do you want to cancel ? ( Y / N ) "));
+          screen_text.push(format!("Do you want to cancel ? ( {} / {} ) ", keys(KeyCode::KeyY), keys(KeyCode::KeyN)));
+        } else if self.state == State::ScreenshotRenderPaused {
+          screen_text.push(String::from("Paused, progress is saved to the checkpoint"));
+          screen_text.push(format!("Press {} to resume", keys(KeyCode::KeyP)));
+          screen_text.push(format!("Press {} to cancel", keys(KeyCode::KeyEscape)));
         } else {
-          screen_text.push(String::from("Press ESC to cancel"));
+          screen_text.push(format!("Press {} to pause", keys(KeyCode::KeyP)));
+          screen_text.push(format!("Press {} to cancel", keys(KeyCode::KeyEscape)));
+        }
+
+        if self.state != State::ScreenshotRenderCancelRequested {
+          if self.preview.is_zoomed {
+            screen_text.push(format!("{} : fit preview to window, {}, {}, {}, {} : pan", keys(KeyCode::KeyZ),
+                                     keys(KeyCode::KeyLeft), keys(KeyCode::KeyRight), keys(KeyCode::KeyUp),
+                                     keys(KeyCode::KeyDown)));
+          } else {
+            screen_text.push(format!("{} : zoom preview to 1:1", keys(KeyCode::KeyZ)));
+          }
+
+          screen_text.push(format!("{} : sample count heatmap", keys(KeyCode::KeyH)));
         }
       }
+      State::ScreenshotResumeQuery => {
+        screen_text.push(String::from("Found unfinished screenshot render"));
+        screen_text.push(String::from(" "));
+        screen_text.push(format!("Do you want to resume it ? ( {} / {} ) ", keys(KeyCode::KeyY), keys(KeyCode::KeyN)));
+      }
       _ => {
         panic!();
       }
@@ -410,14 +1088,38 @@ impl App {
   }
 
   fn screenshot_render_begin(&mut self) -> Result<()> {
-    let system_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
-    let name = format!("screenshot_{:08X}.bmp", system_time);
+    let name = if self.screenshot_custom_name.is_empty() {
+      let system_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+      format!("screenshot_{:08X}.{}", system_time, self.screenshot_format)
+    } else if self.screenshot_custom_name.ends_with(&format!(".{}", self.screenshot_format)) {
+      self.screenshot_custom_name.clone()
+    } else {
+      format!("{}.{}", self.screenshot_custom_name, self.screenshot_format)
+    };
+
     self.screenshot_file_name = std::env::current_exe()?.parent().unwrap().join(name);
 
+    self.render.set_aov_enabled(!self.settings.screenshot_aovs.is_empty());
+    self.render.debug_mode = None;
     self.render.resize_image(self.screenshot_width, self.screenshot_height);
-    self.screenshot_start_ticks = Some(Instant::now());
-    self.render.begin_render(Config::SCREENSHOT_REFLECTIONS, self.screenshot_samples, false);
+    // stop-at-noise-level mode, every pixel gets at least the selected supersampling
+    self.render.adaptive = if self.settings.screenshot_noise_level > 0.0 {
+      Some(AdaptiveSampling {
+        threshold: self.settings.screenshot_noise_level,
+        min_samples: self.settings.adaptive_min_samples,
+        max_samples: self.settings.screenshot_max_samples,
+      })
+    } else {
+      None
+    };
+
+    self.render.set_denoiser(if self.settings.screenshot_denoise { Some(Denoiser::default()) } else { None });
+    self.render.begin_render(self.screenshot_reflections, self.screenshot_samples, false);
     self.render_chunk_in_pixels = 1;
+    self.checkpoint_error = None;
+    self.checkpoint_time = Instant::now();
+    self.preview = Preview::default();
+    self.restart_screenshot_timer();
 
     Ok(())
   }
@@ -427,9 +1129,9 @@ impl App {
     let is_complete = self.render.render(self.render_chunk_in_pixels)?;
     let elapsed_ms = now.elapsed().as_millis() as u32;
 
-    if elapsed_ms < Config::MIN_CHUNK_RENDER_TIME {
+    if elapsed_ms < self.settings.min_chunk_render_time {
       self.render_chunk_in_pixels = u32::min(self.render_chunk_in_pixels * 2, self.render.image_height * self.render.image_width);
-    } else if elapsed_ms > Config::MAX_CHUNK_RENDER_TIME {
+    } else if elapsed_ms > self.settings.max_chunk_render_time {
       self.render_chunk_in_pixels = u32::max(self.render_chunk_in_pixels / 2, 1);
     }
 
@@ -438,8 +1140,89 @@ impl App {
     Ok(is_complete)
   }
 
+  // estimation of the time left is based on the progress made since the last (re)start
+  fn restart_screenshot_timer(&mut self) {
+    self.screenshot_progress = self.render.get_progress();
+    self.screenshot_start_progress = self.screenshot_progress;
+    self.screenshot_start_ticks = Some(Instant::now());
+  }
+
+  // checkpoint failure should not interrupt the render, so the error is only reported
+  fn save_checkpoint(&mut self) {
+    self.checkpoint_time = Instant::now();
+
+    if let Err(err) = self.write_checkpoint() {
+      println!("Failed to save checkpoint: {:#}", err);
+      self.checkpoint_error = Some(format!("{:#}", err));
+    } else {
+      self.checkpoint_error = None;
+    }
+  }
+
+  fn write_checkpoint(&self) -> Result<()> {
+    // writing to a temporary file first keeps the previous checkpoint intact if the app crashes while saving
+    let temp_path = self.checkpoint_path.with_extension("tmp");
+    let mut stream = BufWriter::new(File::create(&temp_path)?);
+    let file_name = self.screenshot_file_name.to_str().context("Invalid screenshot file name")?;
+    stream.write_u32::<LittleEndian>(file_name.len() as u32)?;
+    stream.write_all(file_name.as_bytes())?;
+    self.render.save_state(&mut stream)?;
+    drop(stream);
+    std::fs::rename(&temp_path, &self.checkpoint_path)?;
+
+    Ok(())
+  }
+
+  fn load_checkpoint(&mut self) -> Result<()> {
+    let mut stream = BufReader::new(File::open(&self.checkpoint_path)?);
+    let name_length = stream.read_u32::<LittleEndian>()? as usize;
+
+    if name_length > MAX_CHECKPOINT_NAME_LENGTH {
+      return Err(Error::msg("Invalid screenshot file name"));
+    }
+
+    let mut name_bytes = vec![0u8; name_length];
+    stream.read_exact(&mut name_bytes)?;
+    let file_name = String::from_utf8(name_bytes).context("Invalid screenshot file name")?;
+    self.render.load_state(&mut stream).context("Failed to load checkpoint")?;
+
+    self.screenshot_file_name = PathBuf::from(file_name);
+    self.screenshot_width = self.render.image_width;
+    self.screenshot_height = self.render.image_height;
+    self.screenshot_samples = self.render.get_samples();
+    self.screenshot_reflections = self.render.get_max_reflections();
+    self.render_chunk_in_pixels = 1;
+    self.checkpoint_error = None;
+    self.checkpoint_time = Instant::now();
+    self.preview = Preview::default();
+    self.restart_screenshot_timer();
+
+    Ok(())
+  }
+
+  fn remove_checkpoint(&mut self) {
+    if self.checkpoint_path.exists() {
+      if let Err(err) = std::fs::remove_file(&self.checkpoint_path) {
+        println!("Failed to remove checkpoint: {}", err);
+      }
+    }
+  }
+
+  // AOV images get the AOV name appended to the screenshot file name
   fn screenshot_render_save(&mut self) -> Result<()> {
-    self.render.to_texture().save_to_file(&self.screenshot_file_name)
+    self.render.to_texture().save_to_file(&self.screenshot_file_name)?;
+
+    for &aov in &self.settings.screenshot_aovs {
+      // the render resumed from a checkpoint may have no AOV buffer
+      if let Some(texture) = self.render.get_aov_texture(aov) {
+        let stem = self.screenshot_file_name.file_stem().context("Invalid screenshot file name")?.to_string_lossy();
+        let extension = self.screenshot_file_name.extension().context("Invalid screenshot file name")?.to_string_lossy();
+        let file_name = self.screenshot_file_name.with_file_name(format!("{}_{}.{}", stem, aov.name(), extension));
+        texture.save_to_file(&file_name)?;
+      }
+    }
+
+    Ok(())
   }
 
   fn screenshot_render_end(&mut self) -> Result<()> {
@@ -447,8 +1230,45 @@ impl App {
     self.screenshot_width = 0;
     self.screenshot_height = 0;
     self.screenshot_samples = 0;
+    self.screenshot_reflections = 0;
+    self.render.set_aov_enabled(false);
+    self.render.debug_mode = self.debug_mode;
+    // the screenshot resumed from a checkpoint may have been rendered in the other mode
+    self.render.spectral = self.settings.spectral;
     self.render.resize_image(self.window_width, self.window_height);
+    self.render.set_denoiser(self.get_viewer_denoiser());
 
     Ok(())
   }
-}
\ No newline at end of file
+}
+
+fn get_modified_time(path: &Path) -> Option<SystemTime> {
+  std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// the custom screenshot starts from the first resolution and supersampling of the settings
+fn new_screenshot_form(settings: &Settings) -> ScreenshotForm {
+  let (width, height) = settings.resolutions.first()
+    .map_or((Config::RESOLUTIONS[0].0, Config::RESOLUTIONS[0].1), |resolution| (resolution.w, resolution.h));
+  let samples = settings.supersample_rates.first().map_or(Config::SUPERSAMPLE_RATES[0].0, |ss_rate| ss_rate.rate);
+
+  ScreenshotForm::new(width, height, samples, settings.screenshot_reflections)
+}
+
+fn get_pick_text(picked: &PickInfo) -> Vec<String> {
+  let vector = |v: &Vector3| format!("({:.3}, {:.3}, {:.3})", v.x, v.y, v.z);
+  let color = |c: &Color| format!("({:.3}, {:.3}, {:.3})", c.r, c.g, c.b);
+  let material = &picked.material;
+
+  vec![
+    format!("Object : #{} {}", picked.object_index, picked.object_type),
+    format!("Position : {}", vector(&picked.position)),
+    format!("Normal : {}", vector(&picked.normal)),
+    format!("Distance : {:.3}", picked.distance),
+    format!("UV : ({:.3}, {:.3})", picked.uv.0, picked.uv.1),
+    format!("Material : #{} {} {}", picked.material_id, picked.material_name.as_deref().unwrap_or(""), material.kind.name()),
+    format!("Color : {}", color(&material.color)),
+    format!("Reflectivity : {:.3}", material.reflectivity),
+    format!("Transparency : {:.3}", material.transparency),
+  ]
+}
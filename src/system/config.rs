@@ -7,9 +7,109 @@ pub mod default {
   pub const MOTION_REFLECTIONS: u32 = 4;
   pub const SCREENSHOT_REFLECTIONS: u32 = 20;
 
+  pub const SEED: u64 = 0;
+  pub const SAMPLER: &str = "sobol";
+  pub const FILTER: &str = "box";
+  pub const FILTER_RADIUS: f32 = 0.5;
+  pub const DENOISE: bool = false;
+  // empty debug mode renders the full lighting
+  pub const DEBUG_MODE: &str = "";
+  // every path samples a wavelength, the dispersive materials split the light into colors
+  pub const SPECTRAL: bool = false;
+  pub const SCREENSHOT_DENOISE: bool = false;
+  // comma separated AOV names saved next to the screenshot
+  pub const SCREENSHOT_AOVS: &str = "";
+
+  // zero threshold disables the adaptive sampling
+  pub const ADAPTIVE_THRESHOLD: f32 = 0.0;
+  pub const ADAPTIVE_MIN_SAMPLES: u32 = 8;
+  pub const ADAPTIVE_MAX_SAMPLES: u32 = 1024;
+  pub const SCREENSHOT_NOISE_LEVEL: f32 = 0.0;
+  pub const SCREENSHOT_MAX_SAMPLES: u32 = 4096;
+
   pub const MIN_MOTION_FRAME_TIME: f32 = 0.010;
   pub const MAX_MOTION_FRAME_TIME: f32 = 0.020;
 
   pub const MIN_CHUNK_RENDER_TIME: u32 = 5;
   pub const MAX_CHUNK_RENDER_TIME: u32 = 20;
-}
\ No newline at end of file
+
+  pub const MOUSE_LOOK_SENSITIVITY: f32 = 0.003;
+  pub const MOUSE_WHEEL_ZOOM_FACTOR: f32 = 0.9;
+  pub const GAMEPAD_DEAD_ZONE: f32 = 0.15;
+
+  pub const SETTINGS_FILE_NAME: &str = "settings.ini";
+  pub const SETTINGS_RELOAD_INTERVAL: u32 = 1000;
+
+  pub const SCENE_FILE_NAME: &str = "scene.ini";
+  pub const SCENE_RELOAD_INTERVAL: u32 = 1000;
+
+  pub const CHECKPOINT_FILE_NAME: &str = "screenshot.checkpoint";
+  pub const CHECKPOINT_INTERVAL: u32 = 300;
+
+  pub const PREVIEW_UPDATE_INTERVAL: u32 = 250;
+  pub const PREVIEW_PAN_STEP: f32 = 0.25;
+
+  pub const RESOLUTIONS: [(u32, u32, &str); 9] = [
+    (800, 600, "(4:3)"),
+    (1024, 768, "(4:3)"),
+    (1280, 960, "(4:3)"),
+    (1280, 800, "(16:10)"),
+    (1680, 1050, "(16:10)"),
+    (1920, 1200, "(16:10)"),
+    (1280, 720, "(HD)"),
+    (1920, 1080, "(Full HD)"),
+    (7680, 4320, "(Super Hi Vision 16:9)"),
+  ];
+
+  pub const SUPERSAMPLE_RATES: [(i32, &str); 9] = [
+    (1, "(fast but rough)"),
+    (2, ""),
+    (4, ""),
+    (8, ""),
+    (16, ""),
+    (32, ""),
+    (64, ""),
+    (128, ""),
+    (256, "(slow but smooth)"),
+  ];
+
+  // key names are SDL key names, several keys for the same action are separated by comma
+  pub const KEY_BINDINGS: [(&str, &str); 36] = [
+    ("turn_left", "Left"),
+    ("turn_right", "Right"),
+    ("turn_up", "Up"),
+    ("turn_down", "Down"),
+    ("move_forward", "W"),
+    ("move_back", "S"),
+    ("move_left", "A"),
+    ("move_right", "D"),
+    ("ascent", "Space"),
+    ("descent", "Left Ctrl"),
+    ("mouse_look", "M"),
+    ("screenshot", "F2"),
+    ("select_1", "1"),
+    ("select_2", "2"),
+    ("select_3", "3"),
+    ("select_4", "4"),
+    ("select_5", "5"),
+    ("select_6", "6"),
+    ("select_7", "7"),
+    ("select_8", "8"),
+    ("select_9", "9"),
+    ("cancel", "Escape"),
+    ("yes", "Y"),
+    ("no", "N"),
+    ("custom_screenshot", "C"),
+    ("next_field", "Tab"),
+    ("erase", "Backspace"),
+    ("confirm", "Return, Keypad Enter"),
+    ("pause", "P"),
+    ("preview_zoom", "Z"),
+    ("sample_heatmap", "H"),
+    ("denoise", "F3"),
+    ("debug_mode", "F4"),
+    ("inspect", "I"),
+    ("edit_object", "E"),
+    ("save_scene", "F5"),
+  ];
+}
@@ -0,0 +1,10 @@
+#[derive(PartialEq, Clone, Copy)]
+pub enum GamepadAxis
+{
+  LeftX,
+  LeftY,
+  RightX,
+  RightY,
+  TriggerLeft,
+  TriggerRight,
+}
@@ -1,4 +1,4 @@
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum KeyCode
 {
   Key1,
@@ -13,6 +13,7 @@ pub enum KeyCode
   KeyF2,
   KeyY,
   KeyN,
+  KeyM,
   KeyW,
   KeyS,
   KeyA,
@@ -24,4 +25,60 @@ pub enum KeyCode
   KeySpace,
   KeyControl,
   KeyEscape,
-}
\ No newline at end of file
+  KeyC,
+  KeyTab,
+  KeyBackspace,
+  KeyEnter,
+  KeyP,
+  KeyZ,
+  KeyH,
+  KeyF3,
+  KeyF4,
+  KeyI,
+  KeyE,
+  KeyF5,
+}
+
+impl KeyCode {
+  pub fn from_action(action: &str) -> Option<KeyCode> {
+    match action {
+      "turn_left" => Some(KeyCode::KeyLeft),
+      "turn_right" => Some(KeyCode::KeyRight),
+      "turn_up" => Some(KeyCode::KeyUp),
+      "turn_down" => Some(KeyCode::KeyDown),
+      "move_forward" => Some(KeyCode::KeyW),
+      "move_back" => Some(KeyCode::KeyS),
+      "move_left" => Some(KeyCode::KeyA),
+      "move_right" => Some(KeyCode::KeyD),
+      "ascent" => Some(KeyCode::KeySpace),
+      "descent" => Some(KeyCode::KeyControl),
+      "mouse_look" => Some(KeyCode::KeyM),
+      "screenshot" => Some(KeyCode::KeyF2),
+      "select_1" => Some(KeyCode::Key1),
+      "select_2" => Some(KeyCode::Key2),
+      "select_3" => Some(KeyCode::Key3),
+      "select_4" => Some(KeyCode::Key4),
+      "select_5" => Some(KeyCode::Key5),
+      "select_6" => Some(KeyCode::Key6),
+      "select_7" => Some(KeyCode::Key7),
+      "select_8" => Some(KeyCode::Key8),
+      "select_9" => Some(KeyCode::Key9),
+      "cancel" => Some(KeyCode::KeyEscape),
+      "yes" => Some(KeyCode::KeyY),
+      "no" => Some(KeyCode::KeyN),
+      "custom_screenshot" => Some(KeyCode::KeyC),
+      "next_field" => Some(KeyCode::KeyTab),
+      "erase" => Some(KeyCode::KeyBackspace),
+      "confirm" => Some(KeyCode::KeyEnter),
+      "pause" => Some(KeyCode::KeyP),
+      "preview_zoom" => Some(KeyCode::KeyZ),
+      "sample_heatmap" => Some(KeyCode::KeyH),
+      "denoise" => Some(KeyCode::KeyF3),
+      "debug_mode" => Some(KeyCode::KeyF4),
+      "inspect" => Some(KeyCode::KeyI),
+      "edit_object" => Some(KeyCode::KeyE),
+      "save_scene" => Some(KeyCode::KeyF5),
+      _ => None,
+    }
+  }
+}
@@ -2,9 +2,21 @@ use crate::render;
 
 pub use self::config::default;
 pub use self::keyboard::KeyCode;
+pub use self::gamepad::GamepadAxis;
 pub use self::app::App;
+pub use self::settings::Settings;
+pub use self::screenshot_form::ScreenshotForm;
+pub use self::preview::Preview;
+pub use self::object_editor::ObjectEditor;
 
 mod app;
 mod config;
 mod keyboard;
+mod gamepad;
+mod settings;
+mod screenshot_form;
+mod preview;
+mod object_editor;
 
+#[cfg(test)]
+mod tests;
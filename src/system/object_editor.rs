@@ -0,0 +1,121 @@
+use crate::math::Vector3;
+
+use super::render::{MaterialKind, ObjectDesc, MATERIAL_KINDS};
+
+const COLOR_STEP: f32 = 0.05;
+const MOVE_STEP: f32 = 0.1;
+const SCALE_STEP: f32 = 1.05;
+// the material switched to the subsurface one gets it unless it has its own
+const DEFAULT_SCATTERING_DISTANCE: f32 = 0.1;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Field
+{
+  Kind,
+  Red,
+  Green,
+  Blue,
+  Reflectivity,
+  Transparency,
+  Scattering,
+  MoveX,
+  MoveY,
+  MoveZ,
+  Scale,
+}
+
+const FIELDS: [Field; 11] = [
+  Field::Kind,
+  Field::Red,
+  Field::Green,
+  Field::Blue,
+  Field::Reflectivity,
+  Field::Transparency,
+  Field::Scattering,
+  Field::MoveX,
+  Field::MoveY,
+  Field::MoveZ,
+  Field::Scale,
+];
+
+// editing of the picked scene object, the original is kept to revert the changes
+#[derive(Debug, Clone)]
+pub struct ObjectEditor {
+  pub index: usize,
+  pub original: ObjectDesc,
+  pub field: Field,
+}
+
+impl ObjectEditor {
+  pub fn new(index: usize, original: ObjectDesc) -> ObjectEditor {
+    ObjectEditor {
+      index,
+      original,
+      field: Field::Kind,
+    }
+  }
+
+  pub fn next_field(&mut self) {
+    let index = FIELDS.iter().position(|&field| field == self.field).unwrap();
+    self.field = FIELDS[(index + 1) % FIELDS.len()];
+  }
+
+  pub fn prev_field(&mut self) {
+    let index = FIELDS.iter().position(|&field| field == self.field).unwrap();
+    self.field = FIELDS[(index + FIELDS.len() - 1) % FIELDS.len()];
+  }
+
+  // changes the selected value of the object one step up or down, the material values stay in [0, 1]
+  pub fn change(&self, object: &mut ObjectDesc, is_increase: bool) {
+    let sign = if is_increase { 1.0 } else { -1.0 };
+    let material = &mut object.material;
+    let step_value = |value: &mut f32| *value = (*value + sign * COLOR_STEP).clamp(0.0, 1.0);
+
+    match self.field {
+      Field::Kind => {
+        let index = MATERIAL_KINDS.iter().position(|kind| *kind == material.kind).unwrap();
+        let count = MATERIAL_KINDS.len();
+        let index = if is_increase { (index + 1) % count } else { (index + count - 1) % count };
+        material.kind = MATERIAL_KINDS[index].clone();
+
+        if material.kind == MaterialKind::Subsurface && material.scattering_distance <= 0.0 {
+          material.scattering_distance = DEFAULT_SCATTERING_DISTANCE;
+        }
+      }
+      Field::Red => step_value(&mut material.color.r),
+      Field::Green => step_value(&mut material.color.g),
+      Field::Blue => step_value(&mut material.color.b),
+      Field::Reflectivity => step_value(&mut material.reflectivity),
+      Field::Transparency => step_value(&mut material.transparency),
+      Field::Scattering => material.scattering_distance *= if is_increase { SCALE_STEP } else { 1.0 / SCALE_STEP },
+      Field::MoveX => object.translate(&Vector3::new(sign * MOVE_STEP, 0.0, 0.0)),
+      Field::MoveY => object.translate(&Vector3::new(0.0, sign * MOVE_STEP, 0.0)),
+      Field::MoveZ => object.translate(&Vector3::new(0.0, 0.0, sign * MOVE_STEP)),
+      Field::Scale => object.scale(if is_increase { SCALE_STEP } else { 1.0 / SCALE_STEP }),
+    }
+  }
+
+  pub fn get_lines(&self, object: &ObjectDesc) -> Vec<String> {
+    let material = &object.material;
+    let center = object.get_center();
+
+    FIELDS.iter().map(|&field| {
+      let text = match field {
+        Field::Kind => format!("Material : {}", material.kind.name()),
+        Field::Red => format!("Red : {:.2}", material.color.r),
+        Field::Green => format!("Green : {:.2}", material.color.g),
+        Field::Blue => format!("Blue : {:.2}", material.color.b),
+        Field::Reflectivity => format!("Reflectivity : {:.2}", material.reflectivity),
+        Field::Transparency => format!("Transparency : {:.2}", material.transparency),
+        Field::Scattering => format!("Scattering : {:.3}", material.scattering_distance),
+        Field::MoveX => format!("Position X : {:.2}", center.x),
+        Field::MoveY => format!("Position Y : {:.2}", center.y),
+        Field::MoveZ => format!("Position Z : {:.2}", center.z),
+        Field::Scale => String::from("Scale"),
+      };
+
+      let marker = if field == self.field { ">" } else { " " };
+      format!("{} {}", marker, text)
+    }).collect()
+  }
+}
@@ -0,0 +1,91 @@
+use super::render::Render;
+
+const EMPTY_COLORS: [[u8; 3]; 2] = [[32, 32, 32], [48, 48, 48]];
+const EMPTY_CELL_SIZE: u32 = 16;
+const HIGHLIGHT_COLOR: [u8; 3] = [255, 64, 64];
+
+// Preview of the render image in the window, either fitted to the window or in 1:1 scale with panning.
+// Coordinates of the view are top-down while the render image rows go bottom-up.
+#[derive(Debug, Default, Clone)]
+pub struct Preview {
+  pub is_zoomed: bool,
+  pub pan_x: i32,
+  pub pan_y: i32,
+  pub is_heatmap: bool,
+}
+
+impl Preview {
+  pub fn toggle_zoom(&mut self, render: &Render, view_width: u32, view_height: u32) {
+    self.is_zoomed = !self.is_zoomed;
+
+    if self.is_zoomed {
+      // start with the currently rendered row in the center of the view
+      let (cur_x, cur_y) = render.get_cursor();
+      self.pan_x = cur_x as i32 - view_width as i32 / 2;
+      self.pan_y = (render.image_height as i32 - 1 - cur_y as i32) - view_height as i32 / 2;
+      self.clamp_pan(render, view_width, view_height);
+    }
+  }
+
+  // shift is given in fractions of the view size
+  pub fn pan(&mut self, render: &Render, shift_x: f32, shift_y: f32, view_width: u32, view_height: u32) {
+    if self.is_zoomed {
+      self.pan_x += (shift_x * view_width as f32) as i32;
+      self.pan_y += (shift_y * view_height as f32) as i32;
+      self.clamp_pan(render, view_width, view_height);
+    }
+  }
+
+  fn clamp_pan(&mut self, render: &Render, view_width: u32, view_height: u32) {
+    self.pan_x = i32::max(0, i32::min(self.pan_x, render.image_width as i32 - view_width as i32));
+    self.pan_y = i32::max(0, i32::min(self.pan_y, render.image_height as i32 - view_height as i32));
+  }
+
+  fn get_scale(&self, render: &Render, view_width: u32, view_height: u32) -> f32 {
+    if self.is_zoomed {
+      1.0
+    } else {
+      f32::max(
+        render.image_width as f32 / view_width as f32,
+        render.image_height as f32 / view_height as f32,
+      )
+    }
+  }
+
+  pub fn get_pixel(&self, render: &Render, x: u32, y: u32, view_width: u32, view_height: u32) -> [u8; 3] {
+    let scale = self.get_scale(render, view_width, view_height);
+
+    let (image_x, image_y) = if self.is_zoomed {
+      (x as f32 + self.pan_x as f32, y as f32 + self.pan_y as f32)
+    } else {
+      let offset_x = (view_width as f32 - render.image_width as f32 / scale) / 2.0;
+      let offset_y = (view_height as f32 - render.image_height as f32 / scale) / 2.0;
+      ((x as f32 - offset_x) * scale, (y as f32 - offset_y) * scale)
+    };
+
+    if image_x < 0.0 || image_y < 0.0 || image_x >= render.image_width as f32 || image_y >= render.image_height as f32 {
+      return [0, 0, 0];
+    }
+
+    let image_x = image_x as u32;
+    let image_y = render.image_height - 1 - image_y as u32;
+
+    if !render.is_pixel_rendered(image_x, image_y) {
+      let (_, cur_y) = render.get_cursor();
+
+      // the row being rendered is highlighted, at least one view row is marked even for downscaled image
+      if (image_y as f32 - cur_y as f32).abs() < f32::max(scale, 1.0) {
+        return HIGHLIGHT_COLOR;
+      }
+
+      let cell = (x / EMPTY_CELL_SIZE + y / EMPTY_CELL_SIZE) % 2;
+      return EMPTY_COLORS[cell as usize];
+    }
+
+    if self.is_heatmap {
+      render.get_sample_heatmap(image_x, image_y).rgb()
+    } else {
+      render.get_pixel(image_x, image_y).rgb()
+    }
+  }
+}
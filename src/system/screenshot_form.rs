@@ -0,0 +1,207 @@
+use anyhow::{Error, Result};
+
+use super::render::{MAX_IMAGE_SIZE, MAX_SAMPLES};
+
+const MAX_REFLECTIONS: u32 = 100;
+const FORMATS: [&str; 2] = ["bmp", "tga"];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Field
+{
+  Width,
+  Height,
+  MatchAspect,
+  Samples,
+  Reflections,
+  Format,
+  FileName,
+}
+
+const FIELDS: [Field; 7] = [
+  Field::Width,
+  Field::Height,
+  Field::MatchAspect,
+  Field::Samples,
+  Field::Reflections,
+  Field::Format,
+  Field::FileName,
+];
+
+#[derive(Debug, PartialEq)]
+pub struct ScreenshotParams {
+  pub width: u32,
+  pub height: u32,
+  pub samples: i32,
+  pub reflections: u32,
+  pub format: &'static str,
+  // empty name means auto generated one
+  pub file_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenshotForm {
+  pub width: String,
+  pub height: String,
+  pub match_aspect: bool,
+  pub samples: String,
+  pub reflections: String,
+  pub format_index: usize,
+  pub file_name: String,
+  pub field: Field,
+  pub error: Option<String>,
+}
+
+impl ScreenshotForm {
+  pub fn new(width: u32, height: u32, samples: i32, reflections: u32) -> ScreenshotForm {
+    ScreenshotForm {
+      width: width.to_string(),
+      height: height.to_string(),
+      match_aspect: false,
+      samples: samples.to_string(),
+      reflections: reflections.to_string(),
+      format_index: 0,
+      file_name: String::new(),
+      field: Field::Width,
+      error: None,
+    }
+  }
+
+  pub fn next_field(&mut self) {
+    let index = FIELDS.iter().position(|&field| field == self.field).unwrap();
+    self.field = FIELDS[(index + 1) % FIELDS.len()];
+
+    if self.field == Field::Height && self.match_aspect {
+      self.next_field();
+    }
+  }
+
+  // toggles the switch fields, ignored for the text ones
+  pub fn toggle(&mut self) {
+    match self.field {
+      Field::MatchAspect => self.match_aspect = !self.match_aspect,
+      Field::Format => self.format_index = (self.format_index + 1) % FORMATS.len(),
+      _ => {}
+    }
+  }
+
+  pub fn input_text(&mut self, text: &str) {
+    let is_number_field = self.field != Field::FileName;
+
+    if let Some(value) = self.field_value_mut() {
+      value.extend(text.chars().filter(|&c| {
+        if is_number_field {
+          c.is_ascii_digit()
+        } else {
+          !c.is_control() && c != '/' && c != '\\'
+        }
+      }));
+    }
+
+    self.error = None;
+  }
+
+  pub fn erase(&mut self) {
+    if let Some(value) = self.field_value_mut() {
+      value.pop();
+    }
+
+    self.error = None;
+  }
+
+  pub fn is_text_field(&self) -> bool {
+    match self.field {
+      Field::Width | Field::Height | Field::Samples | Field::Reflections | Field::FileName => true,
+      Field::MatchAspect | Field::Format => false,
+    }
+  }
+
+  fn field_value_mut(&mut self) -> Option<&mut String> {
+    match self.field {
+      Field::Width => Some(&mut self.width),
+      Field::Height => Some(&mut self.height),
+      Field::Samples => Some(&mut self.samples),
+      Field::Reflections => Some(&mut self.reflections),
+      Field::FileName => Some(&mut self.file_name),
+      Field::MatchAspect | Field::Format => None,
+    }
+  }
+
+  pub fn get_height(&self, window_width: u32, window_height: u32) -> Option<u32> {
+    if self.match_aspect {
+      let width = self.width.parse::<u32>().ok()?;
+      Some(u32::max(1, (width as u64 * window_height as u64 / window_width as u64) as u32))
+    } else {
+      self.height.parse::<u32>().ok()
+    }
+  }
+
+  pub fn validate(&self, window_width: u32, window_height: u32) -> Result<ScreenshotParams> {
+    let width = self.width.parse::<u32>()
+      .map_err(|_| Error::msg("Width should be a number"))?;
+    let height = self.get_height(window_width, window_height)
+      .ok_or_else(|| Error::msg("Height should be a number"))?;
+    let samples = self.samples.parse::<i32>()
+      .map_err(|_| Error::msg("Samples should be a number"))?;
+    let reflections = self.reflections.parse::<u32>()
+      .map_err(|_| Error::msg("Reflections should be a number"))?;
+
+    if width == 0 || height == 0 || width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE {
+      return Err(Error::msg(format!("Resolution should be in range 1..{}", MAX_IMAGE_SIZE)));
+    }
+
+    if !(1..=MAX_SAMPLES).contains(&samples) {
+      return Err(Error::msg(format!("Samples should be in range 1..{}", MAX_SAMPLES)));
+    }
+
+    if !(1..=MAX_REFLECTIONS).contains(&reflections) {
+      return Err(Error::msg(format!("Reflections should be in range 1..{}", MAX_REFLECTIONS)));
+    }
+
+    let file_name = self.file_name.trim();
+
+    if file_name.starts_with('.') {
+      return Err(Error::msg("Invalid file name"));
+    }
+
+    Ok(ScreenshotParams {
+      width,
+      height,
+      samples,
+      reflections,
+      format: FORMATS[self.format_index],
+      file_name: file_name.to_owned(),
+    })
+  }
+
+  pub fn get_lines(&self, window_width: u32, window_height: u32) -> Vec<String> {
+    let height = if self.match_aspect {
+      self.get_height(window_width, window_height).map_or(String::from("?"), |h| h.to_string())
+    } else {
+      self.height.clone()
+    };
+
+    let file_name = if self.file_name.is_empty() { "<auto>" } else { &self.file_name };
+
+    let lines = [
+      (Field::Width, format!("Width : {}", self.width)),
+      (Field::Height, format!("Height : {}", height)),
+      (Field::MatchAspect, format!("Match window aspect : {}", if self.match_aspect { "yes" } else { "no" })),
+      (Field::Samples, format!("SSAA : {0}x{0}", self.samples)),
+      (Field::Reflections, format!("Reflections : {}", self.reflections)),
+      (Field::Format, format!("Format : {}", FORMATS[self.format_index])),
+      (Field::FileName, format!("File name : {}", file_name)),
+    ];
+
+    lines.iter()
+      .map(|(field, line)| {
+        if *field == self.field && self.is_text_field() {
+          format!("> {}_", line)
+        } else if *field == self.field {
+          format!("> {}", line)
+        } else {
+          format!("   {}", line)
+        }
+      })
+      .collect()
+  }
+}
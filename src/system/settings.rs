@@ -0,0 +1,349 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Error, Result};
+
+use super::default as Config;
+use super::KeyCode;
+use super::render::camera::{DEFAULT_MAX_SHIFT_SPEED, DEFAULT_MAX_TURN_SPEED};
+use super::render::{AovKind, DebugMode, FilterKind, SamplerKind, MAX_FILTER_RADIUS, MAX_SAMPLES};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolution {
+  pub w: u32,
+  pub h: u32,
+  pub tip: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsRate {
+  pub rate: i32,
+  pub tip: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBinding {
+  pub key: KeyCode,
+  pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+  pub static_samples: i32,
+  pub motion_min_samples: i32,
+  pub motion_max_samples: i32,
+  pub static_reflections: u32,
+  pub motion_reflections: u32,
+  pub min_motion_frame_time: f32,
+  pub max_motion_frame_time: f32,
+  pub min_chunk_render_time: u32,
+  pub max_chunk_render_time: u32,
+  pub seed: u64,
+  pub sampler: SamplerKind,
+  pub filter: FilterKind,
+  pub filter_radius: f32,
+  pub denoise: bool,
+  pub debug_mode: Option<DebugMode>,
+  pub spectral: bool,
+  pub adaptive_threshold: f32,
+  pub adaptive_min_samples: u32,
+  pub adaptive_max_samples: u32,
+
+  pub screenshot_reflections: u32,
+  pub checkpoint_interval: u32,
+  pub screenshot_noise_level: f32,
+  pub screenshot_max_samples: u32,
+  pub screenshot_denoise: bool,
+  pub screenshot_aovs: Vec<AovKind>,
+  pub resolutions: Vec<Resolution>,
+  pub supersample_rates: Vec<SsRate>,
+
+  pub turn_speed: f32,
+  pub shift_speed: f32,
+  pub mouse_look_sensitivity: f32,
+  pub mouse_wheel_zoom_factor: f32,
+  pub gamepad_dead_zone: f32,
+
+  pub key_bindings: Vec<KeyBinding>,
+}
+
+impl Default for Settings {
+  fn default() -> Settings {
+    Settings {
+      static_samples: Config::STATIC_SAMPLES,
+      motion_min_samples: Config::MOTION_MIN_SAMPLES,
+      motion_max_samples: Config::MOTION_MAX_SAMPLES,
+      static_reflections: Config::STATIC_REFLECTIONS,
+      motion_reflections: Config::MOTION_REFLECTIONS,
+      min_motion_frame_time: Config::MIN_MOTION_FRAME_TIME,
+      max_motion_frame_time: Config::MAX_MOTION_FRAME_TIME,
+      min_chunk_render_time: Config::MIN_CHUNK_RENDER_TIME,
+      max_chunk_render_time: Config::MAX_CHUNK_RENDER_TIME,
+      seed: Config::SEED,
+      sampler: SamplerKind::from_name(Config::SAMPLER).unwrap(),
+      filter: FilterKind::from_name(Config::FILTER).unwrap(),
+      filter_radius: Config::FILTER_RADIUS,
+      denoise: Config::DENOISE,
+      debug_mode: parse_debug_mode(Config::DEBUG_MODE).unwrap(),
+      spectral: Config::SPECTRAL,
+      adaptive_threshold: Config::ADAPTIVE_THRESHOLD,
+      adaptive_min_samples: Config::ADAPTIVE_MIN_SAMPLES,
+      adaptive_max_samples: Config::ADAPTIVE_MAX_SAMPLES,
+      screenshot_reflections: Config::SCREENSHOT_REFLECTIONS,
+      checkpoint_interval: Config::CHECKPOINT_INTERVAL,
+      screenshot_noise_level: Config::SCREENSHOT_NOISE_LEVEL,
+      screenshot_max_samples: Config::SCREENSHOT_MAX_SAMPLES,
+      screenshot_denoise: Config::SCREENSHOT_DENOISE,
+      screenshot_aovs: parse_aovs(Config::SCREENSHOT_AOVS).unwrap(),
+      resolutions: Config::RESOLUTIONS.iter()
+        .map(|&(w, h, tip)| Resolution { w, h, tip: tip.to_owned() })
+        .collect(),
+      supersample_rates: Config::SUPERSAMPLE_RATES.iter()
+        .map(|&(rate, tip)| SsRate { rate, tip: tip.to_owned() })
+        .collect(),
+      turn_speed: DEFAULT_MAX_TURN_SPEED,
+      shift_speed: DEFAULT_MAX_SHIFT_SPEED,
+      mouse_look_sensitivity: Config::MOUSE_LOOK_SENSITIVITY,
+      mouse_wheel_zoom_factor: Config::MOUSE_WHEEL_ZOOM_FACTOR,
+      gamepad_dead_zone: Config::GAMEPAD_DEAD_ZONE,
+      key_bindings: Config::KEY_BINDINGS.iter()
+        .map(|&(action, names)| KeyBinding {
+          key: KeyCode::from_action(action).unwrap(),
+          names: split_list(names),
+        })
+        .collect(),
+    }
+  }
+}
+
+impl Settings {
+  pub fn load_from_file(path: &Path) -> Result<Settings> {
+    let text = fs::read_to_string(path)
+      .with_context(|| format!("Failed to read settings file {}", path.display()))?;
+
+    Settings::parse(&text)
+      .with_context(|| format!("Failed to parse settings file {}", path.display()))
+  }
+
+  // INI-like format: "[section]" headers, "key = value" pairs, '#' and ';' start a comment,
+  // lists of resolutions and supersample rates are defined by repeating the key
+  pub fn parse(text: &str) -> Result<Settings> {
+    let mut settings = Settings::default();
+    let mut section = String::new();
+    let mut resolutions = Vec::new();
+    let mut supersample_rates = Vec::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+      let line = line.split(['#', ';']).next().unwrap().trim();
+
+      if line.is_empty() {
+        continue;
+      }
+
+      let result = if line.starts_with('[') && line.ends_with(']') {
+        section = line[1..line.len() - 1].trim().to_owned();
+        Ok(())
+      } else if let Some((key, value)) = line.split_once('=') {
+        let (key, value) = (key.trim(), value.trim());
+
+        match (section.as_str(), key) {
+          ("screenshot", "resolution") => parse_resolution(value).map(|res| resolutions.push(res)),
+          ("screenshot", "supersample_rate") => parse_ss_rate(value).map(|ss| supersample_rates.push(ss)),
+          _ => settings.set_value(&section, key, value),
+        }
+      } else {
+        Err(Error::msg("Expected \"key = value\" or \"[section]\""))
+      };
+
+      result.with_context(|| format!("Line {}: {}", line_index + 1, line))?;
+    }
+
+    if !resolutions.is_empty() {
+      settings.resolutions = resolutions;
+    }
+
+    if !supersample_rates.is_empty() {
+      settings.supersample_rates = supersample_rates;
+    }
+
+    settings.validate()?;
+
+    Ok(settings)
+  }
+
+  fn set_value(&mut self, section: &str, key: &str, value: &str) -> Result<()> {
+    match (section, key) {
+      ("quality", "static_samples") => self.static_samples = parse_value(value)?,
+      ("quality", "motion_min_samples") => self.motion_min_samples = parse_value(value)?,
+      ("quality", "motion_max_samples") => self.motion_max_samples = parse_value(value)?,
+      ("quality", "static_reflections") => self.static_reflections = parse_value(value)?,
+      ("quality", "motion_reflections") => self.motion_reflections = parse_value(value)?,
+      ("quality", "min_motion_frame_time") => self.min_motion_frame_time = parse_value(value)?,
+      ("quality", "max_motion_frame_time") => self.max_motion_frame_time = parse_value(value)?,
+      ("quality", "min_chunk_render_time") => self.min_chunk_render_time = parse_value(value)?,
+      ("quality", "max_chunk_render_time") => self.max_chunk_render_time = parse_value(value)?,
+      ("quality", "seed") => self.seed = parse_value(value)?,
+      ("quality", "adaptive_threshold") => self.adaptive_threshold = parse_value(value)?,
+      ("quality", "adaptive_min_samples") => self.adaptive_min_samples = parse_value(value)?,
+      ("quality", "adaptive_max_samples") => self.adaptive_max_samples = parse_value(value)?,
+      ("quality", "filter") => {
+        self.filter = FilterKind::from_name(value).ok_or_else(|| Error::msg(format!("Unknown filter \"{}\"", value)))?;
+      }
+      ("quality", "filter_radius") => self.filter_radius = parse_value(value)?,
+      ("quality", "denoise") => self.denoise = parse_value(value)?,
+      ("quality", "debug_mode") => self.debug_mode = parse_debug_mode(value)?,
+      ("quality", "spectral") => self.spectral = parse_value(value)?,
+      ("quality", "sampler") => {
+        self.sampler = SamplerKind::from_name(value).ok_or_else(|| Error::msg(format!("Unknown sampler \"{}\"", value)))?;
+      }
+      ("screenshot", "reflections") => self.screenshot_reflections = parse_value(value)?,
+      ("screenshot", "checkpoint_interval") => self.checkpoint_interval = parse_value(value)?,
+      ("screenshot", "noise_level") => self.screenshot_noise_level = parse_value(value)?,
+      ("screenshot", "max_samples") => self.screenshot_max_samples = parse_value(value)?,
+      ("screenshot", "denoise") => self.screenshot_denoise = parse_value(value)?,
+      ("screenshot", "aovs") => self.screenshot_aovs = parse_aovs(value)?,
+      ("control", "turn_speed") => self.turn_speed = parse_value(value)?,
+      ("control", "shift_speed") => self.shift_speed = parse_value(value)?,
+      ("control", "mouse_look_sensitivity") => self.mouse_look_sensitivity = parse_value(value)?,
+      ("control", "mouse_wheel_zoom_factor") => self.mouse_wheel_zoom_factor = parse_value(value)?,
+      ("control", "gamepad_dead_zone") => self.gamepad_dead_zone = parse_value(value)?,
+      ("keys", action) => {
+        let key = KeyCode::from_action(action).ok_or_else(|| Error::msg("Unknown key action"))?;
+        let binding = self.key_bindings.iter_mut().find(|binding| binding.key == key).unwrap();
+        binding.names = split_list(value);
+      }
+      _ => return Err(Error::msg("Unknown setting")),
+    }
+
+    Ok(())
+  }
+
+  // names of the keys bound to the action, the alternative keys are separated by slashes
+  pub fn get_key_names(&self, key: KeyCode) -> String {
+    self.key_bindings.iter()
+      .find(|binding| binding.key == key)
+      .map_or_else(String::new, |binding| binding.names.join(" / "))
+  }
+
+  fn validate(&self) -> Result<()> {
+    if self.static_samples == 0 || self.motion_min_samples == 0 || self.motion_max_samples == 0 {
+      return Err(Error::msg("Samples can't be zero"));
+    }
+
+    let samples = [self.static_samples, self.motion_min_samples, self.motion_max_samples];
+
+    if samples.iter().any(|samples| samples.unsigned_abs() > MAX_SAMPLES as u32) {
+      return Err(Error::msg(format!("Samples should be in range -{}..{}", MAX_SAMPLES, MAX_SAMPLES)));
+    }
+
+    if self.motion_max_samples > self.motion_min_samples {
+      return Err(Error::msg("Motion max samples should not exceed motion min samples"));
+    }
+
+    if self.static_reflections == 0 || self.motion_reflections == 0 || self.screenshot_reflections == 0 {
+      return Err(Error::msg("Reflections can't be zero"));
+    }
+
+    if self.min_motion_frame_time > self.max_motion_frame_time {
+      return Err(Error::msg("Min motion frame time should not exceed max motion frame time"));
+    }
+
+    if self.min_chunk_render_time > self.max_chunk_render_time {
+      return Err(Error::msg("Min chunk render time should not exceed max chunk render time"));
+    }
+
+    if self.adaptive_threshold < 0.0 || self.screenshot_noise_level < 0.0 {
+      return Err(Error::msg("Adaptive sampling threshold can't be negative"));
+    }
+
+    if self.adaptive_min_samples < 2 || self.adaptive_min_samples > self.adaptive_max_samples {
+      return Err(Error::msg("Adaptive min samples should be in range 2..adaptive max samples"));
+    }
+
+    if self.screenshot_max_samples == 0 {
+      return Err(Error::msg("Screenshot max samples can't be zero"));
+    }
+
+    if !(self.filter_radius > 0.0 && self.filter_radius <= MAX_FILTER_RADIUS) {
+      return Err(Error::msg(format!("Filter radius should be in range (0, {}]", MAX_FILTER_RADIUS)));
+    }
+
+    if self.resolutions.len() > 9 || self.supersample_rates.len() > 9 {
+      return Err(Error::msg("No more than 9 resolutions and supersample rates are supported"));
+    }
+
+    if self.turn_speed <= 0.0 || self.shift_speed <= 0.0 {
+      return Err(Error::msg("Movement speeds should be positive"));
+    }
+
+    if !self.mouse_look_sensitivity.is_finite() {
+      return Err(Error::msg("Mouse look sensitivity should be finite"));
+    }
+
+    if !(self.mouse_wheel_zoom_factor > 0.0 && self.mouse_wheel_zoom_factor.is_finite()) {
+      return Err(Error::msg("Mouse wheel zoom factor should be positive"));
+    }
+
+    if self.gamepad_dead_zone < 0.0 || self.gamepad_dead_zone >= 1.0 {
+      return Err(Error::msg("Gamepad dead zone should be in range [0, 1)"));
+    }
+
+    Ok(())
+  }
+}
+
+fn parse_value<T: FromStr>(value: &str) -> Result<T> {
+  value.parse::<T>().map_err(|_| Error::msg(format!("Invalid value \"{}\"", value)))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+  value.split(',')
+    .map(|name| name.trim().to_owned())
+    .filter(|name| !name.is_empty())
+    .collect()
+}
+
+// empty value turns the debug shading off
+fn parse_debug_mode(value: &str) -> Result<Option<DebugMode>> {
+  if value.is_empty() {
+    return Ok(None);
+  }
+
+  DebugMode::from_name(value)
+    .map(Some)
+    .ok_or_else(|| Error::msg(format!("Unknown debug mode \"{}\"", value)))
+}
+
+// "depth, normal, object_id"
+fn parse_aovs(value: &str) -> Result<Vec<AovKind>> {
+  split_list(value).iter()
+    .map(|name| AovKind::from_name(name).ok_or_else(|| Error::msg(format!("Unknown AOV \"{}\"", name))))
+    .collect()
+}
+
+// "1920x1080 (Full HD)"
+fn parse_resolution(value: &str) -> Result<Resolution> {
+  let (size, tip) = value.split_once(' ').unwrap_or((value, ""));
+  let (w, h) = size.split_once('x').ok_or_else(|| Error::msg("Expected resolution as WIDTHxHEIGHT"))?;
+  let w: u32 = parse_value(w)?;
+  let h: u32 = parse_value(h)?;
+
+  if w == 0 || h == 0 {
+    return Err(Error::msg("Resolution can't be zero"));
+  }
+
+  Ok(Resolution { w, h, tip: tip.trim().to_owned() })
+}
+
+// "256 (slow but smooth)"
+fn parse_ss_rate(value: &str) -> Result<SsRate> {
+  let (rate, tip) = value.split_once(' ').unwrap_or((value, ""));
+  let rate: i32 = parse_value(rate)?;
+
+  if !(1..=MAX_SAMPLES).contains(&rate) {
+    return Err(Error::msg(format!("Supersample rate should be in range 1..{}", MAX_SAMPLES)));
+  }
+
+  Ok(SsRate { rate, tip: tip.trim().to_owned() })
+}
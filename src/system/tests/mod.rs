@@ -0,0 +1,6 @@
+use super::*;
+
+mod settings;
+mod screenshot_form;
+mod preview;
+mod object_editor;
@@ -0,0 +1,73 @@
+use super::render::{MaterialKind, SceneDesc};
+use super::ObjectEditor;
+
+#[test]
+fn change_material() {
+  let object = SceneDesc::default().objects[0].clone();
+  let editor = ObjectEditor::new(0, object.clone());
+  let mut edited = object.clone();
+
+  editor.change(&mut edited, true);
+  assert_eq!(edited.material.kind, MaterialKind::Dielectric);
+  editor.change(&mut edited, false);
+  editor.change(&mut edited, false);
+  assert_eq!(edited.material.kind, MaterialKind::None);
+
+  let mut editor = editor;
+  editor.next_field();
+  editor.change(&mut edited, true);
+  assert_eq!(edited.material.color.r, 1.0);
+  editor.change(&mut edited, false);
+  assert!((edited.material.color.r - 0.95).abs() < 1e-6);
+  assert_eq!(editor.original, object);
+}
+
+#[test]
+fn select_fields() {
+  let object = SceneDesc::default().objects[0].clone();
+  let mut editor = ObjectEditor::new(0, object.clone());
+  let lines = editor.get_lines(&object);
+  assert!(lines[0].starts_with('>'));
+  assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+
+  editor.prev_field();
+  let lines = editor.get_lines(&object);
+  assert!(lines.last().unwrap().starts_with('>'));
+  editor.next_field();
+  assert!(editor.get_lines(&object)[0].starts_with('>'));
+}
+
+#[test]
+fn move_and_scale() {
+  let object = SceneDesc::default().objects[0].clone();
+  let mut editor = ObjectEditor::new(0, object.clone());
+  let mut edited = object.clone();
+
+  while !editor.get_lines(&edited).iter().any(|line| line.starts_with("> Position Y")) {
+    editor.next_field();
+  }
+
+  editor.change(&mut edited, true);
+  assert!((edited.get_center().y - object.get_center().y - 0.1).abs() < 1e-5);
+}
+
+#[test]
+fn subsurface_scattering() {
+  let object = SceneDesc::default().objects[0].clone();
+  let mut editor = ObjectEditor::new(0, object.clone());
+  let mut edited = object;
+
+  // metal, dielectric and subsurface, the new subsurface material scatters at once
+  editor.change(&mut edited, true);
+  editor.change(&mut edited, true);
+  assert_eq!(edited.material.kind, MaterialKind::Subsurface);
+  assert!(edited.material.scattering_distance > 0.0);
+
+  while !editor.get_lines(&edited).iter().any(|line| line.starts_with("> Scattering")) {
+    editor.next_field();
+  }
+
+  let distance = edited.material.scattering_distance;
+  editor.change(&mut edited, true);
+  assert!(edited.material.scattering_distance > distance);
+}
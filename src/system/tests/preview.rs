@@ -0,0 +1,39 @@
+use super::Preview;
+use super::render::Render;
+
+#[test]
+fn fit_to_view() {
+  let mut render = Render::new();
+  render.resize_image(8, 4);
+  render.begin_render(1, 1, false);
+  let preview = Preview::default();
+
+  // image is scaled down by 2 and centered vertically in 4x4 view
+  assert_eq!(preview.get_pixel(&render, 0, 0, 4, 4), [0, 0, 0]);
+  assert_eq!(preview.get_pixel(&render, 0, 3, 4, 4), [0, 0, 0]);
+
+  // nothing is rendered yet, the bottom row is the one in progress
+  assert_eq!(preview.get_pixel(&render, 0, 2, 4, 4), [255, 64, 64]);
+  assert_eq!(preview.get_pixel(&render, 0, 1, 4, 4), [32, 32, 32]);
+}
+
+#[test]
+fn zoom_and_pan() {
+  let mut render = Render::new();
+  render.resize_image(100, 100);
+  render.begin_render(1, 1, false);
+  let mut preview = Preview::default();
+
+  preview.toggle_zoom(&render, 10, 10);
+  assert!(preview.is_zoomed);
+  assert_eq!((preview.pan_x, preview.pan_y), (0, 90));
+
+  preview.pan(&render, 0.5, 0.5, 10, 10);
+  assert_eq!((preview.pan_x, preview.pan_y), (5, 90));
+
+  preview.pan(&render, -1.0, -1.0, 10, 10);
+  assert_eq!((preview.pan_x, preview.pan_y), (0, 80));
+
+  preview.toggle_zoom(&render, 10, 10);
+  assert!(!preview.is_zoomed);
+}
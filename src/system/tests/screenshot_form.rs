@@ -0,0 +1,52 @@
+use super::ScreenshotForm;
+
+#[test]
+fn validate() {
+  let form = ScreenshotForm::new(1920, 1080, 4, 20);
+  let params = form.validate(640, 480).unwrap();
+  assert_eq!((params.width, params.height), (1920, 1080));
+  assert_eq!(params.samples, 4);
+  assert_eq!(params.reflections, 20);
+  assert_eq!(params.format, "bmp");
+  assert_eq!(params.file_name, "");
+}
+
+#[test]
+fn match_window_aspect() {
+  let mut form = ScreenshotForm::new(1000, 1000, 1, 20);
+  form.next_field();
+  form.next_field();
+  form.toggle();
+  let params = form.validate(400, 300).unwrap();
+  assert_eq!((params.width, params.height), (1000, 750));
+}
+
+#[test]
+fn input_text() {
+  let mut form = ScreenshotForm::new(1, 1, 1, 20);
+  form.erase();
+  form.input_text("3x2a0");
+  assert_eq!(form.width, "320");
+
+  for _ in 0..6 {
+    form.next_field();
+  }
+
+  form.input_text("my/shot");
+  assert_eq!(form.file_name, "myshot");
+  assert_eq!(form.validate(640, 480).unwrap().file_name, "myshot");
+}
+
+#[test]
+fn validation_errors() {
+  assert!(ScreenshotForm::new(0, 100, 1, 20).validate(640, 480).is_err());
+  assert!(ScreenshotForm::new(100, 100000, 1, 20).validate(640, 480).is_err());
+  assert!(ScreenshotForm::new(100, 100, 0, 20).validate(640, 480).is_err());
+  assert!(ScreenshotForm::new(100, 100, 1, 0).validate(640, 480).is_err());
+
+  let mut form = ScreenshotForm::new(100, 100, 1, 20);
+  form.erase();
+  form.erase();
+  form.erase();
+  assert!(form.validate(640, 480).is_err());
+}
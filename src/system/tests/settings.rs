@@ -0,0 +1,88 @@
+use super::{KeyCode, Settings};
+use super::render::{AovKind, DebugMode, FilterKind, SamplerKind};
+
+#[test]
+fn parse_empty() {
+  let settings = Settings::parse("").unwrap();
+  assert_eq!(settings, Settings::default());
+}
+
+#[test]
+fn parse_values() {
+  let text = "
+    # comment
+    [quality]
+    static_samples = 2 ; trailing comment
+    static_reflections = 10
+    sampler = halton
+    filter = mitchell
+    filter_radius = 2
+    denoise = true
+    debug_mode = barycentric
+    spectral = true
+
+    [control]
+    shift_speed = 5.5
+
+    [screenshot]
+    reflections = 30
+    aovs = depth, object_id
+    resolution = 640x480 (VGA)
+    resolution = 3840x2160
+    supersample_rate = 3 (odd)
+  ";
+
+  let settings = Settings::parse(text).unwrap();
+  assert_eq!(settings.static_samples, 2);
+  assert_eq!(settings.static_reflections, 10);
+  assert_eq!(settings.sampler, SamplerKind::Halton);
+  assert_eq!(settings.filter, FilterKind::Mitchell);
+  assert_eq!(settings.filter_radius, 2.0);
+  assert!(settings.denoise);
+  assert_eq!(settings.debug_mode, Some(DebugMode::Barycentric));
+  assert!(settings.spectral);
+  assert_eq!(settings.shift_speed, 5.5);
+  assert_eq!(settings.screenshot_reflections, 30);
+  assert_eq!(settings.screenshot_aovs, vec![AovKind::Depth, AovKind::ObjectId]);
+  assert_eq!(settings.resolutions.len(), 2);
+  assert_eq!((settings.resolutions[0].w, settings.resolutions[0].h), (640, 480));
+  assert_eq!(settings.resolutions[0].tip, "(VGA)");
+  assert_eq!((settings.resolutions[1].w, settings.resolutions[1].h), (3840, 2160));
+  assert_eq!(settings.supersample_rates.len(), 1);
+  assert_eq!(settings.supersample_rates[0].rate, 3);
+}
+
+#[test]
+fn parse_key_bindings() {
+  let settings = Settings::parse("[keys]\nmove_forward = W, Up\n").unwrap();
+  let binding = settings.key_bindings.iter().find(|binding| binding.key == KeyCode::KeyW).unwrap();
+  assert_eq!(binding.names, vec!["W", "Up"]);
+  assert_eq!(settings.get_key_names(KeyCode::KeyW), "W / Up");
+  assert_eq!(settings.get_key_names(KeyCode::KeyF2), "F2");
+}
+
+#[test]
+fn parse_errors() {
+  assert!(Settings::parse("[quality]\nunknown = 1").is_err());
+  assert!(Settings::parse("[quality]\nstatic_samples = one").is_err());
+  assert!(Settings::parse("[quality]\nstatic_samples = 0").is_err());
+  assert!(Settings::parse("[quality]\nsampler = random").is_err());
+  assert!(Settings::parse("[quality]\nfilter_radius = 5").is_err());
+  assert!(Settings::parse("[quality]\ndebug_mode = wireframe").is_err());
+  assert!(Settings::parse("[keys]\nfly = F").is_err());
+  assert!(Settings::parse("[screenshot]\nresolution = 640*480").is_err());
+  assert!(Settings::parse("[screenshot]\naovs = depth, speed").is_err());
+  assert!(Settings::parse("[screenshot]\nsupersample_rate = 100000").is_err());
+  assert!(Settings::parse("[quality]\nstatic_samples = 100000").is_err());
+  assert!(Settings::parse("[control]\nmouse_wheel_zoom_factor = 0").is_err());
+  assert!(Settings::parse("[control]\nmouse_wheel_zoom_factor = -1.1").is_err());
+  assert!(Settings::parse("[control]\nmouse_wheel_zoom_factor = nan").is_err());
+  assert!(Settings::parse("[control]\nmouse_look_sensitivity = inf").is_err());
+  assert!(Settings::parse("static_samples").is_err());
+}
+
+#[test]
+fn parse_example_file() {
+  let settings = Settings::parse(include_str!("../../../assets/settings.ini")).unwrap();
+  assert_eq!(settings, Settings::default());
+}